@@ -0,0 +1,117 @@
+//! SVG badges for crates to show off how little they waste, based on the waste of their latest version.
+use crate::{changes::percent, latest_version, Report};
+use serde_derive::{Deserialize, Serialize};
+
+/// The name of the badge file, to be placed next to the crate page
+pub const BADGE_FILE_NAME: &str = "badge.svg";
+
+const LABEL: &str = "crate";
+
+/// Thresholds in percent of wasted bytes in the latest version of a crate, to decide how its badge looks like
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct BadgeThresholds {
+    /// Crates wasting at most this percentage are lean
+    pub lean: f64,
+    /// Crates wasting more than this percentage are shown in red, and in yellow otherwise
+    pub wasteful: f64,
+}
+
+impl BadgeThresholds {
+    /// Return the thresholds for crates `lean` and `wasteful` in percent, or an error if crates could be both.
+    pub fn new(lean: f64, wasteful: f64) -> Result<Self, String> {
+        if lean <= wasteful {
+            Ok(BadgeThresholds { lean, wasteful })
+        } else {
+            Err(format!(
+                "The threshold of lean crates of {}% must not be above the one of wasteful crates of {}%",
+                lean, wasteful
+            ))
+        }
+    }
+}
+
+impl Default for BadgeThresholds {
+    fn default() -> Self {
+        BadgeThresholds {
+            lean: 1.0,
+            wasteful: 25.0,
+        }
+    }
+}
+
+/// The state of a crate as shown on its badge
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Badge {
+    Lean,
+    Waste { percent: f64 },
+    Wasteful { percent: f64 },
+}
+
+impl Badge {
+    /// Return the badge of a crate whose latest version wastes `wasted_bytes` of `total_bytes`.
+    pub fn new(wasted_bytes: u64, total_bytes: u64, thresholds: &BadgeThresholds) -> Self {
+        let percent = percent(wasted_bytes, total_bytes);
+        if percent <= thresholds.lean {
+            Badge::Lean
+        } else if percent <= thresholds.wasteful {
+            Badge::Waste { percent }
+        } else {
+            Badge::Wasteful { percent }
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Badge::Lean => "lean".into(),
+            Badge::Waste { percent } | Badge::Wasteful { percent } => format!("{:.0}% waste", percent),
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Badge::Lean => "#4c1",
+            Badge::Waste { .. } => "#dfb317",
+            Badge::Wasteful { .. } => "#e05d44",
+        }
+    }
+
+    /// Return the badge as flat SVG image, in the style of the badges shown in most README files.
+    pub fn to_svg(&self) -> String {
+        // An approximation of the width of text in Verdana at 11px, good enough for short texts
+        let text_width = |text: &str| text.chars().count() as u32 * 7 + 10;
+        let message = self.message();
+        let (label_width, message_width) = (text_width(LABEL), text_width(&message));
+        let width = label_width + message_width;
+        format!(
+            concat!(
+                r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">"##,
+                r##"<title>{label}: {message}</title>"##,
+                r##"<rect width="{label_width}" height="20" fill="#555"/>"##,
+                r##"<rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>"##,
+                r##"<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">"##,
+                r##"<text x="{label_x}" y="14">{label}</text><text x="{message_x}" y="14">{message}</text>"##,
+                r##"</g></svg>"##
+            ),
+            width = width,
+            label = LABEL,
+            message = message,
+            label_width = label_width,
+            message_width = message_width,
+            color = self.color(),
+            label_x = label_width / 2,
+            message_x = label_width + message_width / 2,
+        )
+    }
+}
+
+impl Report {
+    /// Return the badge of a crate report based on the waste in its latest version, or `None` if this isn't
+    /// a crate report or it has no versions.
+    pub fn badge(&self, thresholds: &BadgeThresholds) -> Option<Badge> {
+        match self {
+            Report::Crate { info_by_version, .. } => latest_version(info_by_version)
+                .map(|(_, latest)| Badge::new(latest.waste.total_bytes, latest.all.total_bytes, thresholds)),
+            Report::Version { .. } | Report::CrateCollection { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Significant changes in waste between consecutive versions of a crate, to celebrate crates which became lean and
+//! to notice those which regressed.
+use crate::Report;
+use serde_derive::{Deserialize, Serialize};
+#[cfg(feature = "html")]
+use {
+    crate::html::parse_semver,
+    std::time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The amount of changes to keep, the most recent ones first
+pub const RECENT_CHANGES: usize = 100;
+
+/// The amount of percentage points by which the waste of a version has to differ from its predecessor to be
+/// considered a change
+pub const SIGNIFICANT_CHANGE_PERCENT: f64 = 5.0;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Deserialize, Serialize)]
+pub enum ChangeKind {
+    /// The version is lean, but its predecessor wasn't
+    BecameLean,
+    /// The version wastes significantly less than its predecessor
+    Improved,
+    /// The version wastes significantly more than its predecessor, or isn't lean anymore
+    Regressed,
+}
+
+/// A version of a crate whose waste differs significantly from the version before it.
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Change {
+    pub crate_name: String,
+    pub version: String,
+    pub previous_version: String,
+    /// The time the change was noticed at, in seconds since the unix epoch
+    pub time: u64,
+    pub kind: ChangeKind,
+    pub total_bytes: u64,
+    pub wasted_bytes: u64,
+    pub previous_total_bytes: u64,
+    pub previous_wasted_bytes: u64,
+}
+
+/// Recent changes, the most recent ones first, with at most [`RECENT_CHANGES`] entries.
+pub type Changes = Vec<Change>;
+
+/// Return the percentage of `wasted_bytes` in `total_bytes`.
+pub(crate) fn percent(wasted_bytes: u64, total_bytes: u64) -> f64 {
+    if total_bytes == 0 {
+        0.0
+    } else {
+        wasted_bytes as f64 * 100.0 / total_bytes as f64
+    }
+}
+
+impl Change {
+    /// The percentage of wasted bytes in the changed version
+    pub fn waste_percent(&self) -> f64 {
+        percent(self.wasted_bytes, self.total_bytes)
+    }
+
+    /// The percentage of wasted bytes in the version before it
+    pub fn previous_waste_percent(&self) -> f64 {
+        percent(self.previous_wasted_bytes, self.previous_total_bytes)
+    }
+}
+
+impl ChangeKind {
+    /// Return the kind of change from `previous` to `current` percentage of waste, where crates wasting at most
+    /// `lean_percent` are lean, or `None` if the change isn't significant.
+    pub fn new(previous: f64, current: f64, lean_percent: f64) -> Option<Self> {
+        let (was_lean, is_lean) = (previous <= lean_percent, current <= lean_percent);
+        if is_lean && !was_lean {
+            Some(ChangeKind::BecameLean)
+        } else if was_lean && !is_lean || current - previous >= SIGNIFICANT_CHANGE_PERCENT {
+            Some(ChangeKind::Regressed)
+        } else if previous - current >= SIGNIFICANT_CHANGE_PERCENT {
+            Some(ChangeKind::Improved)
+        } else {
+            None
+        }
+    }
+}
+
+/// Combine `lhs` and `rhs`, keeping only the most recent change of each version and at most [`RECENT_CHANGES`].
+pub fn merge(lhs: Changes, rhs: Changes) -> Changes {
+    let mut all: Vec<_> = lhs.into_iter().chain(rhs).collect();
+    all.sort_by(|l, r| {
+        r.time
+            .cmp(&l.time)
+            .then_with(|| l.crate_name.cmp(&r.crate_name))
+            .then_with(|| l.version.cmp(&r.version))
+    });
+    let mut seen = std::collections::BTreeSet::new();
+    all.retain(|c| seen.insert((c.crate_name.clone(), c.version.clone())));
+    all.truncate(RECENT_CHANGES);
+    all
+}
+
+impl Report {
+    /// Return the recent changes of a crate or crate collection report, or nothing for version reports.
+    pub fn changes(&self) -> &[Change] {
+        match self {
+            Report::Crate { changes, .. } | Report::CrateCollection { changes, .. } => changes,
+            Report::Version { .. } => &[],
+        }
+    }
+
+    /// Compare each version of this crate report which isn't in the `previous` report of the same crate to the
+    /// version before it, and record the significant changes as of `time`.
+    ///
+    /// Crates wasting at most `lean_percent` of their bytes are considered lean. Nothing happens if this isn't a
+    /// crate report, or if `previous` is of another crate.
+    #[cfg(feature = "html")]
+    pub fn record_changes(&mut self, previous: &Report, lean_percent: f64, time: SystemTime) {
+        let (crate_name, info_by_version, changes, previous_info_by_version) = match (self, previous) {
+            (
+                Report::Crate {
+                    crate_name,
+                    info_by_version,
+                    changes,
+                    ..
+                },
+                Report::Crate {
+                    crate_name: previous_crate_name,
+                    info_by_version: previous_info_by_version,
+                    ..
+                },
+            ) if *crate_name == *previous_crate_name => {
+                (crate_name, info_by_version, changes, previous_info_by_version)
+            }
+            _ => return,
+        };
+        let time = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let mut all_versions: Vec<_> = previous_info_by_version
+            .iter()
+            .chain(info_by_version.iter())
+            .map(|(version, info)| (parse_semver(version), version, info))
+            .collect();
+        all_versions.sort_by(|l, r| l.0.cmp(&r.0));
+
+        let new_changes: Changes = all_versions
+            .windows(2)
+            .filter(|w| w[0].1 != w[1].1 && !previous_info_by_version.contains_key(w[1].1))
+            .filter_map(|w| {
+                let ((_, previous_version, previous), (_, version, current)) = (&w[0], &w[1]);
+                ChangeKind::new(
+                    percent(previous.waste.total_bytes, previous.all.total_bytes),
+                    percent(current.waste.total_bytes, current.all.total_bytes),
+                    lean_percent,
+                )
+                .map(|kind| Change {
+                    crate_name: crate_name.clone(),
+                    version: (*version).clone(),
+                    previous_version: (*previous_version).clone(),
+                    time,
+                    kind,
+                    total_bytes: current.all.total_bytes,
+                    wasted_bytes: current.waste.total_bytes,
+                    previous_total_bytes: previous.all.total_bytes,
+                    previous_wasted_bytes: previous.waste.total_bytes,
+                })
+            })
+            .collect();
+        *changes = merge(std::mem::take(changes), new_changes);
+    }
+}
@@ -0,0 +1,93 @@
+//! Snapshots of crate and crate collection reports taken at each reporting run, to show how waste changes over time.
+use crate::{Report, VersionInfo};
+use serde_derive::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The totals of a report at the time of a reporting run.
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Snapshot {
+    /// The time the snapshot was taken at, in seconds since the unix epoch
+    pub time: u64,
+    pub total_bytes: u64,
+    pub wasted_bytes: u64,
+    pub potential_gains_bytes: u64,
+    /// The amount of crates in a crate collection, or of versions of a crate
+    pub children: u64,
+}
+
+/// All snapshots of a report, ordered by time, with at most one snapshot per day.
+pub type History = Vec<Snapshot>;
+
+fn day_of(snapshot: &Snapshot) -> u64 {
+    snapshot.time / SECONDS_PER_DAY
+}
+
+/// Add `snapshot` to the end of `history`, replacing the last snapshot if it was taken on the same day.
+pub fn record(history: &mut History, snapshot: Snapshot) {
+    if history.last().map(day_of) == Some(day_of(&snapshot)) {
+        history.pop();
+    }
+    history.push(snapshot);
+}
+
+/// Combine `lhs` and `rhs` into a single history, keeping only the latest snapshot of each day.
+pub fn merge(lhs: History, rhs: History) -> History {
+    let mut all: Vec<_> = lhs.into_iter().chain(rhs).collect();
+    all.sort_by_key(|s| s.time);
+    all.into_iter().fold(Vec::new(), |mut history, snapshot| {
+        record(&mut history, snapshot);
+        history
+    })
+}
+
+fn snapshot_of<'a>(
+    time: SystemTime,
+    total_bytes: u64,
+    info_by_child: impl ExactSizeIterator<Item = &'a VersionInfo>,
+) -> Snapshot {
+    let children = info_by_child.len() as u64;
+    let (wasted_bytes, potential_gains_bytes) = info_by_child.fold((0, 0), |(waste, gains), info| {
+        (
+            waste + info.waste.total_bytes,
+            gains + info.potential_gains.as_ref().map(|g| g.total_bytes).unwrap_or(0),
+        )
+    });
+    Snapshot {
+        time: time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        total_bytes,
+        wasted_bytes,
+        potential_gains_bytes,
+        children,
+    }
+}
+
+impl Report {
+    /// Return a snapshot of a crate or crate collection report as of `time`, or `None` for version reports.
+    pub fn snapshot(&self, time: SystemTime) -> Option<Snapshot> {
+        match self {
+            Report::Crate {
+                total_size_in_bytes,
+                info_by_version,
+                ..
+            } => Some(snapshot_of(time, *total_size_in_bytes, info_by_version.values())),
+            Report::CrateCollection {
+                total_size_in_bytes,
+                info_by_crate,
+                ..
+            } => Some(snapshot_of(time, *total_size_in_bytes, info_by_crate.values())),
+            Report::Version { .. } => None,
+        }
+    }
+
+    /// Add a snapshot as of `time` to the history of a crate or crate collection report, and do nothing otherwise.
+    pub fn record_snapshot(&mut self, time: SystemTime) {
+        if let Some(snapshot) = self.snapshot(time) {
+            match self {
+                Report::Crate { history, .. } | Report::CrateCollection { history, .. } => record(history, snapshot),
+                Report::Version { .. } => unreachable!("versions have no snapshots"),
+            }
+        }
+    }
+}
@@ -1,8 +1,14 @@
-use super::{AggregateFileInfo, Dict, Fix, Report, VersionInfo};
+use super::{
+    badge::BADGE_FILE_NAME,
+    changes::{Change, ChangeKind},
+    history::Snapshot,
+    AggregateFileInfo, Dict, Fix, Report, VersionInfo,
+};
 use bytesize::ByteSize;
 use dia_semver::Semver;
-use horrorshow::{box_html, helper::doctype, html, Render, RenderBox, RenderOnce, TemplateBuffer};
-use std::time::SystemTime;
+use horrorshow::{box_html, helper::doctype, html, Raw, Render, RenderBox, RenderOnce, TemplateBuffer};
+use serde_derive::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub fn fix_to_wasted_files_aggregate(fix: Option<Fix>) -> Option<AggregateFileInfo> {
     match fix.unwrap_or(Fix::RemoveExclude) {
@@ -23,7 +29,139 @@ pub fn fix_to_wasted_files_aggregate(fix: Option<Fix>) -> Option<AggregateFileIn
 
 pub const NO_EXT_MARKER: &str = "<NO_EXT>";
 
-fn parse_semver(version: &str) -> Semver {
+/// The amount of crates to list in the ranking of crates by downloaded waste on the top-level page
+const TOP_DOWNLOADED_WASTE_CRATES: usize = 100;
+
+/// The amount of crates shown with details on the top-level page if scripts are disabled, and per page when searching
+const CRATES_PER_PAGE: usize = 100;
+
+/// The address the waste report is published at, to link to it from elsewhere
+const PUBLISHED_AT: &str = "https://the-lean-crate.github.io/waste";
+
+/// The name of the file with the search index of the top-level page, to be placed next to it
+pub const SEARCH_INDEX_FILE_NAME: &str = "search-index.js";
+
+/// The name of the page listing recent changes, placed next to the top-level page
+pub const CHANGES_PAGE_FILE_NAME: &str = "changes.html";
+
+/// The name of the Atom feed of recent changes, placed next to the top-level page
+pub const CHANGES_FEED_FILE_NAME: &str = "changes.atom";
+
+/// A crate in the search index of the top-level page, serialized as array to keep the index small.
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct SearchEntry(
+    /// The name of the crate
+    pub String,
+    /// The total size of all versions in bytes
+    pub u64,
+    /// The wasted bytes in all versions
+    pub u64,
+    /// The wasted bytes in the latest version, if known
+    pub Option<u64>,
+    /// The bytes potentially gained by the suggested fixes
+    pub u64,
+    /// The wasted bytes transferred by all downloads
+    pub u64,
+);
+
+impl From<(&String, &VersionInfo)> for SearchEntry {
+    fn from((name, info): (&String, &VersionInfo)) -> Self {
+        SearchEntry(
+            name.clone(),
+            info.all.total_bytes,
+            info.waste.total_bytes,
+            info.waste_latest_version.as_ref().map(|(_, latest)| latest.total_bytes),
+            info.potential_gains.as_ref().map(|g| g.total_bytes).unwrap_or(0),
+            info.downloaded_waste_bytes,
+        )
+    }
+}
+
+/// Filters, sorts and paginates the crates of the search index on the top-level page.
+/// It's a function to call with the amount of crates per page.
+const SEARCH_SCRIPT: &str = r#"
+(function (PAGE_SIZE) {
+    var NAME = 0, TOTAL = 1, WASTE = 2, LATEST_WASTE = 3, GAINS = 4, DOWNLOADED_WASTE = 5;
+    var crates = window.CRINER_SEARCH_INDEX;
+    if (!crates) {
+        return;
+    }
+    var query = document.getElementById("search-query");
+    var order = document.getElementById("search-order");
+    var previous = document.getElementById("search-previous");
+    var next = document.getElementById("search-next");
+    var status = document.getElementById("search-status");
+    var list = document.getElementById("children-list");
+    var page = 0;
+
+    function latestWaste(c) {
+        return c[LATEST_WASTE] === null ? c[WASTE] : c[LATEST_WASTE];
+    }
+    var keys = {
+        "waste": latestWaste,
+        "total": function (c) { return c[TOTAL]; },
+        "gains": function (c) { return c[GAINS]; },
+        "downloaded-waste": function (c) { return c[DOWNLOADED_WASTE]; }
+    };
+    function formatBytes(bytes) {
+        var units = ["B", "KB", "MB", "GB", "TB", "PB"];
+        var unit = 0;
+        while (bytes >= 1000 && unit < units.length - 1) {
+            bytes /= 1000;
+            unit += 1;
+        }
+        return (unit === 0 ? bytes : bytes.toFixed(1)) + " " + units[unit];
+    }
+    function matching() {
+        var needle = query.value.trim().toLowerCase();
+        var found = crates.filter(function (c) { return c[NAME].toLowerCase().indexOf(needle) !== -1; });
+        if (order.value === "name") {
+            found.sort(function (a, b) { return a[NAME] < b[NAME] ? -1 : a[NAME] > b[NAME] ? 1 : 0; });
+        } else {
+            var key = keys[order.value];
+            found.sort(function (a, b) { return key(b) - key(a); });
+        }
+        return found;
+    }
+    function render() {
+        var found = matching();
+        var pages = Math.max(1, Math.ceil(found.length / PAGE_SIZE));
+        page = Math.max(0, Math.min(page, pages - 1));
+        list.textContent = "";
+        list.start = page * PAGE_SIZE + 1;
+        found.slice(page * PAGE_SIZE, (page + 1) * PAGE_SIZE).forEach(function (c) {
+            var item = document.createElement("li");
+            var heading = document.createElement("h3");
+            var link = document.createElement("a");
+            link.href = c[NAME];
+            link.textContent = c[NAME];
+            heading.appendChild(link);
+            var stats = document.createElement("p");
+            stats.textContent = formatBytes(latestWaste(c)) + " wasted in the latest version, "
+                + formatBytes(c[WASTE]) + " in all versions with " + formatBytes(c[TOTAL]) + " in total"
+                + (c[GAINS] > 0 ? ", " + formatBytes(c[GAINS]) + " potentially gained" : "")
+                + (c[DOWNLOADED_WASTE] > 0 ? ", " + formatBytes(c[DOWNLOADED_WASTE]) + " transferred by all downloads" : "");
+            item.appendChild(heading);
+            item.appendChild(stats);
+            list.appendChild(item);
+        });
+        status.textContent = found.length + " crates, page " + (page + 1) + " of " + pages;
+        previous.disabled = page === 0;
+        next.disabled = page >= pages - 1;
+    }
+    function restart() {
+        page = 0;
+        render();
+    }
+    query.addEventListener("input", restart);
+    order.addEventListener("change", restart);
+    previous.addEventListener("click", function () { page -= 1; render(); });
+    next.addEventListener("click", function () { page += 1; render(); });
+    document.getElementById("search").hidden = false;
+    render();
+})"#;
+
+pub(crate) fn parse_semver(version: &str) -> Semver {
     use std::str::FromStr;
     Semver::from_str(version)
         .or_else(|_| {
@@ -76,6 +214,29 @@ fn savings_section(d: Option<AggregateFileInfo>) -> Box<dyn Render> {
     }
 }
 
+fn download_waste(info_by_child: &Dict<VersionInfo>) -> (u64, u64) {
+    info_by_child.values().fold((0, 0), |(compressed, downloaded), e| {
+        (
+            compressed + e.compressed_waste_bytes.unwrap_or_default(),
+            downloaded + e.downloaded_waste_bytes,
+        )
+    })
+}
+
+fn download_waste_section(compressed_waste_bytes: u64, downloaded_waste_bytes: u64) -> Box<dyn Render> {
+    box_html! {
+        @ if compressed_waste_bytes > 0 {
+            section(id="download-waste") {
+                h3: "estimated download savings";
+                p: format!("{} compressed", ByteSize(compressed_waste_bytes));
+                @ if downloaded_waste_bytes > 0 {
+                    p: format!("{} weighted by downloads", ByteSize(downloaded_waste_bytes));
+                }
+            }
+        }
+    }
+}
+
 fn title_section(title: impl Into<String>) -> Box<dyn RenderBox> {
     let title = title.into();
     box_html! {
@@ -99,6 +260,8 @@ fn info_section(name: String, info: VersionInfo) -> Box<dyn RenderBox> {
         waste,
         potential_gains,
         waste_latest_version,
+        compressed_waste_bytes,
+        downloaded_waste_bytes,
     } = info;
     box_html! {
         section(id="child-total") {
@@ -118,6 +281,12 @@ fn info_section(name: String, info: VersionInfo) -> Box<dyn RenderBox> {
                 p: format!("{} wasted in {} files", ByteSize(info.total_bytes), info.total_files);
             }
         }
+        @ if let Some(compressed_waste_bytes) = compressed_waste_bytes.filter(|b| *b > 0) {
+            section(id="child-download-waste") {
+                h3: "Download waste in all versions";
+                p: format!("{} compressed, {} weighted by downloads", ByteSize(compressed_waste_bytes), ByteSize(downloaded_waste_bytes));
+            }
+        }
         @ if let Some(gains) = potential_gains {
             section(id="child-gains") {
                 h3: "Potential Gains";
@@ -127,6 +296,198 @@ fn info_section(name: String, info: VersionInfo) -> Box<dyn RenderBox> {
     }
 }
 
+/// Escape `text` for use in the content of an XML element, like in SVG images or feeds.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn date_of(snapshot: &Snapshot) -> String {
+    let time = UNIX_EPOCH + Duration::from_secs(snapshot.time);
+    humantime::format_rfc3339_seconds(time).to_string()[..10].to_owned()
+}
+
+/// The average size, waste and potential gains of a child of `snapshot`.
+fn per_child(snapshot: &Snapshot) -> Snapshot {
+    let children = snapshot.children.max(1);
+    Snapshot {
+        total_bytes: snapshot.total_bytes / children,
+        wasted_bytes: snapshot.wasted_bytes / children,
+        potential_gains_bytes: snapshot.potential_gains_bytes / children,
+        ..snapshot.clone()
+    }
+}
+
+/// Return an inline SVG chart showing the total size, waste and potential gains of all `snapshots` over time,
+/// describing each one with the amount of `children` it had.
+/// If `per_child` is set, the average of a single child like a version is shown instead of the total.
+pub(crate) fn trend_chart(snapshots: &[Snapshot], children: &str, per_child: Option<&str>) -> String {
+    const WIDTH: u64 = 600;
+    const HEIGHT: u64 = 200;
+    const LEFT: u64 = 70;
+    const BOTTOM: u64 = 20;
+    let mut snapshots: Vec<_> = match per_child {
+        Some(_) => snapshots.iter().map(self::per_child).collect(),
+        None => snapshots.to_vec(),
+    };
+    // Clocks may go backwards between reporting runs
+    snapshots.sort_by_key(|s| s.time);
+    let first = snapshots.first().map(|s| s.time).unwrap_or(0);
+    let time_range = snapshots
+        .last()
+        .map(|s| s.time.saturating_sub(first))
+        .unwrap_or(0)
+        .max(1);
+    let max_bytes = snapshots.iter().map(|s| s.total_bytes).max().unwrap_or(0).max(1);
+    let x = |s: &Snapshot| LEFT + s.time.saturating_sub(first) * (WIDTH - LEFT - 10) / time_range;
+    let y = |bytes: u64| 10 + (HEIGHT - BOTTOM - 10) - bytes * (HEIGHT - BOTTOM - 10) / max_bytes;
+    let line = |color: &str, bytes: &dyn Fn(&Snapshot) -> u64| {
+        let points: Vec<_> = snapshots.iter().map(|s| format!("{},{}", x(s), y(bytes(s)))).collect();
+        format!(
+            r#"<polyline fill="none" stroke="{}" stroke-width="2" points="{}"></polyline>"#,
+            color,
+            points.join(" ")
+        )
+    };
+    let per = per_child.map(|child| format!(" per {}", child)).unwrap_or_default();
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-size="10">"#,
+        w = WIDTH,
+        h = HEIGHT
+    );
+    svg.push_str(&format!(
+        r#"<line x1="{l}" y1="10" x2="{l}" y2="{b}" stroke="black"></line><line x1="{l}" y1="{b}" x2="{w}" y2="{b}" stroke="black"></line>"#,
+        l = LEFT,
+        b = HEIGHT - BOTTOM,
+        w = WIDTH
+    ));
+    svg.push_str(&format!(
+        r#"<text x="0" y="14">{}</text><text x="0" y="{}">0 B</text>"#,
+        ByteSize(max_bytes),
+        HEIGHT - BOTTOM
+    ));
+    if let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) {
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}">{}</text><text x="{}" y="{}" text-anchor="end">{}</text>"#,
+            LEFT,
+            HEIGHT - 5,
+            date_of(first),
+            WIDTH,
+            HEIGHT - 5,
+            date_of(last)
+        ));
+    }
+    svg.push_str(&line("gray", &|s| s.total_bytes));
+    svg.push_str(&line("red", &|s| s.wasted_bytes));
+    svg.push_str(&line("green", &|s| s.potential_gains_bytes));
+    for snapshot in &snapshots {
+        svg.push_str(&format!(
+            r#"<circle cx="{}" cy="{}" r="3" fill="red"><title>{}</title></circle>"#,
+            x(snapshot),
+            y(snapshot.wasted_bytes),
+            escape_xml(&format!(
+                "{}: {} wasted of {}{} in {} {}, {} potentially gained",
+                date_of(snapshot),
+                ByteSize(snapshot.wasted_bytes),
+                ByteSize(snapshot.total_bytes),
+                per,
+                snapshot.children,
+                children,
+                ByteSize(snapshot.potential_gains_bytes)
+            ))
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// A chart of how the report changed over time, if there are at least two snapshots in its `history`, showing
+/// the average of a single child if `per_child` is set.
+fn trend_section(
+    history: Vec<Snapshot>,
+    children: &'static str,
+    per_child: Option<&'static str>,
+) -> Box<dyn RenderBox> {
+    let chart = (history.len() > 1).then(|| trend_chart(&history, children, per_child));
+    let (title, legend) = match per_child {
+        Some(child) => (
+            format!("Waste per {} over time", child),
+            format!(
+                "Average size of a {} in gray, its waste in red and potential gains in green.",
+                child
+            ),
+        ),
+        None => (
+            "Waste over time".to_owned(),
+            "Total size in gray, waste in red and potential gains in green.".to_owned(),
+        ),
+    };
+    box_html! {
+        @ if let Some(chart) = chart {
+            section(id="trend") {
+                h1: title;
+                p: legend;
+                : Raw(chart);
+            }
+        }
+    }
+}
+
+/// The badge of the crate, along with the Markdown to embed it into README files.
+fn badge_section(crate_name: &str) -> Box<dyn RenderBox> {
+    let markdown = format!(
+        "[![lean crate]({url}/{name}/{badge})]({url}/{name}/)",
+        url = PUBLISHED_AT,
+        name = crate_name,
+        badge = BADGE_FILE_NAME
+    );
+    box_html! {
+        section(id="badge") {
+            img(src=BADGE_FILE_NAME, alt="lean crate badge");
+            p: "Show it in your README with the following Markdown:";
+            pre {
+                code: markdown
+            }
+        }
+    }
+}
+
+fn change_title(change: &Change) -> String {
+    let what = match change.kind {
+        ChangeKind::BecameLean => "became lean",
+        ChangeKind::Improved => "wastes less",
+        ChangeKind::Regressed => "wastes more",
+    };
+    format!("{} {} {}", change.crate_name, change.version, what)
+}
+
+fn change_summary(change: &Change) -> String {
+    format!(
+        "waste went from {:.1}% of {} in {} to {:.1}% of {}",
+        change.previous_waste_percent(),
+        ByteSize(change.previous_total_bytes),
+        change.previous_version,
+        change.waste_percent(),
+        ByteSize(change.total_bytes),
+    )
+}
+
+fn change_time(change: &Change) -> String {
+    humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(change.time)).to_string()
+}
+
+/// Links to the page and the feed of recent changes.
+fn recent_changes_section() -> Box<dyn RenderBox> {
+    box_html! {
+        section(id="recent-changes") {
+            : "Crates which recently became lean or changed their waste significantly: ";
+            a(href=CHANGES_PAGE_FILE_NAME): "recent changes";
+            : " | ";
+            a(href=CHANGES_FEED_FILE_NAME): "feed";
+        }
+    }
+}
+
 fn page_footer() -> impl Render {
     html! {
         footer {
@@ -154,30 +515,134 @@ fn page_footer() -> impl Render {
 enum SortOrder {
     Semver,
     Waste,
+    /// By the amount of wasted bytes transferred by all downloads
+    DownloadedWaste,
+}
+
+fn sort_children(children: &mut [(String, VersionInfo)], order: SortOrder) {
+    children.sort_by(|(ln, le), (rn, re)| match order {
+        SortOrder::Semver => parse_semver(ln).cmp(&parse_semver(rn)),
+        SortOrder::Waste => match (&le.waste_latest_version, &re.waste_latest_version) {
+            (Some(le), Some(re)) => le.1.total_bytes.cmp(&re.1.total_bytes),
+            _ => le.waste.total_bytes.cmp(&re.waste.total_bytes),
+        },
+        SortOrder::DownloadedWaste => le.downloaded_waste_bytes.cmp(&re.downloaded_waste_bytes),
+    });
 }
 
+/// The names of the `limit` children which transferred the most waste across all of their downloads, most first,
+/// along with the waste of all of their downloads and their compressed waste.
+pub(crate) fn downloaded_waste_ranking(
+    info_by_child: &Dict<VersionInfo>,
+    limit: usize,
+) -> Vec<(String, u64, Option<u64>)> {
+    let mut sorted: Vec<_> = info_by_child
+        .iter()
+        .filter(|(_, info)| info.downloaded_waste_bytes > 0)
+        .map(|(name, info)| (name.clone(), info.clone()))
+        .collect();
+    sort_children(&mut sorted, SortOrder::DownloadedWaste);
+    sorted
+        .into_iter()
+        .rev()
+        .take(limit)
+        .map(|(name, info)| (name, info.downloaded_waste_bytes, info.compressed_waste_bytes))
+        .collect()
+}
+
+/// A list of the `limit` children which transferred the most waste across all of their downloads.
+/// `compressed_label` describes what the compressed waste of a child amounts to, which is the waste of
+/// a single download for versions, but the waste of downloading each version once for crates.
+fn downloaded_waste_ranking_section(
+    title: impl Into<String>,
+    info_by_child: &Dict<VersionInfo>,
+    prefix: &str,
+    suffix: &str,
+    compressed_label: &'static str,
+    limit: usize,
+) -> Box<dyn RenderBox> {
+    let title = title.into();
+    let ranking: Vec<_> = downloaded_waste_ranking(info_by_child, limit)
+        .into_iter()
+        .map(|(name, downloaded_waste_bytes, compressed_waste_bytes)| {
+            (
+                format!("{}{}{}", prefix, name, suffix),
+                name,
+                downloaded_waste_bytes,
+                compressed_waste_bytes,
+            )
+        })
+        .collect();
+    box_html! {
+        @ if !ranking.is_empty() {
+            section(id="downloaded-waste-ranking") {
+                h1: title;
+                ol {
+                    @ for (link, name, downloaded_waste_bytes, compressed_waste_bytes) in ranking {
+                        li {
+                            a(href=link): name;
+                            : format_args!(" : {} transferred by all downloads", ByteSize(downloaded_waste_bytes));
+                            @ if let Some(compressed_waste_bytes) = compressed_waste_bytes {
+                                : format_args!(", {} {}", ByteSize(compressed_waste_bytes), compressed_label);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Controls to search, sort and paginate the crates listed by the `child_items_section`, which are hidden
+/// until the search index was loaded by the `search_scripts()`.
+fn search_controls_section() -> Box<dyn RenderBox> {
+    box_html! {
+        section(id="search", hidden?=true) {
+            input(id="search-query", type="search", placeholder="Search crates", autocomplete="off");
+            select(id="search-order") {
+                option(value="waste"): "Waste in latest version";
+                option(value="downloaded-waste"): "Waste transferred by all downloads";
+                option(value="gains"): "Potential gains";
+                option(value="total"): "Total size";
+                option(value="name"): "Name";
+            }
+            button(id="search-previous", type="button"): "Previous";
+            button(id="search-next", type="button"): "Next";
+            span(id="search-status");
+        }
+    }
+}
+
+/// Load the search index and make the search controls work, which must come after the list of children.
+fn search_scripts() -> Box<dyn RenderBox> {
+    let script = format!("{}({});", SEARCH_SCRIPT, CRATES_PER_PAGE);
+    box_html! {
+        script(src=SEARCH_INDEX_FILE_NAME) {}
+        script: Raw(script);
+    }
+}
+
+/// A list of at most `limit` children, in descending `order`, followed by the names of all other children for
+/// use if scripts are disabled.
 fn child_items_section(
     title: impl Into<String>,
     info_by_child: Dict<VersionInfo>,
     prefix: String,
     suffix: impl Into<String>,
     order: SortOrder,
+    limit: usize,
 ) -> Box<dyn RenderBox> {
     let title = title.into();
     let suffix = suffix.into();
     let mut sorted: Vec<_> = info_by_child.into_iter().collect();
-    sorted.sort_by(|(ln, le), (rn, re)| match order {
-        SortOrder::Semver => parse_semver(ln).cmp(&parse_semver(rn)),
-        SortOrder::Waste => match (&le.waste_latest_version, &re.waste_latest_version) {
-            (Some(le), Some(re)) => le.1.total_bytes.cmp(&re.1.total_bytes),
-            _ => le.waste.total_bytes.cmp(&re.waste.total_bytes),
-        },
-    });
+    sort_children(&mut sorted, order);
+    sorted.reverse();
+    let remaining = sorted.split_off(limit.min(sorted.len()));
     box_html! {
         section(id="children") {
             h1: title;
-            ol {
-                @ for (name, info) in sorted.into_iter().rev() {
+            ol(id="children-list") {
+                @ for (name, info) in sorted.into_iter() {
                     li {
                         h3 {
                             a(href=format!("{}{}{}", prefix, name, suffix)) {
@@ -188,6 +653,19 @@ fn child_items_section(
                     }
                 }
             }
+            @ if !remaining.is_empty() {
+                noscript {
+                    ol(start=limit + 1) {
+                        @ for (name, _) in remaining.into_iter() {
+                            li {
+                                a(href=format!("{}{}{}", prefix, name, suffix)) {
+                                    : name
+                                }
+                            }
+                        }
+                    }
+                }
+            }
         }
     }
 }
@@ -246,8 +724,14 @@ impl RenderOnce for Report {
                 total_size_in_bytes,
                 mut wasted_files,
                 suggested_fix,
+                provenance,
+                files_dropped_by_fix,
+                manifest_patch,
+                compressed_waste_bytes,
+                downloads,
             } => {
                 wasted_files.sort_by_key(|(_, s)| *s);
+                let flagged_by = provenance.as_ref().map(|p| (p, p.matcher()));
                 let title = format!("{}:{}", crate_name, crate_version);
                 tmpl << html! {
                     : doctype::HTML;
@@ -258,12 +742,41 @@ impl RenderOnce for Report {
                                 : title_section(title);
                                 : total_section(total_size_in_bytes, total_files);
                                 : savings_section(fix_to_wasted_files_aggregate(suggested_fix.clone()));
+                                @ if let Some(compressed_waste_bytes) = compressed_waste_bytes.filter(|b| *b > 0) {
+                                    section(id="download-waste") {
+                                        h3: "estimated download savings";
+                                        p: format!("{} per download", ByteSize(compressed_waste_bytes));
+                                        @ if let Some(downloads) = downloads {
+                                            p: format!("{} in {} downloads so far", ByteSize(compressed_waste_bytes * downloads), downloads);
+                                        }
+                                    }
+                                }
                                 @ if let Some(suggested_fix) = suggested_fix {
                                     section {
                                         h3: "Fix";
                                         section {
                                             |t| write!(t, "{:#?}", suggested_fix)
                                         }
+                                        @ if let Some(patch) = manifest_patch.as_ref() {
+                                            section(id="manifest-patch") {
+                                                p {
+                                                    : "Apply it to Cargo.toml with this ";
+                                                    a(href=format!("{}.patch", crate_version)): "patch";
+                                                    : ":";
+                                                }
+                                                pre: patch
+                                            }
+                                        }
+                                        @ if !files_dropped_by_fix.is_empty() {
+                                            section {
+                                                p: "Careful: this fix would stop packaging files which are needed to build the crate:";
+                                                ul {
+                                                    @ for path in files_dropped_by_fix.iter() {
+                                                        li: path
+                                                    }
+                                                }
+                                            }
+                                        }
                                     }
                                 } else {
                                     p: "Perfectly lean!"
@@ -272,9 +785,17 @@ impl RenderOnce for Report {
                                     section {
                                         h3: format!("{} wasted files", wasted_files.len());
                                         p: format!("total waste: {}", ByteSize(wasted_files.iter().map(|(_, s)| *s).sum::<u64>()));
+                                        @ if let Some(provenance) = provenance.as_ref() {
+                                            p: format!("classified with rule set '{}'", provenance.rule_set);
+                                        }
                                         ol {
                                             @ for (path, size) in wasted_files.into_iter().rev() {
-                                                li : format_args!("{} : {}", path, ByteSize(size))
+                                                li {
+                                                    : format_args!("{} : {}", path, ByteSize(size));
+                                                    @ if let Some((p, m)) = flagged_by.as_ref() {
+                                                        small : format_args!(" - {}", p.reason_from(m(&path)))
+                                                    }
+                                                }
                                             }
                                         }
                                     }
@@ -291,8 +812,11 @@ impl RenderOnce for Report {
                 total_files,
                 info_by_version,
                 wasted_by_extension,
+                history,
+                changes: _,
             } => {
                 let gains = potential_savings(&info_by_version);
+                let (compressed_waste_bytes, downloaded_waste_bytes) = download_waste(&info_by_version);
                 let no_prefix = String::new();
                 tmpl << html! {
                     : doctype::HTML;
@@ -301,10 +825,14 @@ impl RenderOnce for Report {
                         body {
                             article {
                                 : title_section(crate_name.clone());
+                                : badge_section(&crate_name);
                                 : total_section(total_size_in_bytes, total_files);
                                 : savings_section(gains);
+                                : download_waste_section(compressed_waste_bytes, downloaded_waste_bytes);
+                                : trend_section(history, "versions", Some("version"));
                                 : by_extension_section(wasted_by_extension);
-                                : child_items_section("Versions", info_by_version, no_prefix, ".html", SortOrder::Semver);
+                                : downloaded_waste_ranking_section("Versions by downloaded waste", &info_by_version, "", ".html", "per download", usize::MAX);
+                                : child_items_section("Versions", info_by_version, no_prefix, ".html", SortOrder::Semver, usize::MAX);
                             }
                         }
                         : page_footer();
@@ -316,11 +844,14 @@ impl RenderOnce for Report {
                 total_files,
                 info_by_crate,
                 wasted_by_extension,
+                history,
+                changes: _,
             } => {
                 let title = "Crates.io Waste Report";
                 let no_prefix = String::new();
                 let no_suffix = String::new();
                 let gains = potential_savings(&info_by_crate);
+                let (compressed_waste_bytes, downloaded_waste_bytes) = download_waste(&info_by_crate);
                 let (waste_in_bytes, wasted_files_count) = wasted_by_extension
                     .iter()
                     .fold((0, 0), |(waste_bytes, waste_files), e| {
@@ -338,8 +869,14 @@ impl RenderOnce for Report {
                                     h3: format!("{} wasted in {} files", ByteSize(waste_in_bytes), wasted_files_count);
                                 }
                                 : savings_section(gains);
+                                : download_waste_section(compressed_waste_bytes, downloaded_waste_bytes);
+                                : trend_section(history, "crates", None);
+                                : recent_changes_section();
                                 : by_extension_section(wasted_by_extension);
-                                : child_items_section("Crates", info_by_crate, no_prefix, no_suffix, SortOrder::Waste);
+                                : downloaded_waste_ranking_section("Crates by downloaded waste", &info_by_crate, "", "", "when downloading each version once", TOP_DOWNLOADED_WASTE_CRATES);
+                                : search_controls_section();
+                                : child_items_section("Crates", info_by_crate, no_prefix, no_suffix, SortOrder::Waste, CRATES_PER_PAGE);
+                                : search_scripts();
                             }
                         }
                         : page_footer();
@@ -349,3 +886,109 @@ impl RenderOnce for Report {
         }
     }
 }
+
+impl Report {
+    /// Return the search index of the crates in a crate collection report, or `None` for all other reports.
+    pub fn search_index(&self) -> Option<Vec<SearchEntry>> {
+        match self {
+            Report::CrateCollection { info_by_crate, .. } => {
+                Some(info_by_crate.iter().map(SearchEntry::from).collect())
+            }
+            Report::Version { .. } | Report::Crate { .. } => None,
+        }
+    }
+
+    /// Write the search index of a crate collection report as script for use by its html page, and return false
+    /// if this isn't a crate collection.
+    pub fn write_search_index(&self, mut out: impl std::io::Write) -> std::io::Result<bool> {
+        match self.search_index() {
+            Some(index) => {
+                out.write_all(b"window.CRINER_SEARCH_INDEX = ")?;
+                serde_json::to_writer(&mut out, &index)?;
+                out.write_all(b";\n")?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Write a page listing the recent changes of a crate collection report, and return false if this isn't a crate
+    /// collection.
+    pub fn write_changes_page(&self, mut out: impl std::io::Write) -> Result<bool, horrorshow::Error> {
+        use horrorshow::Template;
+
+        let changes = match self {
+            Report::CrateCollection { changes, .. } => changes,
+            Report::Version { .. } | Report::Crate { .. } => return Ok(false),
+        };
+        let title = "Recent changes in waste";
+        let page = html! {
+            : doctype::HTML;
+            html {
+                : page_head(title);
+                body {
+                    article {
+                        : title_section(title);
+                        p {
+                            : "Versions which became lean or changed their waste significantly compared to the version before them, also available as ";
+                            a(href=CHANGES_FEED_FILE_NAME): "feed";
+                            : ".";
+                        }
+                        @ if changes.is_empty() {
+                            p: "No changes were noticed yet."
+                        }
+                        ol(id="changes") {
+                            @ for change in changes.iter() {
+                                li {
+                                    : format!("{}: ", &change_time(change)[..10]);
+                                    a(href=format!("{}/{}.html", change.crate_name, change.version)): change_title(change);
+                                    small: format!(" - {}", change_summary(change));
+                                }
+                            }
+                        }
+                    }
+                }
+                : page_footer();
+            }
+        };
+        page.write_to_io(&mut out)?;
+        Ok(true)
+    }
+
+    /// Write an Atom feed of the recent changes of a crate collection report, and return false if this isn't a
+    /// crate collection.
+    pub fn write_changes_feed(&self, mut out: impl std::io::Write) -> std::io::Result<bool> {
+        let changes = match self {
+            Report::CrateCollection { changes, .. } => changes,
+            Report::Version { .. } | Report::Crate { .. } => return Ok(false),
+        };
+        let updated = changes
+            .first()
+            .map(change_time)
+            .unwrap_or_else(|| humantime::format_rfc3339_seconds(SystemTime::now()).to_string());
+        writeln!(out, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+        writeln!(out, r#"<feed xmlns="http://www.w3.org/2005/Atom">"#)?;
+        writeln!(out, "<title>Crates.io Waste Report: recent changes</title>")?;
+        writeln!(out, "<id>{}/{}</id>", PUBLISHED_AT, CHANGES_FEED_FILE_NAME)?;
+        writeln!(
+            out,
+            r#"<link rel="self" href="{}/{}"/>"#,
+            PUBLISHED_AT, CHANGES_FEED_FILE_NAME
+        )?;
+        writeln!(out, r#"<link href="{}/{}"/>"#, PUBLISHED_AT, CHANGES_PAGE_FILE_NAME)?;
+        writeln!(out, "<updated>{}</updated>", updated)?;
+        writeln!(out, "<author><name>Criner</name></author>")?;
+        for change in changes {
+            let url = format!("{}/{}/{}.html", PUBLISHED_AT, change.crate_name, change.version);
+            writeln!(out, "<entry>")?;
+            writeln!(out, "<title>{}</title>", escape_xml(&change_title(change)))?;
+            writeln!(out, "<id>{}#{:?}</id>", url, change.kind)?;
+            writeln!(out, r#"<link href="{}"/>"#, url)?;
+            writeln!(out, "<updated>{}</updated>", change_time(change))?;
+            writeln!(out, "<summary>{}</summary>", escape_xml(&change_summary(change)))?;
+            writeln!(out, "</entry>")?;
+        }
+        writeln!(out, "</feed>")?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,235 @@
+//! A JSON representation of reports with a versioned schema, which is independent of how reports are stored.
+use crate::{Dict, Fix, Patterns, Report, VersionInfo};
+use serde_derive::{Deserialize, Serialize};
+
+/// The version of the schema of [`Document`]s, which is incremented whenever fields are changed or removed.
+/// Adding fields does not change the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The JSON representation of a [`Report`].
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Document {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub report: ReportDocument,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReportDocument {
+    Version(VersionDocument),
+    Crate(CrateDocument),
+    CrateCollection(CrateCollectionDocument),
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct VersionDocument {
+    pub crate_name: String,
+    pub crate_version: String,
+    pub total_size_in_bytes: u64,
+    pub total_files: u64,
+    pub wasted_size_in_bytes: u64,
+    /// The estimated size of the wasted files in the compressed crate, if known
+    pub compressed_waste_bytes: Option<u64>,
+    pub downloads: Option<u64>,
+    /// The name of the rule set used to classify files, or `None` if there is no waste
+    pub rule_set: Option<String>,
+    pub wasted_files: Vec<WastedFileDocument>,
+    pub suggested_fix: Option<FixDocument>,
+    /// Files needed to build the crate which wouldn't be packaged anymore if the `suggested_fix` was applied
+    pub files_dropped_by_fix: Vec<String>,
+    /// A unified diff of `Cargo.toml` implementing the `suggested_fix`
+    pub manifest_patch: Option<String>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct WastedFileDocument {
+    pub path: String,
+    pub size_in_bytes: u64,
+    /// Why the file is considered waste, if known
+    pub reason: Option<String>,
+}
+
+/// The `include` and `exclude` fields of the manifest after applying a fix.
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct FixDocument {
+    /// The patterns to set as `include` field, or `None` to leave it as is
+    pub include: Option<Patterns>,
+    /// The patterns to set as `exclude` field, or `None` to leave it as is unless `remove_exclude` is true
+    pub exclude: Option<Patterns>,
+    pub remove_exclude: bool,
+    /// True if the crate has a build script, which has to be packaged as well
+    pub has_build_script: bool,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct CrateDocument {
+    pub crate_name: String,
+    pub total_size_in_bytes: u64,
+    pub total_files: u64,
+    pub versions: Vec<SummaryDocument>,
+    pub wasted_by_extension: Vec<ExtensionDocument>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct CrateCollectionDocument {
+    pub total_size_in_bytes: u64,
+    pub total_files: u64,
+    pub crates: Vec<SummaryDocument>,
+    pub wasted_by_extension: Vec<ExtensionDocument>,
+}
+
+/// The totals of a crate version or of all versions of a crate.
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct SummaryDocument {
+    /// The version or the name of the crate
+    pub name: String,
+    pub total_size_in_bytes: u64,
+    pub total_files: u64,
+    pub wasted_size_in_bytes: u64,
+    pub wasted_files: u64,
+    /// The estimated size of the waste in compressed crates, or `None` if unknown
+    pub compressed_waste_bytes: Option<u64>,
+    pub downloaded_waste_bytes: u64,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct ExtensionDocument {
+    pub extension: String,
+    pub total_size_in_bytes: u64,
+    pub total_files: u64,
+}
+
+impl From<&Fix> for FixDocument {
+    fn from(fix: &Fix) -> Self {
+        let (include, exclude, remove_exclude, has_build_script) = match fix {
+            Fix::ImprovedInclude {
+                include,
+                has_build_script,
+                ..
+            }
+            | Fix::NewInclude {
+                include,
+                has_build_script,
+            } => (Some(include.clone()), None, false, *has_build_script),
+            Fix::RemoveExcludeAndUseInclude { include, .. } => (Some(include.clone()), None, true, false),
+            Fix::EnrichedExclude {
+                exclude,
+                has_build_script,
+                ..
+            } => (None, Some(exclude.clone()), false, *has_build_script),
+            Fix::RemoveExclude => (None, None, true, false),
+        };
+        FixDocument {
+            include,
+            exclude,
+            remove_exclude,
+            has_build_script,
+        }
+    }
+}
+
+fn summaries(info: &Dict<VersionInfo>) -> Vec<SummaryDocument> {
+    info.iter()
+        .map(|(name, info)| SummaryDocument {
+            name: name.clone(),
+            total_size_in_bytes: info.all.total_bytes,
+            total_files: info.all.total_files,
+            wasted_size_in_bytes: info.waste.total_bytes,
+            wasted_files: info.waste.total_files,
+            compressed_waste_bytes: info.compressed_waste_bytes,
+            downloaded_waste_bytes: info.downloaded_waste_bytes,
+        })
+        .collect()
+}
+
+fn extensions(info: &Dict<crate::AggregateFileInfo>) -> Vec<ExtensionDocument> {
+    info.iter()
+        .map(|(extension, info)| ExtensionDocument {
+            extension: extension.clone(),
+            total_size_in_bytes: info.total_bytes,
+            total_files: info.total_files,
+        })
+        .collect()
+}
+
+impl Report {
+    /// Return the JSON representation of this report.
+    pub fn to_json_document(&self) -> Document {
+        let report = match self {
+            Report::Version {
+                crate_name,
+                crate_version,
+                total_size_in_bytes,
+                total_files,
+                wasted_files,
+                suggested_fix,
+                provenance,
+                files_dropped_by_fix,
+                manifest_patch,
+                compressed_waste_bytes,
+                downloads,
+            } => ReportDocument::Version(VersionDocument {
+                crate_name: crate_name.clone(),
+                crate_version: crate_version.clone(),
+                total_size_in_bytes: *total_size_in_bytes,
+                total_files: *total_files,
+                wasted_size_in_bytes: wasted_files.iter().map(|(_, size)| size).sum(),
+                compressed_waste_bytes: *compressed_waste_bytes,
+                downloads: *downloads,
+                rule_set: provenance.as_ref().map(|p| p.rule_set.clone()),
+                wasted_files: self
+                    .explain()
+                    .map(|e| e.wasted_files)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|file| WastedFileDocument {
+                        path: file.path,
+                        size_in_bytes: file.size,
+                        reason: file.reason.map(|r| r.to_string()),
+                    })
+                    .collect(),
+                suggested_fix: suggested_fix.as_ref().map(FixDocument::from),
+                files_dropped_by_fix: files_dropped_by_fix.clone(),
+                manifest_patch: manifest_patch.clone(),
+            }),
+            Report::Crate {
+                crate_name,
+                total_size_in_bytes,
+                total_files,
+                info_by_version,
+                wasted_by_extension,
+                history: _,
+                changes: _,
+            } => ReportDocument::Crate(CrateDocument {
+                crate_name: crate_name.clone(),
+                total_size_in_bytes: *total_size_in_bytes,
+                total_files: *total_files,
+                versions: summaries(info_by_version),
+                wasted_by_extension: extensions(wasted_by_extension),
+            }),
+            Report::CrateCollection {
+                total_size_in_bytes,
+                total_files,
+                info_by_crate,
+                wasted_by_extension,
+                history: _,
+                changes: _,
+            } => ReportDocument::CrateCollection(CrateCollectionDocument {
+                total_size_in_bytes: *total_size_in_bytes,
+                total_files: *total_files,
+                crates: summaries(info_by_crate),
+                wasted_by_extension: extensions(wasted_by_extension),
+            }),
+        };
+        Document {
+            schema_version: SCHEMA_VERSION,
+            report,
+        }
+    }
+
+    /// Write the JSON representation of this report into `out`, nicely formatted.
+    pub fn write_json(&self, out: impl std::io::Write) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(out, &self.to_json_document())
+    }
+}
@@ -3,9 +3,20 @@
 #[macro_use]
 extern crate lazy_static;
 
+#[cfg(feature = "html")]
+pub mod badge;
+pub mod changes;
+pub mod history;
 #[cfg(feature = "html")]
 pub mod html;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+pub mod patch;
 pub mod result;
+pub mod rules;
+pub mod validate;
 
 #[cfg(test)]
 mod test;
@@ -13,7 +24,8 @@ mod test;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-pub use result::{globset_from_patterns, tar_path_to_utf8_str};
+pub use result::{globset_from_patterns, rust_source_outline, tar_path_to_utf8_str};
+pub use rules::{RuleBook, Rules};
 
 pub type Patterns = Vec<String>;
 
@@ -26,15 +38,20 @@ pub struct TarHeader {
     pub size: u64,
     /// The type of entry, to be analyzed with tar::EntryType
     pub entry_type: u8,
+    /// The estimated size of the file after compression, which is what it adds to the size of the downloaded crate,
+    /// or `None` if it wasn't estimated when extracting the crate.
+    #[serde(default)]
+    pub compressed_size: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct TarPackage {
     /// Meta data of all entries in the crate
     pub entries_meta_data: Vec<TarHeader>,
-    /// The actual content of selected files, Cargo.*, build.rs and lib/main
-    /// IMPORTANT: This file may be partial and limited in size unless it is Cargo.toml, which
-    /// is always complete.
+    /// The actual content of selected files, Cargo.*, build.rs and lib/main, along with the [outline][rust_source_outline()]
+    /// of other Rust sources, which is used to find all compiled modules.
+    /// IMPORTANT: This file may be partial and limited in size unless it is Cargo.toml or Cargo.toml.orig, which
+    /// are always complete.
     /// Note that these are also present in entries_meta_data.
     pub entries: Vec<(TarHeader, Vec<u8>)>,
 }
@@ -102,6 +119,9 @@ pub struct CargoConfig {
     pub package: Option<PackageSection>,
     pub lib: Option<SectionWithPath>,
     pub bin: Option<Vec<SectionWithPath>>,
+    pub example: Option<Vec<SectionWithPath>>,
+    pub test: Option<Vec<SectionWithPath>>,
+    pub bench: Option<Vec<SectionWithPath>>,
 }
 
 impl CargoConfig {
@@ -111,18 +131,93 @@ impl CargoConfig {
     pub fn build_script_path(&self) -> Option<&str> {
         self.package.as_ref().and_then(|p| p.build_script_path())
     }
+    /// Returns true if automatic detection of the build script was turned off with `build = false`.
+    /// Packages which link a native library always have a build script, no matter what.
+    pub fn build_script_disabled(&self) -> bool {
+        self.package
+            .as_ref()
+            .is_some_and(|p| p.links.is_none() && p.build.as_ref().and_then(|b| b.as_bool()) == Some(false))
+    }
     pub fn lib_path(&self) -> &str {
         self.lib
             .as_ref()
             .and_then(|l| l.path.as_deref())
             .unwrap_or("src/lib.rs")
     }
-    pub fn bin_paths(&self) -> Vec<&str> {
-        self.bin
-            .as_ref()
-            .map(|l| l.iter().filter_map(|s| s.path.as_deref()).collect())
-            .unwrap_or_else(|| vec!["src/main.rs"])
+    /// The paths of all binaries, either declared or `src/main.rs` unless automatic discovery is disabled.
+    /// Binaries declared without path have all paths cargo would infer, of which only one is expected to exist.
+    pub fn bin_paths(&self) -> Vec<String> {
+        let package_name = self.package.as_ref().and_then(|p| p.name.as_deref());
+        match self.bin.as_ref() {
+            Some(bins) => bins
+                .iter()
+                .flat_map(|s| match (s.path.as_deref(), s.name.as_deref()) {
+                    (Some(path), _) => vec![path.to_owned()],
+                    (None, Some(name)) if Some(name) == package_name => vec!["src/main.rs".into()],
+                    (None, Some(name)) => inferred_target_paths("src/bin", name).to_vec(),
+                    (None, None) => Vec::new(),
+                })
+                .collect(),
+            None if self.package.as_ref().and_then(|p| p.autobins) == Some(false) => Vec::new(),
+            None => vec!["src/main.rs".into()],
+        }
     }
+    /// The paths of all explicitly declared examples, tests and benchmarks. Older versions of Cargo fail to load
+    /// a manifest if any of these is missing, even though they are not built for dependents.
+    /// Like with [`bin_paths()`][CargoConfig::bin_paths()], targets without path have all paths cargo would infer.
+    pub fn declared_target_paths(&self) -> Vec<String> {
+        fn paths<'a>(
+            targets: Option<&'a Vec<SectionWithPath>>,
+            directory: &'a str,
+        ) -> impl Iterator<Item = String> + 'a {
+            targets
+                .into_iter()
+                .flatten()
+                .flat_map(move |s| match (s.path.as_ref(), s.name.as_ref()) {
+                    (Some(path), _) => vec![path.clone()],
+                    (None, Some(name)) => inferred_target_paths(directory, name).to_vec(),
+                    (None, None) => Vec::new(),
+                })
+        }
+        paths(self.example.as_ref(), "examples")
+            .chain(paths(self.test.as_ref(), "tests"))
+            .chain(paths(self.bench.as_ref(), "benches"))
+            .collect()
+    }
+    /// The paths of files the manifest refers to which are needed to publish or document the crate,
+    /// like the readme and license file or files passed to rustdoc on docs.rs.
+    /// Readmes cargo would detect on its own are among them unless detection is turned off with `readme = false`.
+    pub fn referenced_file_paths(&self) -> Vec<String> {
+        let package = match self.package.as_ref() {
+            Some(p) => p,
+            None => return Vec::new(),
+        };
+        let readme: Vec<String> = match package.readme.as_ref() {
+            Some(toml::value::Value::String(path)) => vec![path.clone()],
+            Some(toml::value::Value::Boolean(false)) => Vec::new(),
+            Some(toml::value::Value::Boolean(true)) => vec!["README.md".into()],
+            _ => vec!["README.md".into(), "README.txt".into(), "README".into()],
+        };
+        readme
+            .into_iter()
+            .chain(
+                package
+                    .license_file
+                    .as_ref()
+                    .and_then(|v| v.as_str())
+                    .map(ToOwned::to_owned),
+            )
+            .chain(package.docs_rs_file_paths())
+            .collect()
+    }
+}
+
+/// The paths cargo tries for a target called `name` without declared path, in the given target `directory`.
+fn inferred_target_paths(directory: &str, name: &str) -> [String; 2] {
+    [
+        format!("{}/{}.rs", directory, name),
+        format!("{}/{}/main.rs", directory, name),
+    ]
 }
 
 impl From<&str> for CargoConfig {
@@ -133,24 +228,192 @@ impl From<&str> for CargoConfig {
 
 #[derive(Default, Deserialize)]
 pub struct SectionWithPath {
+    pub name: Option<String>,
     pub path: Option<String>,
 }
 
 #[derive(Default, Deserialize)]
 pub struct PackageSection {
+    pub name: Option<String>,
+    /// The patterns, or `None` if unset or inherited from a workspace which isn't known to us
+    #[serde(default, deserialize_with = "patterns_unless_inherited")]
     pub include: Option<Patterns>,
+    /// The patterns, or `None` if unset or inherited from a workspace which isn't known to us
+    #[serde(default, deserialize_with = "patterns_unless_inherited")]
     pub exclude: Option<Patterns>,
     pub build: Option<toml::value::Value>,
+    /// The name of the native library the package links, which requires a build script
+    pub links: Option<String>,
+    pub autobins: Option<bool>,
+    /// A path, or a boolean to turn automatic detection on or off
+    pub readme: Option<toml::value::Value>,
+    #[serde(rename = "license-file")]
+    pub license_file: Option<toml::value::Value>,
+    pub metadata: Option<toml::value::Value>,
+}
+
+/// Deserialize patterns, ignoring `{ workspace = true }` as found in manifests which weren't normalized.
+fn patterns_unless_inherited<'de, D>(deserializer: D) -> Result<Option<Patterns>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MaybeInherited {
+        Patterns(Patterns),
+        Inherited {},
+    }
+    Ok(match serde::Deserialize::deserialize(deserializer)? {
+        MaybeInherited::Patterns(patterns) => Some(patterns),
+        MaybeInherited::Inherited {} => None,
+    })
 }
 
 impl PackageSection {
     pub fn build_script_path(&self) -> Option<&str> {
         self.build.as_ref().and_then(|s| s.as_str())
     }
+
+    /// Files passed to rustdoc via the `rustdoc-args` in `package.metadata.docs.rs`, like `--html-in-header katex.html`.
+    fn docs_rs_file_paths(&self) -> Vec<String> {
+        const FILE_ARGS: &[&str] = &[
+            "--html-in-header",
+            "--html-before-content",
+            "--html-after-content",
+            "--extend-css",
+            "--markdown-css",
+            "--theme",
+        ];
+        let args: Vec<&str> = self
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("docs").and_then(|d| d.get("rs")).or_else(|| m.get("docs-rs")))
+            .and_then(|d| d.get("rustdoc-args"))
+            .and_then(|a| a.as_array())
+            .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        args.iter()
+            .zip(args.iter().skip(1))
+            .filter(|(flag, _)| FILE_ARGS.contains(flag))
+            .map(|(_, path)| path.to_string())
+            .chain(args.iter().filter_map(|arg| {
+                FILE_ARGS
+                    .iter()
+                    .find_map(|flag| arg.strip_prefix(flag).and_then(|a| a.strip_prefix('=')))
+                    .map(ToOwned::to_owned)
+            }))
+            .collect()
+    }
 }
 
 pub type WastedFile = (String, u64);
 
+/// The rules which caused files to be considered waste.
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Provenance {
+    /// The name of the rule set used to classify files
+    pub rule_set: String,
+    /// If true, files were flagged by the exclude patterns in the crate manifest instead of the ones of the rule set
+    pub manifest_exclude: bool,
+    /// The exclude patterns which flagged at least one of the wasted files, in order of precedence
+    pub exclude: Patterns,
+    /// If true, the crate has a build script, which might have needed files not matched by any include pattern
+    #[serde(default)]
+    pub has_build_script: bool,
+}
+
+impl Provenance {
+    /// Return the exclude pattern which flagged the wasted file at `path`, or `None` if it was flagged
+    /// for not being matched by any include pattern of the rule set.
+    pub fn flagged_by(&self, path: &str) -> Option<&str> {
+        self.matcher()(path)
+    }
+
+    /// Like [`flagged_by()`][Provenance::flagged_by()], but more efficient when used for many paths.
+    pub fn matcher<'a>(&'a self) -> impl Fn(&str) -> Option<&'a str> + 'a {
+        let globs = globset_from_patterns(&self.exclude);
+        move |path| {
+            let path = std::path::Path::new(path);
+            let first_match = if self.manifest_exclude {
+                // Manifest excludes also apply to all files in excluded directories
+                path.ancestors()
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .flat_map(|p| globs.matches(p))
+                    .min()
+            } else {
+                globs.matches(path).into_iter().min()
+            };
+            first_match.map(|idx| self.exclude[idx].as_str())
+        }
+    }
+
+    /// Return why the wasted file at `path` is considered waste.
+    pub fn reason(&self, path: &str) -> Reason {
+        self.reason_from(self.flagged_by(path))
+    }
+
+    fn reason_from(&self, flagged_by: Option<&str>) -> Reason {
+        match flagged_by {
+            Some(pattern) if self.manifest_exclude => Reason::ExcludedByManifest {
+                pattern: pattern.to_owned(),
+            },
+            Some(pattern) => Reason::MatchesExclude {
+                pattern: pattern.to_owned(),
+            },
+            None if self.has_build_script => Reason::NotReferenced,
+            None => Reason::NotIncluded,
+        }
+    }
+}
+
+/// The reason for a file to be considered waste.
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Reason {
+    /// The file matches the given exclude pattern of the rule set
+    MatchesExclude { pattern: String },
+    /// The file matches the given exclude pattern of the crate manifest, which has no effect as include patterns are present
+    ExcludedByManifest { pattern: String },
+    /// The file isn't matched by any include pattern, nor is it referenced by `include_str!()` or `include_bytes!()`
+    NotIncluded,
+    /// Like [`NotIncluded`][Reason::NotIncluded], but the crate has a build script which doesn't reference the file either
+    NotReferenced,
+}
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::MatchesExclude { pattern } => write!(f, "matches '{}'", pattern),
+            Reason::ExcludedByManifest { pattern } => write!(
+                f,
+                "excluded by '{}' in Cargo.toml, which is ignored in presence of include patterns",
+                pattern
+            ),
+            Reason::NotIncluded => f.write_str("not included, nor referenced by include_str!()"),
+            Reason::NotReferenced => f.write_str("not included, nor referenced by the build script or include_str!()"),
+        }
+    }
+}
+
+/// A wasted file along with the reason for it to be considered waste.
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct ExplainedFile {
+    pub path: String,
+    pub size: u64,
+    /// The reason, or `None` if the report was created without recording it
+    pub reason: Option<Reason>,
+}
+
+/// All wasted files of a crate version along with the reason for them to be considered waste.
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Explanation {
+    pub crate_name: String,
+    pub crate_version: String,
+    /// The name of the rule set used to classify files, or `None` if there is no waste
+    pub rule_set: Option<String>,
+    pub wasted_files: Vec<ExplainedFile>,
+}
+
 #[derive(Default, Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
 pub struct AggregateFileInfo {
     pub total_bytes: u64,
@@ -175,9 +438,13 @@ impl std::ops::AddAssign for VersionInfo {
             waste,
             potential_gains,
             waste_latest_version,
+            compressed_waste_bytes,
+            downloaded_waste_bytes,
         } = rhs;
         self.all += all;
         self.waste += waste;
+        self.compressed_waste_bytes = add_optional_bytes(self.compressed_waste_bytes, compressed_waste_bytes);
+        self.downloaded_waste_bytes += downloaded_waste_bytes;
         self.potential_gains = add_optional_aggregate(self.potential_gains.clone(), potential_gains);
         self.waste_latest_version =
             add_named_optional_aggregate(self.waste_latest_version.clone(), waste_latest_version);
@@ -189,7 +456,7 @@ fn add_named_optional_aggregate(
     rhs: Option<(String, AggregateFileInfo)>,
 ) -> Option<(String, AggregateFileInfo)> {
     Some(match (lhs, rhs) {
-        (Some((lhs_name, lhs)), Some((rhs_name, _))) if lhs_name > rhs_name => (lhs_name, lhs),
+        (Some((lhs_name, lhs)), Some((rhs_name, _))) if is_later_version(&lhs_name, &rhs_name) => (lhs_name, lhs),
         (Some(_), Some((rhs_name, rhs))) => (rhs_name, rhs),
         (Some(v), None) => v,
         (None, Some(v)) => v,
@@ -197,6 +464,56 @@ fn add_named_optional_aggregate(
     })
 }
 
+/// Whether a version is a release, its numeric components, and the identifiers of its pre-release.
+type VersionOrder<'a> = (bool, Vec<u64>, Vec<(bool, u64, &'a str)>);
+
+/// The order of `version`, where pre-releases come before all releases as they aren't considered the latest version
+/// of a crate.
+/// Pre-release identifiers are compared numerically if they are numbers, which come before all other identifiers.
+fn version_order(version: &str) -> VersionOrder<'_> {
+    let version = version.split('+').next().unwrap_or(version);
+    let (core, pre_release) = match version.split_once('-') {
+        Some((core, pre_release)) => (core, Some(pre_release)),
+        None => (version, None),
+    };
+    (
+        pre_release.is_none(),
+        core.split('.').map(|n| n.parse().unwrap_or(0)).collect(),
+        pre_release
+            .into_iter()
+            .flat_map(|p| p.split('.'))
+            .map(|identifier| match identifier.parse() {
+                Ok(number) => (false, number, ""),
+                Err(_) => (true, 0, identifier),
+            })
+            .collect(),
+    )
+}
+
+/// Return true if `version` is later than `other`, with pre-releases being earlier than all releases.
+pub fn is_later_version(version: &str, other: &str) -> bool {
+    version_order(version) > version_order(other)
+}
+
+/// Return the latest version and its value in `by_version`, which is the latest pre-release only if there are no releases.
+pub fn latest_version<T>(by_version: &Dict<T>) -> Option<(&String, &T)> {
+    by_version.iter().reduce(|latest, candidate| {
+        if is_later_version(candidate.0, latest.0) {
+            candidate
+        } else {
+            latest
+        }
+    })
+}
+
+/// Add byte counts which are `None` if unknown, which is only the case for the sum if both are unknown.
+pub fn add_optional_bytes(lhs: Option<u64>, rhs: Option<u64>) -> Option<u64> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => Some(lhs + rhs),
+        (lhs, rhs) => lhs.or(rhs),
+    }
+}
+
 pub fn add_optional_aggregate(
     lhs: Option<AggregateFileInfo>,
     rhs: Option<AggregateFileInfo>,
@@ -218,6 +535,14 @@ pub struct VersionInfo {
     pub waste: AggregateFileInfo,
     pub waste_latest_version: Option<(String, AggregateFileInfo)>,
     pub potential_gains: Option<AggregateFileInfo>,
+    /// The estimated size of the `waste` in compressed crates, which is what each download of all versions would save,
+    /// as far as it is known, or `None` if it isn't known for any version
+    #[serde(default)]
+    pub compressed_waste_bytes: Option<u64>,
+    /// The `compressed_waste_bytes` of each version multiplied by its amount of downloads, as far as these are known.
+    /// Downloads are counted when a version is reported for the first time, and aren't updated later.
+    #[serde(default)]
+    pub downloaded_waste_bytes: u64,
 }
 
 pub type AggregateVersionInfo = VersionInfo;
@@ -225,6 +550,7 @@ pub type AggregateVersionInfo = VersionInfo;
 pub type Dict<T> = BTreeMap<String, T>;
 
 #[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+#[allow(clippy::large_enum_variant)] // most reports are version reports
 pub enum Report {
     Version {
         crate_name: String,
@@ -233,6 +559,23 @@ pub enum Report {
         total_files: u64,
         wasted_files: Vec<WastedFile>,
         suggested_fix: Option<Fix>,
+        /// The rules which flagged the `wasted_files`, or `None` if there is no waste
+        #[serde(default)]
+        provenance: Option<Provenance>,
+        /// Files needed to build the crate which wouldn't be packaged anymore if the `suggested_fix` was applied
+        #[serde(default)]
+        files_dropped_by_fix: Vec<String>,
+        /// A unified diff of the crate manifest implementing the `suggested_fix`, or `None` if there is no fix
+        /// or the manifest couldn't be edited
+        #[serde(default)]
+        manifest_patch: Option<String>,
+        /// The estimated size of the `wasted_files` in the compressed crate, which is what each download would save,
+        /// or `None` if the sizes of compressed files weren't estimated
+        #[serde(default)]
+        compressed_waste_bytes: Option<u64>,
+        /// How often this version was downloaded when the report was generated, if known
+        #[serde(default)]
+        downloads: Option<u64>,
     },
     Crate {
         crate_name: String,
@@ -240,12 +583,24 @@ pub enum Report {
         total_files: u64,
         info_by_version: Dict<VersionInfo>,
         wasted_by_extension: Dict<AggregateFileInfo>,
+        /// Snapshots of this report taken at previous reporting runs
+        #[serde(default)]
+        history: history::History,
+        /// Recent significant changes in waste between consecutive versions
+        #[serde(default)]
+        changes: changes::Changes,
     },
     CrateCollection {
         total_size_in_bytes: u64,
         total_files: u64,
         info_by_crate: Dict<AggregateVersionInfo>,
         wasted_by_extension: Dict<AggregateFileInfo>,
+        /// Snapshots of this report taken at previous reporting runs
+        #[serde(default)]
+        history: history::History,
+        /// Recent significant changes in waste between consecutive versions
+        #[serde(default)]
+        changes: changes::Changes,
     },
 }
 
@@ -257,38 +612,96 @@ fn remove_implicit_entries(entries: &mut Vec<TarHeader>) {
 }
 
 impl Report {
-    pub fn from_package(
+    /// Explain why each of the wasted files of a version report is considered waste, or return `None`
+    /// if this isn't a version report.
+    pub fn explain(&self) -> Option<Explanation> {
+        match self {
+            Report::Version {
+                crate_name,
+                crate_version,
+                wasted_files,
+                provenance,
+                ..
+            } => {
+                let flagged_by = provenance.as_ref().map(|p| (p, p.matcher()));
+                Some(Explanation {
+                    crate_name: crate_name.clone(),
+                    crate_version: crate_version.clone(),
+                    rule_set: provenance.as_ref().map(|p| p.rule_set.clone()),
+                    wasted_files: wasted_files
+                        .iter()
+                        .map(|(path, size)| ExplainedFile {
+                            path: path.clone(),
+                            size: *size,
+                            reason: flagged_by.as_ref().map(|(p, m)| p.reason_from(m(path))),
+                        })
+                        .collect(),
+                })
+            }
+            Report::Crate { .. } | Report::CrateCollection { .. } => None,
+        }
+    }
+
+    /// Create a report for the given crate `package`, classifying files using the builtin rules.
+    pub fn from_package(crate_name: &str, crate_version: &str, package: TarPackage) -> Report {
+        Self::from_package_with_rules(crate_name, crate_version, package, &Rules::builtin())
+    }
+
+    /// Create a report for the given crate `package`, classifying files using the given `rules`.
+    pub fn from_package_with_rules(
         crate_name: &str,
         crate_version: &str,
         TarPackage {
             mut entries_meta_data,
             entries,
         }: TarPackage,
+        rules: &Rules,
     ) -> Report {
         remove_implicit_entries(&mut entries_meta_data);
         let total_size_in_bytes = entries_meta_data.iter().map(|e| e.size).sum();
         let total_files = entries_meta_data.len() as u64;
         let cargo_config = Self::cargo_config_from_entries(&entries);
+        let required_files = validate::required_files(&cargo_config, &entries, &entries_meta_data);
+        let manifest = patch::original_manifest(&entries);
         let (includes, excludes, compile_time_includes, build_script_name) =
-            Self::cargo_config_into_includes_excludes(cargo_config, &entries, &entries_meta_data);
+            Self::cargo_config_into_includes_excludes(cargo_config, &entries, &entries_meta_data, rules);
+        let manifest_include = includes.clone();
+        let mut manifest_exclude = None;
+        let has_build_script = build_script_name.is_some();
         let (suggested_fix, wasted_files) = match (includes, excludes, build_script_name, compile_time_includes) {
             (Some(includes), Some(excludes), _presence_of_build_script_not_relevant, _) => {
+                manifest_exclude = Some(excludes.clone());
                 Self::compute_includes_from_includes_and_excludes(entries_meta_data, includes, excludes)
             }
             (Some(includes), None, build_script_name, _) => {
-                Self::enrich_includes(entries_meta_data, includes, build_script_name.is_some())
+                Self::enrich_includes(entries_meta_data, includes, build_script_name.is_some(), rules)
             }
             (None, Some(excludes), build_script_name, compile_time_includes) => Self::enrich_excludes(
                 entries_meta_data,
                 excludes,
                 compile_time_includes,
                 build_script_name.is_some(),
+                rules,
             ),
             (None, None, build_script_name, compile_time_includes) => {
-                Self::standard_includes(entries_meta_data, build_script_name, compile_time_includes)
+                Self::standard_includes(entries_meta_data, build_script_name, compile_time_includes, rules)
             }
         };
+        let compressed_waste_bytes = Self::compressed_size_of(&wasted_files);
         let wasted_files = Self::convert_to_wasted_files(wasted_files);
+        let provenance = Self::provenance_of(&wasted_files, rules, manifest_exclude, has_build_script);
+        let files_dropped_by_fix = suggested_fix
+            .as_ref()
+            .map(|fix| {
+                // cargo refuses to package a crate with invalid patterns, which drops all files
+                validate::files_dropped_by_fix(fix, manifest_include.as_ref(), &required_files)
+                    .unwrap_or_else(|_| required_files.clone())
+            })
+            .unwrap_or_default();
+        let manifest_patch = suggested_fix
+            .as_ref()
+            .zip(manifest)
+            .and_then(|(fix, manifest)| patch::manifest_patch(manifest, fix));
         Report::Version {
             crate_name: crate_name.into(),
             crate_version: crate_version.into(),
@@ -296,6 +709,11 @@ impl Report {
             total_files,
             wasted_files,
             suggested_fix,
+            provenance,
+            files_dropped_by_fix,
+            manifest_patch,
+            compressed_waste_bytes,
+            downloads: None,
         }
     }
 }
@@ -0,0 +1,169 @@
+//! A Markdown summary of reports, suitable for pasting into issues and pull requests.
+use crate::{Dict, Fix, Report, VersionInfo};
+use bytesize::ByteSize;
+use std::fmt::{self, Write};
+
+/// The amount of crates with the most waste to list in the summary of a crate collection
+const TOP_WASTEFUL_CRATES: usize = 100;
+
+/// Escape characters in `text` which would otherwise end a table cell.
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn write_patterns(out: &mut impl Write, field: &str, patterns: &[String]) -> fmt::Result {
+    let patterns: Vec<_> = patterns.iter().map(|p| format!("{:?}", p)).collect();
+    writeln!(out, "{} = [{}]", field, patterns.join(", "))
+}
+
+fn write_fix(out: &mut impl Write, fix: &Fix, manifest_patch: Option<&String>) -> fmt::Result {
+    writeln!(out, "### Suggested fix\n")?;
+    if let Some(patch) = manifest_patch {
+        return writeln!(out, "```diff\n{}```", patch);
+    }
+    writeln!(out, "In the `[package]` section of `Cargo.toml`:\n\n```toml")?;
+    match fix {
+        Fix::ImprovedInclude { include, .. } | Fix::NewInclude { include, .. } => {
+            write_patterns(out, "include", include)?
+        }
+        Fix::RemoveExcludeAndUseInclude { include, .. } => {
+            writeln!(out, "# remove the 'exclude' field")?;
+            write_patterns(out, "include", include)?
+        }
+        Fix::EnrichedExclude { exclude, .. } => write_patterns(out, "exclude", exclude)?,
+        Fix::RemoveExclude => writeln!(out, "# remove the 'exclude' field")?,
+    }
+    writeln!(out, "```")
+}
+
+fn write_summary_table(out: &mut impl Write, title: &str, info: &[(&String, &VersionInfo)]) -> fmt::Result {
+    writeln!(out, "| {} | Files | Size | Wasted files | Wasted size |", title)?;
+    writeln!(out, "|---|---:|---:|---:|---:|")?;
+    for (name, info) in info {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | {} |",
+            escape(name),
+            info.all.total_files,
+            ByteSize(info.all.total_bytes),
+            info.waste.total_files,
+            ByteSize(info.waste.total_bytes)
+        )?;
+    }
+    Ok(())
+}
+
+fn write_totals(
+    out: &mut impl Write,
+    total_files: u64,
+    total_size_in_bytes: u64,
+    info: &Dict<VersionInfo>,
+) -> fmt::Result {
+    let waste = info.values().fold(VersionInfo::default(), |mut acc, info| {
+        acc += info.clone();
+        acc
+    });
+    writeln!(
+        out,
+        "{} of {} files are waste, with {} of {} in total.\n",
+        waste.waste.total_files,
+        total_files,
+        ByteSize(waste.waste.total_bytes),
+        ByteSize(total_size_in_bytes)
+    )
+}
+
+impl Report {
+    /// Write a summary of this report into `out`, formatted as Markdown.
+    pub fn write_markdown(&self, out: &mut impl Write) -> fmt::Result {
+        match self {
+            Report::Version {
+                crate_name,
+                crate_version,
+                total_size_in_bytes,
+                total_files,
+                wasted_files,
+                suggested_fix,
+                files_dropped_by_fix,
+                manifest_patch,
+                compressed_waste_bytes,
+                ..
+            } => {
+                writeln!(out, "## Waste in {} {}\n", crate_name, crate_version)?;
+                if wasted_files.is_empty() {
+                    return writeln!(out, "No waste was found.");
+                }
+                write!(
+                    out,
+                    "{} of {} files are waste, with {} of {} in total",
+                    wasted_files.len(),
+                    total_files,
+                    ByteSize(wasted_files.iter().map(|(_, size)| size).sum()),
+                    ByteSize(*total_size_in_bytes)
+                )?;
+                match compressed_waste_bytes {
+                    Some(bytes) => writeln!(out, ", and each download would be {} smaller.\n", ByteSize(*bytes))?,
+                    None => writeln!(out, ".\n")?,
+                }
+                writeln!(out, "| File | Size | Reason |")?;
+                writeln!(out, "|---|---:|---|")?;
+                for file in self.explain().map(|e| e.wasted_files).unwrap_or_default() {
+                    writeln!(
+                        out,
+                        "| `{}` | {} | {} |",
+                        escape(&file.path),
+                        ByteSize(file.size),
+                        file.reason.map(|r| escape(&r.to_string())).unwrap_or_default()
+                    )?;
+                }
+                if let Some(fix) = suggested_fix {
+                    writeln!(out)?;
+                    write_fix(out, fix, manifest_patch.as_ref())?;
+                }
+                if !files_dropped_by_fix.is_empty() {
+                    writeln!(
+                        out,
+                        "\n**Warning**: the suggested fix would stop packaging these files needed to build the crate:\n"
+                    )?;
+                    for path in files_dropped_by_fix {
+                        writeln!(out, "* `{}`", escape(path))?;
+                    }
+                }
+                Ok(())
+            }
+            Report::Crate {
+                crate_name,
+                total_size_in_bytes,
+                total_files,
+                info_by_version,
+                ..
+            } => {
+                writeln!(out, "## Waste in all versions of {}\n", crate_name)?;
+                write_totals(out, *total_files, *total_size_in_bytes, info_by_version)?;
+                let versions: Vec<_> = info_by_version.iter().collect();
+                write_summary_table(out, "Version", &versions)
+            }
+            Report::CrateCollection {
+                total_size_in_bytes,
+                total_files,
+                info_by_crate,
+                ..
+            } => {
+                writeln!(out, "## Waste in all crates\n")?;
+                write_totals(out, *total_files, *total_size_in_bytes, info_by_crate)?;
+                let mut crates: Vec<_> = info_by_crate.iter().collect();
+                crates.sort_by_key(|(_, info)| std::cmp::Reverse(info.waste.total_bytes));
+                crates.truncate(TOP_WASTEFUL_CRATES);
+                writeln!(out, "The {} crates with the most waste:\n", crates.len())?;
+                write_summary_table(out, "Crate", &crates)
+            }
+        }
+    }
+
+    /// Return a summary of this report formatted as Markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        self.write_markdown(&mut out).expect("writing to a string never fails");
+        out
+    }
+}
@@ -0,0 +1,85 @@
+//! Apply a [`Fix`] to the manifest of a crate while preserving its formatting, to produce a patch crate authors can apply.
+use crate::{result::find_in_entries, Fix, TarHeader};
+use toml_edit::{Array, Document, Item, TableLike, Value};
+
+/// The name of the manifest as seen by authors, and as used in the header of patches.
+const MANIFEST_PATH: &str = "Cargo.toml";
+
+/// Return the manifest `cargo package` preserved as written by the author, or `None` if it isn't present.
+/// The normalized manifest isn't a substitute, as patches of it wouldn't apply to the manifest in the repository.
+pub(crate) fn original_manifest(entries_with_buffer: &[(TarHeader, Vec<u8>)]) -> Option<&str> {
+    find_in_entries(entries_with_buffer, &[], "Cargo.toml.orig")
+        .and_then(|(_e, buf)| buf)
+        .and_then(|buf| std::str::from_utf8(buf).ok())
+}
+
+/// Turn `patterns` into an array formatted like `previous`, which is spread across multiple lines if `previous` was.
+fn array_like(patterns: &[String], previous: Option<&Value>) -> Value {
+    let mut array: Array = patterns.iter().map(String::as_str).collect();
+    match previous {
+        Some(previous) if previous.to_string().contains('\n') => {
+            for value in array.iter_mut() {
+                value.decor_mut().set_prefix("\n    ");
+            }
+            array.set_trailing_comma(true);
+            array.set_trailing("\n");
+        }
+        _ => array.fmt(),
+    }
+    let mut value = Value::Array(array);
+    if let Some(previous) = previous {
+        *value.decor_mut() = previous.decor().clone();
+    }
+    value
+}
+
+fn set_patterns(package: &mut dyn TableLike, key: &str, patterns: &[String]) {
+    let value = Item::Value(array_like(patterns, package.get(key).and_then(Item::as_value)));
+    match package.get_mut(key) {
+        // assign the value only to keep comments and whitespace of the key
+        Some(previous) => *previous = value,
+        None => {
+            package.insert(key, value);
+        }
+    }
+}
+
+/// Return the given `manifest` with `fix` applied to its `include` and `exclude` fields, leaving everything else as is,
+/// or `None` if the manifest couldn't be parsed or has no package section.
+pub fn manifest_with_fix(manifest: &str, fix: &Fix) -> Option<String> {
+    let mut document: Document = manifest.parse().ok()?;
+    let package = document
+        .as_table_mut()
+        .iter_mut()
+        .find(|(key, _)| *key == "package" || *key == "project")
+        .and_then(|(_, item)| item.as_table_like_mut())?;
+    match fix {
+        Fix::ImprovedInclude { include, .. } | Fix::NewInclude { include, .. } => {
+            set_patterns(package, "include", include)
+        }
+        Fix::RemoveExcludeAndUseInclude { include, .. } => {
+            set_patterns(package, "include", include);
+            package.remove("exclude");
+        }
+        Fix::EnrichedExclude { exclude, .. } => set_patterns(package, "exclude", exclude),
+        Fix::RemoveExclude => {
+            package.remove("exclude");
+        }
+    }
+    Some(document.to_string())
+}
+
+/// Return a unified diff to turn the `manifest` into one with `fix` applied, to be applied with `git apply` or `patch -p1`
+/// from the directory containing the manifest, or `None` if the manifest couldn't be edited or wouldn't change.
+pub fn manifest_patch(manifest: &str, fix: &Fix) -> Option<String> {
+    let fixed = manifest_with_fix(manifest, fix)?;
+    if fixed == manifest {
+        return None;
+    }
+    Some(
+        similar::TextDiff::from_lines(manifest, &fixed)
+            .unified_diff()
+            .header(&format!("a/{}", MANIFEST_PATH), &format!("b/{}", MANIFEST_PATH))
+            .to_string(),
+    )
+}
@@ -1,5 +1,9 @@
-use super::{CargoConfig, Fix, Patterns, PotentialWaste, Report, TarHeader, WastedFile};
-use std::{collections::BTreeSet, path::Path, path::PathBuf};
+use super::{CargoConfig, Fix, Patterns, PotentialWaste, Provenance, Report, Rules, TarHeader, WastedFile};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    path::PathBuf,
+};
 
 lazy_static! {
     static ref COMPILE_TIME_INCLUDE: regex::bytes::Regex =
@@ -8,19 +12,12 @@ lazy_static! {
     static ref BUILD_SCRIPT_PATHS: regex::bytes::Regex =
         regex::bytes::Regex::new(r##""cargo:rerun-if-changed=(?P<path>.+?)"|"(?P<path_like>.+?)""##)
             .expect("valid statically known regex");
-    static ref STANDARD_EXCLUDES_GLOBSET: globset::GlobSet = globset_from_patterns(standard_exclude_patterns());
-    static ref STANDARD_EXCLUDE_MATCHERS: Vec<(&'static str, globset::GlobMatcher)> = standard_exclude_patterns()
-        .iter()
-        .cloned()
-        .map(|p| (p, make_glob(p).compile_matcher()))
-        .collect();
-    static ref STANDARD_INCLUDE_GLOBS: Vec<globset::Glob> =
-        standard_include_patterns().iter().map(|p| make_glob(p)).collect();
-    static ref STANDARD_INCLUDE_MATCHERS: Vec<(&'static str, globset::GlobMatcher)> = standard_include_patterns()
-        .iter()
-        .cloned()
-        .map(|p| (p, make_glob(p).compile_matcher()))
-        .collect();
+    static ref MOD_DECLARATION: regex::bytes::Regex = regex::bytes::Regex::new(
+        r##"(?m)^[ \t]*(?P<attributes>(?:#\[[^\]]*\]\s*)*)(?:pub(?:\([^)]*\))?\s+)?mod\s+(?:r#)?(?P<name>[A-Za-z_][A-Za-z0-9_]*)\s*;"##
+    )
+    .expect("valid statically known regex");
+    static ref PATH_ATTRIBUTE: regex::bytes::Regex =
+        regex::bytes::Regex::new(r##"#\[\s*path\s*=\s*"(?P<path>[^"]+)"\s*\]"##).expect("valid statically known regex");
 }
 
 pub fn tar_path_to_utf8_str(mut bytes: &[u8]) -> &str {
@@ -31,10 +28,6 @@ pub fn tar_path_to_utf8_str(mut bytes: &[u8]) -> &str {
     std::str::from_utf8(bytes).expect("valid utf8 paths in crate archive")
 }
 
-fn tar_path_to_path(bytes: &[u8]) -> &Path {
-    Path::new(tar_path_to_utf8_str(bytes))
-}
-
 fn tar_path_to_path_no_strip(bytes: &[u8]) -> &Path {
     Path::new(std::str::from_utf8(bytes).expect("valid utf8 paths in crate archive"))
 }
@@ -81,76 +74,11 @@ fn directories_of(entries: &[TarHeader]) -> Vec<TarHeader> {
             path: k.to_str().expect("utf8 paths").as_bytes().to_owned(),
             size: 0,
             entry_type: tar_directory_entry,
+            compressed_size: Some(0),
         })
         .collect()
 }
 
-fn standard_exclude_patterns() -> &'static [&'static str] {
-    &[
-        "**/*.jpg",
-        "**/*.jpeg",
-        "**/*.jpeg",
-        "**/*.png",
-        "**/*.gif",
-        "**/*.bmp",
-        "**/doc/**/*",
-        "**/docs/**/*",
-        "**/benches/**/*",
-        "**/benchmark/**/*",
-        "**/benchmarks/**/*",
-        "**/test/**/*",
-        "**/*_test.*",
-        "**/*_test/**/*",
-        "**/tests/**/*",
-        "**/*_tests.*",
-        "**/*_tests/**/*",
-        "**/testing/**/*",
-        "**/spec/**/*",
-        "**/*_spec.*",
-        "**/*_spec/**/*",
-        "**/specs/**/*",
-        "**/*_specs.*",
-        "**/*_specs/**/*",
-        "**/example/**/*",
-        "**/examples/**/*",
-        "**/target/**/*",
-        "**/build/**/*",
-        "**/out/**/*",
-        "**/tmp/**/*",
-        "**/etc/**/*",
-        "**/testdata/**/*",
-        "**/samples/**/*",
-        "**/assets/**/*",
-        "**/maps/**/*",
-        "**/media/**/*",
-        "**/fixtures/**/*",
-        "**/node_modules/**/*",
-    ]
-}
-
-fn standard_include_patterns() -> &'static [&'static str] {
-    &[
-        "src/**/*",
-        "Cargo.*",
-        "authors",
-        "AUTHORS",
-        "license.*",
-        "license-*",
-        "LICENSE.*",
-        "LICENSE-*",
-        "license",
-        "LICENSE",
-        "readme.*",
-        "README.*",
-        "readme",
-        "README",
-        "changelog.*",
-        "CHANGELOG.*",
-        "changelog",
-        "CHANGELOG",
-    ]
-}
-
 pub fn globset_from_patterns(patterns: impl IntoIterator<Item = impl AsRef<str>>) -> globset::GlobSet {
     let mut builder = globset::GlobSetBuilder::new();
     for pattern in patterns.into_iter() {
@@ -208,13 +136,113 @@ fn remove_implicit_includes(include_patterns: &mut Patterns, mut removed_include
     }
 }
 
-/// These input patterns are **file paths**, and we would like to make them into the smallest possible amount of **include patterns**.
-/// These patterns must not accidentally match the 'pattern_to_not_match', as it is the pattern they are supposed to replace.
-/// Note that one could also use negated patterns, so keep the 'pattern to not match', but add a specific negation.
-/// HELP WANTED: This could be done by finding the common ancestors and resolve to patterns that match their children most specifically
-/// See https://github.com/the-lean-crate/criner/issues/2
-fn turn_file_paths_into_patterns(added_include_patterns: Patterns, _pattern_to_not_match: &str) -> Patterns {
-    added_include_patterns
+/// Returns a function which is true for all paths matched by any of the `patterns` without being matched by one of their
+/// negations, i.e. `!pattern`.
+pub(crate) fn patterns_matcher(patterns: &[String]) -> impl Fn(&str) -> bool {
+    let (negated, patterns): (Vec<_>, Vec<_>) = patterns.iter().partition(|p| p.starts_with('!'));
+    let include = globset_from_patterns(patterns);
+    let exclude = globset_from_patterns(negated.into_iter().map(|p| &p[1..]));
+    move |path| include.is_match(path) && !exclude.is_match(path)
+}
+
+/// Turn the `file_paths` into the smallest amount of **include patterns** we can find, which must match all of them
+/// but none of the paths in `excluded` and `others`.
+/// To keep the amount of patterns low, directories can be included as a whole while negating the `excluded` paths within them.
+/// Paths in `others` are never negated, as these may legitimately be matched by other include patterns.
+pub(crate) fn turn_file_paths_into_patterns(file_paths: Vec<&str>, excluded: &[&str], others: &[&str]) -> Patterns {
+    let patterns = patterns_for_directory("", &file_paths, excluded, others, true);
+    debug_assert!(
+        {
+            let is_match = patterns_matcher(&patterns);
+            file_paths.iter().all(|p| is_match(p)) && !excluded.iter().chain(others.iter()).any(|p| is_match(p))
+        },
+        "patterns must match exactly the given file paths"
+    );
+    patterns
+}
+
+/// Find patterns to match all `targets` within `directory` (or the crate root if empty), but none of `negatable` or `fixed`,
+/// all of which are paths within `directory`. Only `negatable` paths may be excluded using negated patterns.
+fn patterns_for_directory(
+    directory: &str,
+    targets: &[&str],
+    negatable: &[&str],
+    fixed: &[&str],
+    allow_negation: bool,
+) -> Patterns {
+    if targets.is_empty() {
+        return Vec::new();
+    }
+    let directory_pattern = || format!("{}/**/*", globset::escape(directory));
+    if !directory.is_empty() && negatable.is_empty() && fixed.is_empty() {
+        return vec![match targets {
+            [single_file] => globset::escape(single_file),
+            _ => directory_pattern(),
+        }];
+    }
+
+    let (prefix_len, escaped_prefix) = if directory.is_empty() {
+        (0, String::new())
+    } else {
+        (directory.len() + 1, format!("{}/", globset::escape(directory)))
+    };
+    let mut by_child_directory = BTreeMap::<&str, (Vec<&str>, Vec<&str>, Vec<&str>)>::new();
+    let mut files_by_extension = BTreeMap::<Option<&str>, Vec<&str>>::new();
+    for (set_index, paths) in [targets, negatable, fixed].iter().enumerate() {
+        for path in paths.iter() {
+            match path[prefix_len..].find('/') {
+                Some(pos) => {
+                    let sets = by_child_directory.entry(&path[..prefix_len + pos]).or_default();
+                    match set_index {
+                        0 => &mut sets.0,
+                        1 => &mut sets.1,
+                        _ => &mut sets.2,
+                    }
+                    .push(path)
+                }
+                None if set_index == 0 => files_by_extension
+                    .entry(Path::new(path).extension().and_then(|e| e.to_str()))
+                    .or_default()
+                    .push(path),
+                None => {}
+            }
+        }
+    }
+
+    let mut patterns = Vec::new();
+    for (child_directory, (targets, negatable, fixed)) in by_child_directory.iter() {
+        patterns.extend(patterns_for_directory(
+            child_directory,
+            targets,
+            negatable,
+            fixed,
+            allow_negation,
+        ));
+    }
+    for (extension, files) in files_by_extension {
+        match extension {
+            Some(extension) if files.len() > 1 => {
+                let pattern = format!("{}*.{}", escaped_prefix, globset::escape(extension));
+                let glob = make_glob(&pattern).compile_matcher();
+                if negatable.iter().chain(fixed.iter()).any(|p| glob.is_match(p)) {
+                    patterns.extend(files.into_iter().map(globset::escape));
+                } else {
+                    patterns.push(pattern);
+                }
+            }
+            _ => patterns.extend(files.into_iter().map(globset::escape)),
+        }
+    }
+
+    if allow_negation && !directory.is_empty() && fixed.is_empty() {
+        let negations = patterns_for_directory(directory, negatable, &[], targets, false);
+        if negations.len() + 1 < patterns.len() {
+            patterns = std::iter::once(directory_pattern())
+                .chain(negations.into_iter().map(|p| format!("!{}", p)))
+                .collect();
+        }
+    }
+    patterns
 }
 
 fn find_include_patterns_that_incorporate_exclude_patterns(
@@ -225,21 +253,21 @@ fn find_include_patterns_that_incorporate_exclude_patterns(
     let mut added_include_patterns = Vec::new();
     let mut removed_include_patterns = Vec::new();
     let mut all_include_patterns = Vec::with_capacity(include_patterns.len());
+    let excluded: Vec<_> = entries_to_exclude
+        .iter()
+        .filter(|e| entry_is_file(e.entry_type))
+        .map(|e| tar_path_to_utf8_str(&e.path))
+        .collect();
     for pattern in include_patterns {
         let glob = make_glob(&pattern);
         let include = glob.compile_matcher();
-        if entries_to_exclude
-            .iter()
-            .any(|e| include.is_match(tar_path_to_path(&e.path)))
-        {
-            let added_includes = turn_file_paths_into_patterns(
-                entries_to_include
-                    .iter()
-                    .filter(|e| include.is_match(tar_path_to_path(&e.path)))
-                    .map(|e| tar_path_to_utf8_str(&e.path).to_string())
-                    .collect(),
-                &pattern,
-            );
+        if excluded.iter().any(|p| include.is_match(p)) {
+            let (file_paths, others): (Vec<_>, Vec<_>) = entries_to_include
+                .iter()
+                .filter(|e| entry_is_file(e.entry_type))
+                .map(|e| tar_path_to_utf8_str(&e.path))
+                .partition(|p| include.is_match(p));
+            let added_includes = turn_file_paths_into_patterns(file_paths, &excluded, &others);
             removed_include_patterns.push(pattern);
             added_include_patterns.extend(added_includes.clone().into_iter());
             all_include_patterns.extend(added_includes.into_iter());
@@ -252,17 +280,20 @@ fn find_include_patterns_that_incorporate_exclude_patterns(
     (all_include_patterns, added_include_patterns, removed_include_patterns)
 }
 
-fn make_glob(pattern: &str) -> globset::Glob {
+pub(crate) fn try_make_glob(pattern: &str) -> Result<globset::Glob, globset::Error> {
     globset::GlobBuilder::new(pattern)
         .literal_separator(false)
         .case_insensitive(false)
         .backslash_escape(true) // most paths in crates.io are forward slashes, there are only 214 or so with backslashes
         .build()
-        .expect("valid include patterns")
+}
+
+pub(crate) fn make_glob(pattern: &str) -> globset::Glob {
+    try_make_glob(pattern).expect("valid include patterns")
 }
 
 fn simplify_includes<'a>(
-    include_patterns_and_matchers: impl Iterator<Item = &'a (&'a str, globset::GlobMatcher)>,
+    include_patterns_and_matchers: impl Iterator<Item = &'a (String, globset::GlobMatcher)>,
     mut entries: Vec<TarHeader>,
 ) -> Patterns {
     let mut out_patterns = Vec::new();
@@ -281,7 +312,7 @@ fn simplify_includes<'a>(
                 out_patterns.push(matched[0].clone());
             }
             _ => {
-                out_patterns.push((*pattern).to_string());
+                out_patterns.push(pattern.to_owned());
             }
         }
         entries.retain(|e| !matched.iter().any(|p| p == tar_path_to_utf8_str(&e.path)));
@@ -290,7 +321,7 @@ fn simplify_includes<'a>(
     out_patterns
 }
 
-fn find_in_entries<'buffer>(
+pub(crate) fn find_in_entries<'buffer>(
     entries_with_buffer: &'buffer [(TarHeader, Vec<u8>)],
     entries: &[TarHeader],
     name: &str,
@@ -317,7 +348,7 @@ fn find_in_entries<'buffer>(
 
 fn matches_in_set_a_but_not_in_set_b(
     mut patterns_to_amend: Patterns,
-    set_a: &[(&str, globset::GlobMatcher)],
+    set_a: &[(String, globset::GlobMatcher)],
     set_b: &globset::GlobSet,
     mut entries: Vec<TarHeader>,
 ) -> (Vec<TarHeader>, Patterns, Patterns) {
@@ -330,12 +361,12 @@ fn matches_in_set_a_but_not_in_set_b(
                 break;
             }
             if set_b.is_empty() {
-                patterns_to_amend.push((*pattern_a).to_string());
+                patterns_to_amend.push(pattern_a.to_owned());
                 continue;
             }
 
             if entries.iter().any(|e| set_b.is_match(tar_path_to_utf8_str(&e.path))) {
-                patterns_to_amend.push((*pattern_a).to_string());
+                patterns_to_amend.push(pattern_a.to_owned());
             }
         }
     }
@@ -395,19 +426,20 @@ fn simplify_standard_excludes_and_match_against_standard_includes(
     potential_waste: Vec<TarHeader>,
     existing_exclude: Patterns,
     compile_time_include: Option<Patterns>,
+    rules: &Rules,
 ) -> (Vec<TarHeader>, Patterns, Patterns) {
     let compile_time_include = compile_time_include.unwrap_or_default();
     let include_iter =
-        globset_from_globs_and_patterns(&STANDARD_INCLUDE_GLOBS, compile_time_include.iter().map(|s| s.as_str()));
+        globset_from_globs_and_patterns(&rules.include_globs, compile_time_include.iter().map(|s| s.as_str()));
     matches_in_set_a_but_not_in_set_b(
         existing_exclude,
-        &STANDARD_EXCLUDE_MATCHERS,
+        &rules.exclude_matchers,
         &include_iter,
         potential_waste,
     )
 }
 
-fn included_paths_of(file: Option<(TarHeader, Option<&[u8]>)>) -> Vec<String> {
+pub(crate) fn included_paths_of(file: Option<(TarHeader, Option<&[u8]>)>) -> Vec<String> {
     file.and_then(|(header, maybe_data)| maybe_data.map(|d| (header, d)))
         .map(|(header, data)| {
             COMPILE_TIME_INCLUDE
@@ -423,13 +455,81 @@ fn included_paths_of(file: Option<(TarHeader, Option<&[u8]>)>) -> Vec<String> {
         .unwrap_or_default()
 }
 
+/// Return only the lines of the Rust source file `data` which are needed to find the files it refers to,
+/// that is `mod` declarations, `#[path]` attributes and compile-time includes.
+pub fn rust_source_outline(data: &[u8]) -> Vec<u8> {
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+    data.split(|b| *b == b'\n')
+        .filter(|line| contains(line, b"mod ") || contains(line, b"#[path") || contains(line, b"include_"))
+        .flat_map(|line| line.iter().copied().chain(std::iter::once(b'\n')))
+        .collect()
+}
+
+/// Follow `mod` declarations and `#[path]` attributes starting at the crate `root` and return the paths of all
+/// Rust source files that are reachable that way, except for the root itself. Only files present in the crate
+/// are returned, and files we don't have the content of are not followed.
+/// Inline modules are not considered, thus their file modules are not found unless they use a `#[path]`.
+pub(crate) fn reachable_source_files(
+    entries_with_buffer: &[(TarHeader, Vec<u8>)],
+    entries: &[TarHeader],
+    root: &str,
+    seen: &mut BTreeSet<String>,
+) -> Vec<String> {
+    let mut out = Vec::new();
+    // (path, is_mod_rs) - root files and those loaded via #[path] own their directory, just like mod.rs files
+    let mut to_visit = vec![(root.to_owned(), true)];
+    while let Some((path, is_mod_rs)) = to_visit.pop() {
+        let data = match find_in_entries(entries_with_buffer, entries, &path) {
+            Some((_, Some(data))) => data,
+            _ => continue,
+        };
+        for cap in MOD_DECLARATION.captures_iter(data) {
+            let name = std::str::from_utf8(&cap["name"]).expect("ascii module name");
+            let candidates = match PATH_ATTRIBUTE.captures(&cap["attributes"]) {
+                Some(attr) => match std::str::from_utf8(&attr["path"]) {
+                    Ok(relative_path) => vec![(to_crate_relative_path(&path, relative_path), true)],
+                    Err(_) => continue,
+                },
+                None => {
+                    let file = Path::new(&path);
+                    let directory = if is_mod_rs {
+                        file.parent().expect("file in directory").to_owned()
+                    } else {
+                        file.with_extension("")
+                    };
+                    vec![
+                        (directory.join(format!("{}.rs", name)), false),
+                        (directory.join(name).join("mod.rs"), true),
+                    ]
+                    .into_iter()
+                    .map(|(p, is_mod_rs)| (p.to_str().expect("utf8 only").to_owned(), is_mod_rs))
+                    .collect()
+                }
+            };
+            for (candidate, is_mod_rs) in candidates {
+                if candidate == root || seen.contains(&candidate) {
+                    continue;
+                }
+                if entries.iter().any(|e| tar_path_to_utf8_str(&e.path) == candidate) {
+                    seen.insert(candidate.clone());
+                    out.push(candidate.clone());
+                    to_visit.push((candidate, is_mod_rs));
+                }
+            }
+        }
+    }
+    out
+}
+
 /// HELP WANTED find the largest common ancestors (e.g. curl/* for curl/foo/* and curl/bar/*) and return these
 /// instead of the ones they contain. This can help speeding up later use of the patterns, as there are less of them.
 fn optimize_directories(dir_patterns: Vec<String>) -> Vec<String> {
     dir_patterns
 }
 
-fn find_paths_mentioned_in_build_script(build: Option<(TarHeader, Option<&[u8]>)>) -> Vec<String> {
+pub(crate) fn find_paths_mentioned_in_build_script(build: Option<(TarHeader, Option<&[u8]>)>) -> Vec<String> {
     build
         .and_then(|(header, maybe_data)| maybe_data.map(|d| (header, d)))
         .map(|(_, data)| {
@@ -479,9 +579,27 @@ fn find_paths_mentioned_in_build_script(build: Option<(TarHeader, Option<&[u8]>)
         .unwrap_or_default()
 }
 
-fn potential_negated_includes(entries: Vec<TarHeader>, patters_to_avoid: globset::GlobSet) -> Option<PotentialWaste> {
-    let (entries_we_would_remove, patterns, _) =
-        matches_in_set_a_but_not_in_set_b(Vec::new(), &STANDARD_EXCLUDE_MATCHERS, &patters_to_avoid, entries);
+/// `required` are paths of files that must not be matched by negated includes.
+fn potential_negated_includes(
+    entries: Vec<TarHeader>,
+    patters_to_avoid: globset::GlobSet,
+    required: &[&str],
+    rules: &Rules,
+) -> Option<PotentialWaste> {
+    let (mut entries_we_would_remove, mut patterns, _) =
+        matches_in_set_a_but_not_in_set_b(Vec::new(), &rules.exclude_matchers, &patters_to_avoid, entries);
+    if patterns.iter().any(|p| {
+        required
+            .iter()
+            .any(|path| make_glob(p).compile_matcher().is_match(path))
+    }) {
+        patterns.retain(|p| {
+            let glob = make_glob(p).compile_matcher();
+            !required.iter().any(|path| glob.is_match(path))
+        });
+        entries_we_would_remove =
+            split_to_matched_and_unmatched(entries_we_would_remove, &globset_from_patterns(&patterns)).0;
+    }
     let negated_patterns: Vec<_> = patterns.into_iter().map(|s| format!("!{}", s)).collect();
     if negated_patterns.is_empty() {
         None
@@ -493,9 +611,11 @@ fn potential_negated_includes(entries: Vec<TarHeader>, patters_to_avoid: globset
     }
 }
 
-fn add_to_includes_if_non_default(file_path: &str, include: &mut Patterns) {
+fn add_to_includes_if_non_default(file_path: &str, include: &mut Patterns, rules: &Rules) {
     let recursive_pattern = Path::new(file_path).parent().expect("file path as input").join("**");
-    if !standard_include_patterns().contains(&recursive_pattern.join("*").to_str().expect("utf8 only")) {
+    let recursive_pattern_str = recursive_pattern.join("*");
+    let recursive_pattern_str = recursive_pattern_str.to_str().expect("utf8 only");
+    if !rules.include().iter().any(|p| p == recursive_pattern_str) {
         include.push(recursive_pattern.join("*.rs").to_str().expect("utf 8 only").to_string())
     }
 }
@@ -513,6 +633,37 @@ impl Report {
             .unwrap_or_default()
     }
 
+    pub(crate) fn provenance_of(
+        wasted_files: &[WastedFile],
+        rules: &Rules,
+        manifest_exclude: Option<Patterns>,
+        has_build_script: bool,
+    ) -> Option<Provenance> {
+        if wasted_files.is_empty() {
+            return None;
+        }
+        let mut provenance = Provenance {
+            rule_set: rules.name().to_owned(),
+            manifest_exclude: manifest_exclude.is_some(),
+            exclude: manifest_exclude.unwrap_or_else(|| rules.exclude().to_vec()),
+            has_build_script,
+        };
+        let used_patterns: BTreeSet<_> = {
+            let flagged_by = provenance.matcher();
+            wasted_files
+                .iter()
+                .filter_map(|(path, _)| flagged_by(path).map(ToOwned::to_owned))
+                .collect()
+        };
+        provenance.exclude.retain(|p| used_patterns.contains(p));
+        Some(provenance)
+    }
+
+    /// Return the estimated size of all `entries` after compression, or `None` if it isn't known for one of them.
+    pub(crate) fn compressed_size_of(entries: &[TarHeader]) -> Option<u64> {
+        entries.iter().map(|e| e.compressed_size).sum()
+    }
+
     pub(crate) fn convert_to_wasted_files(entries: Vec<TarHeader>) -> Vec<WastedFile> {
         entries
             .into_iter()
@@ -524,6 +675,7 @@ impl Report {
         entries: Vec<TarHeader>,
         build_script_name: Option<String>,
         compile_time_include: Option<Patterns>,
+        rules: &Rules,
     ) -> (Option<Fix>, Vec<TarHeader>) {
         let mut compile_time_include = compile_time_include.unwrap_or_default();
         let has_build_script = match build_script_name {
@@ -534,22 +686,30 @@ impl Report {
             None => false,
         };
         let include_globs =
-            globset_from_globs_and_patterns(&STANDARD_INCLUDE_GLOBS, compile_time_include.iter().map(|s| s.as_str()));
+            globset_from_globs_and_patterns(&rules.include_globs, compile_time_include.iter().map(|s| s.as_str()));
         let (included_entries, excluded_entries) = split_to_matched_and_unmatched(entries, &include_globs);
 
         let compile_time_include_matchers: Vec<_> = compile_time_include
             .iter()
-            .map(|s| (s.as_str(), make_glob(s).compile_matcher()))
+            .map(|s| (s.to_owned(), make_glob(s).compile_matcher()))
             .collect();
         let include_patterns = simplify_includes(
-            STANDARD_INCLUDE_MATCHERS
+            rules
+                .include_matchers
                 .iter()
                 .chain(compile_time_include_matchers.iter()),
             included_entries.clone(),
         );
+        let literal_compile_time_include: Vec<_> = compile_time_include
+            .iter()
+            .map(|p| p.as_str())
+            .filter(|p| !p.contains(['*', '?', '[', '{', '\\']))
+            .collect();
         let potential = potential_negated_includes(
             included_entries,
             globset_from_patterns(non_greedy_patterns(&compile_time_include)),
+            &literal_compile_time_include,
+            rules,
         );
 
         if excluded_entries.is_empty() && potential.is_none() {
@@ -607,6 +767,7 @@ impl Report {
         entries: Vec<TarHeader>,
         mut include: Patterns,
         has_build_script: bool,
+        rules: &Rules,
     ) -> (Option<Fix>, Vec<TarHeader>) {
         let mut include_removed = Vec::new();
         remove_implicit_includes(&mut include, &mut include_removed);
@@ -615,8 +776,12 @@ impl Report {
             if include_removed.is_empty() {
                 None
             } else {
-                let potential =
-                    potential_negated_includes(entries, globset_from_patterns(non_greedy_patterns(&include)));
+                let potential = potential_negated_includes(
+                    entries,
+                    globset_from_patterns(non_greedy_patterns(&include)),
+                    &[],
+                    rules,
+                );
                 Some(Fix::ImprovedInclude {
                     include,
                     include_removed,
@@ -633,12 +798,14 @@ impl Report {
         exclude: Patterns,
         compile_time_include: Option<Patterns>,
         has_build_script: bool,
+        rules: &Rules,
     ) -> (Option<Fix>, Vec<TarHeader>) {
-        let (potential_waste, _remaining) = split_to_matched_and_unmatched(entries, &STANDARD_EXCLUDES_GLOBSET);
+        let (potential_waste, _remaining) = split_to_matched_and_unmatched(entries, &rules.exclude_globset);
         let (wasted_files, exclude, exclude_added) = simplify_standard_excludes_and_match_against_standard_includes(
             potential_waste,
             exclude,
             compile_time_include,
+            rules,
         );
         if wasted_files.is_empty() {
             (None, Vec::new())
@@ -662,30 +829,86 @@ impl Report {
         config: CargoConfig,
         entries_with_buffer: &[(TarHeader, Vec<u8>)],
         entries: &[TarHeader],
+        rules: &Rules,
     ) -> (Option<Patterns>, Option<Patterns>, Option<Patterns>, Option<String>) {
         let mut maybe_build_script_path = config.build_script_path().map(|s| s.to_owned());
         let compile_time_includes = {
             let mut includes_parsed_from_files = Vec::new();
-            includes_parsed_from_files.extend(included_paths_of(find_in_entries(
-                entries_with_buffer,
-                entries,
-                config.lib_path(),
-            )));
-            add_to_includes_if_non_default(config.lib_path(), &mut includes_parsed_from_files);
-            for path in config.bin_paths() {
+            let is_in_crate = |path: &str| entries.iter().any(|e| tar_path_to_utf8_str(&e.path) == path);
+            let roots: Vec<_> = std::iter::once(config.lib_path().to_owned())
+                .chain(config.bin_paths())
+                .filter(|path| is_in_crate(path))
+                .collect();
+            for path in &roots {
                 includes_parsed_from_files.extend(included_paths_of(find_in_entries(
                     entries_with_buffer,
                     entries,
                     path,
                 )));
-                add_to_includes_if_non_default(path, &mut includes_parsed_from_files);
+                add_to_includes_if_non_default(path, &mut includes_parsed_from_files, rules);
+            }
+
+            let mut seen = BTreeSet::new();
+            let mut modules = Vec::new();
+            for root in &roots {
+                modules.extend(reachable_source_files(entries_with_buffer, entries, root, &mut seen));
+            }
+            // Declared targets matching our excludes are expected to be removed along with their declaration,
+            // all others are kept along with the modules they use.
+            let required_targets: Vec<_> = config
+                .declared_target_paths()
+                .into_iter()
+                .filter(|path| !rules.exclude_globset.is_match(path) && is_in_crate(path))
+                .collect();
+            for target in &required_targets {
+                includes_parsed_from_files.extend(included_paths_of(find_in_entries(
+                    entries_with_buffer,
+                    entries,
+                    target,
+                )));
+                modules.extend(reachable_source_files(entries_with_buffer, entries, target, &mut seen));
+            }
+            let required_files: Vec<_> = required_targets
+                .into_iter()
+                .chain(
+                    config
+                        .referenced_file_paths()
+                        .into_iter()
+                        .filter(|path| is_in_crate(path)),
+                )
+                .collect();
+            if !modules.is_empty() || !required_files.is_empty() {
+                let included = globset_from_globs_and_patterns(
+                    &rules.include_globs,
+                    includes_parsed_from_files
+                        .iter()
+                        .filter(|p| try_make_glob(p).is_ok())
+                        .collect::<Vec<_>>(),
+                );
+                for path in modules {
+                    includes_parsed_from_files.extend(included_paths_of(find_in_entries(
+                        entries_with_buffer,
+                        entries,
+                        &path,
+                    )));
+                    if !included.is_match(&path) {
+                        includes_parsed_from_files.push(path);
+                    }
+                }
+                for path in required_files {
+                    if !included.is_match(&path) && try_make_glob(&path).is_ok() {
+                        includes_parsed_from_files.push(path);
+                    }
+                }
             }
 
-            let build_script_name = config.actual_or_expected_build_script_path();
-            let maybe_data = find_in_entries(entries_with_buffer, entries, build_script_name);
-            maybe_build_script_path =
-                maybe_build_script_path.or_else(|| maybe_data.as_ref().map(|_| build_script_name.to_owned()));
-            includes_parsed_from_files.extend(find_paths_mentioned_in_build_script(maybe_data));
+            if !config.build_script_disabled() {
+                let build_script_name = config.actual_or_expected_build_script_path();
+                let maybe_data = find_in_entries(entries_with_buffer, entries, build_script_name);
+                maybe_build_script_path =
+                    maybe_build_script_path.or_else(|| maybe_data.as_ref().map(|_| build_script_name.to_owned()));
+                includes_parsed_from_files.extend(find_paths_mentioned_in_build_script(maybe_data));
+            }
 
             if includes_parsed_from_files.is_empty() {
                 None
@@ -0,0 +1,451 @@
+//! Rules to classify files in a crate as needed or as waste, along with a way to configure them in a TOML file.
+//!
+//! A rules file contains named rule sets, each of which is based on another rule set or the `builtin` one,
+//! along with per-crate overrides:
+//!
+//! ```toml
+//! # The rule set to use for all crates without override, `builtin` if unset
+//! default = "team"
+//!
+//! [sets.team]
+//! remove_exclude = ["**/assets/**/*"]
+//!
+//! [sets.gui]
+//! extends = "team"
+//! remove_exclude = ["**/media/**/*"]
+//!
+//! [crates.egui]
+//! set = "gui"
+//! add_exclude = ["media/screenshots/*"]
+//! ```
+use crate::{
+    result::{make_glob, try_make_glob},
+    Dict, Patterns,
+};
+use serde_derive::Deserialize;
+use std::{collections::BTreeSet, fmt, sync::Arc};
+
+/// The name of the rule set which is always available and used if nothing else is configured.
+pub const BUILTIN_RULE_SET: &str = "builtin";
+
+lazy_static! {
+    static ref BUILTIN_RULES: Arc<Rules> = Arc::new(
+        Rules::new(
+            BUILTIN_RULE_SET.into(),
+            builtin_include_patterns().iter().map(|p| p.to_string()).collect(),
+            builtin_exclude_patterns().iter().map(|p| p.to_string()).collect()
+        )
+        .expect("valid builtin patterns")
+    );
+}
+
+fn builtin_exclude_patterns() -> &'static [&'static str] {
+    &[
+        "**/*.jpg",
+        "**/*.jpeg",
+        "**/*.png",
+        "**/*.gif",
+        "**/*.bmp",
+        "**/doc/**/*",
+        "**/docs/**/*",
+        "**/benches/**/*",
+        "**/benchmark/**/*",
+        "**/benchmarks/**/*",
+        "**/test/**/*",
+        "**/*_test.*",
+        "**/*_test/**/*",
+        "**/tests/**/*",
+        "**/*_tests.*",
+        "**/*_tests/**/*",
+        "**/testing/**/*",
+        "**/spec/**/*",
+        "**/*_spec.*",
+        "**/*_spec/**/*",
+        "**/specs/**/*",
+        "**/*_specs.*",
+        "**/*_specs/**/*",
+        "**/example/**/*",
+        "**/examples/**/*",
+        "**/target/**/*",
+        "**/build/**/*",
+        "**/out/**/*",
+        "**/tmp/**/*",
+        "**/etc/**/*",
+        "**/testdata/**/*",
+        "**/samples/**/*",
+        "**/assets/**/*",
+        "**/maps/**/*",
+        "**/media/**/*",
+        "**/fixtures/**/*",
+        "**/node_modules/**/*",
+    ]
+}
+
+fn builtin_include_patterns() -> &'static [&'static str] {
+    &[
+        "src/**/*",
+        "Cargo.*",
+        "authors",
+        "AUTHORS",
+        "license.*",
+        "license-*",
+        "LICENSE.*",
+        "LICENSE-*",
+        "license",
+        "LICENSE",
+        "readme.*",
+        "README.*",
+        "readme",
+        "README",
+        "changelog.*",
+        "CHANGELOG.*",
+        "changelog",
+        "CHANGELOG",
+    ]
+}
+
+/// An error occurring when loading a [`RuleBook`].
+#[derive(Debug)]
+pub enum Error {
+    /// The rules file isn't valid TOML or has unknown fields
+    Toml(toml::de::Error),
+    /// A rule set that is referred to doesn't exist
+    UnknownRuleSet { name: String, referenced_by: String },
+    /// Rule sets extend each other in a cycle
+    Cycle { name: String },
+    /// A rule set uses a name that is reserved, like the one of the builtin rule set
+    ReservedRuleSet { name: String },
+    /// A pattern isn't a valid glob
+    InvalidPattern { pattern: String, source: globset::Error },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Toml(err) => write!(f, "Could not parse rules file: {}", err),
+            Error::UnknownRuleSet { name, referenced_by } => {
+                write!(
+                    f,
+                    "Rule set '{}' referenced by '{}' does not exist",
+                    name, referenced_by
+                )
+            }
+            Error::Cycle { name } => write!(f, "Rule set '{}' extends itself, directly or indirectly", name),
+            Error::ReservedRuleSet { name } => write!(
+                f,
+                "Rule set '{}' is reserved and can't be defined - define a rule set extending it instead",
+                name
+            ),
+            Error::InvalidPattern { pattern, source } => write!(f, "Invalid pattern '{}': {}", pattern, source),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Toml(err) => Some(err),
+            Error::InvalidPattern { source, .. } => Some(source),
+            Error::UnknownRuleSet { .. } | Error::Cycle { .. } | Error::ReservedRuleSet { .. } => None,
+        }
+    }
+}
+
+/// Patterns to match files that are needed for building a crate, and patterns to match files that are most certainly waste.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    name: String,
+    include: Patterns,
+    exclude: Patterns,
+    pub(crate) include_globs: Vec<globset::Glob>,
+    pub(crate) include_matchers: Vec<(String, globset::GlobMatcher)>,
+    pub(crate) exclude_matchers: Vec<(String, globset::GlobMatcher)>,
+    pub(crate) exclude_globset: globset::GlobSet,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::builtin().as_ref().clone()
+    }
+}
+
+impl Rules {
+    /// Create a new rule set named `name` from the given `include` and `exclude` patterns.
+    pub fn new(name: String, include: Patterns, exclude: Patterns) -> Result<Self, Error> {
+        let compile = |patterns: &Patterns| -> Result<Vec<(String, globset::GlobMatcher)>, Error> {
+            patterns
+                .iter()
+                .map(|p| {
+                    try_make_glob(p)
+                        .map(|glob| (p.clone(), glob.compile_matcher()))
+                        .map_err(|source| Error::InvalidPattern {
+                            pattern: p.clone(),
+                            source,
+                        })
+                })
+                .collect()
+        };
+        let include_matchers = compile(&include)?;
+        let exclude_matchers = compile(&exclude)?;
+        Ok(Rules {
+            name,
+            include_globs: include.iter().map(|p| make_glob(p)).collect(),
+            exclude_globset: crate::globset_from_patterns(&exclude),
+            include,
+            exclude,
+            include_matchers,
+            exclude_matchers,
+        })
+    }
+
+    /// The rules used if nothing else is configured.
+    pub fn builtin() -> Arc<Rules> {
+        BUILTIN_RULES.clone()
+    }
+
+    /// The name of the rule set these rules stem from.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Patterns matching files which are typically needed to build a crate.
+    pub fn include(&self) -> &[String] {
+        &self.include
+    }
+
+    /// Patterns matching files which are most certainly not needed to build a crate.
+    pub fn exclude(&self) -> &[String] {
+        &self.exclude
+    }
+
+    fn amended(&self, name: String, amendment: &Amendment) -> Result<Self, Error> {
+        let amend = |patterns: &[String], add: &Patterns, remove: &Patterns| -> Patterns {
+            let remove: BTreeSet<_> = remove.iter().collect();
+            patterns
+                .iter()
+                .chain(add.iter())
+                .filter(|p| !remove.contains(p))
+                .cloned()
+                .collect()
+        };
+        Rules::new(
+            name,
+            amend(&self.include, &amendment.add_include, &amendment.remove_include),
+            amend(&self.exclude, &amendment.add_exclude, &amendment.remove_exclude),
+        )
+    }
+}
+
+/// The 64 bit FNV-1a hash, which unlike the hasher of the standard library is guaranteed not to change.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl std::hash::Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Patterns to add to or remove from the rules they amend
+struct Amendment {
+    add_include: Patterns,
+    remove_include: Patterns,
+    add_exclude: Patterns,
+    remove_exclude: Patterns,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSetDefinition {
+    /// The rule set to start from, `builtin` if unset
+    extends: Option<String>,
+    /// If set, replace the include patterns of the rule set we extend
+    include: Option<Patterns>,
+    /// If set, replace the exclude patterns of the rule set we extend
+    exclude: Option<Patterns>,
+    #[serde(default)]
+    add_include: Patterns,
+    #[serde(default)]
+    remove_include: Patterns,
+    #[serde(default)]
+    add_exclude: Patterns,
+    #[serde(default)]
+    remove_exclude: Patterns,
+}
+
+impl RuleSetDefinition {
+    fn amendment(&self) -> Amendment {
+        Amendment {
+            add_include: self.add_include.clone(),
+            remove_include: self.remove_include.clone(),
+            add_exclude: self.add_exclude.clone(),
+            remove_exclude: self.remove_exclude.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CrateOverride {
+    /// The rule set to use for this crate instead of the default one
+    set: Option<String>,
+    #[serde(default)]
+    add_include: Patterns,
+    #[serde(default)]
+    remove_include: Patterns,
+    #[serde(default)]
+    add_exclude: Patterns,
+    #[serde(default)]
+    remove_exclude: Patterns,
+}
+
+#[derive(Default, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    default: Option<String>,
+    #[serde(default)]
+    sets: Dict<RuleSetDefinition>,
+    #[serde(default)]
+    crates: Dict<CrateOverride>,
+}
+
+/// Named rule sets and the rules to use per crate, typically loaded from a TOML file.
+#[derive(Debug, Clone)]
+pub struct RuleBook {
+    default: Arc<Rules>,
+    sets: Dict<Arc<Rules>>,
+    crates: Dict<Arc<Rules>>,
+}
+
+impl Default for RuleBook {
+    fn default() -> Self {
+        let builtin = Rules::builtin();
+        RuleBook {
+            default: builtin.clone(),
+            sets: std::iter::once((BUILTIN_RULE_SET.to_owned(), builtin)).collect(),
+            crates: Default::default(),
+        }
+    }
+}
+
+impl RuleBook {
+    /// Parse a rules file in TOML format, resolving all rule sets and crate overrides.
+    pub fn from_toml(input: &str) -> Result<Self, Error> {
+        let RulesFile { default, sets, crates } = toml::from_str(input).map_err(Error::Toml)?;
+        if sets.contains_key(BUILTIN_RULE_SET) {
+            return Err(Error::ReservedRuleSet {
+                name: BUILTIN_RULE_SET.into(),
+            });
+        }
+        let mut book = RuleBook::default();
+        for name in sets.keys() {
+            book.resolve(name, &sets, &mut Vec::new())?;
+        }
+        if let Some(name) = default {
+            book.default = book.set(&name, "default")?;
+        }
+        for (
+            crate_name,
+            CrateOverride {
+                set,
+                add_include,
+                remove_include,
+                add_exclude,
+                remove_exclude,
+            },
+        ) in crates
+        {
+            let amendment = Amendment {
+                add_include,
+                remove_include,
+                add_exclude,
+                remove_exclude,
+            };
+            let base = match set {
+                Some(name) => book.set(&name, &crate_name)?,
+                None => book.default.clone(),
+            };
+            let rules = base.amended(format!("{}+{}", base.name(), crate_name), &amendment)?;
+            book.crates.insert(crate_name, Arc::new(rules));
+        }
+        Ok(book)
+    }
+
+    /// Read and parse the rules file at `path`.
+    pub fn from_path(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let input = std::fs::read_to_string(path)?;
+        Self::from_toml(&input).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    /// The rules to use for the crate named `crate_name`.
+    pub fn rules_for(&self, crate_name: &str) -> &Rules {
+        self.crates.get(crate_name).unwrap_or(&self.default)
+    }
+
+    /// A hash of the rules used for all crates, which changes whenever a crate might be classified differently.
+    /// It's stable across runs and platforms of the same pointer width.
+    pub fn fingerprint(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = Fnv1a::default();
+        let rules = std::iter::once((None, &self.default)).chain(self.crates.iter().map(|(n, r)| (Some(n), r)));
+        for (crate_name, rules) in rules {
+            crate_name.hash(&mut hasher);
+            rules.name.hash(&mut hasher);
+            rules.include.hash(&mut hasher);
+            rules.exclude.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    fn set(&self, name: &str, referenced_by: &str) -> Result<Arc<Rules>, Error> {
+        self.sets.get(name).cloned().ok_or_else(|| Error::UnknownRuleSet {
+            name: name.to_owned(),
+            referenced_by: referenced_by.to_owned(),
+        })
+    }
+
+    fn resolve(
+        &mut self,
+        name: &str,
+        definitions: &Dict<RuleSetDefinition>,
+        stack: &mut Vec<String>,
+    ) -> Result<Arc<Rules>, Error> {
+        if let Some(rules) = self.sets.get(name) {
+            return Ok(rules.clone());
+        }
+        if stack.iter().any(|n| n == name) {
+            return Err(Error::Cycle { name: name.to_owned() });
+        }
+        let definition = definitions.get(name).ok_or_else(|| Error::UnknownRuleSet {
+            name: name.to_owned(),
+            referenced_by: stack.last().cloned().unwrap_or_default(),
+        })?;
+        stack.push(name.to_owned());
+        let base = self.resolve(
+            definition.extends.as_deref().unwrap_or(BUILTIN_RULE_SET),
+            definitions,
+            stack,
+        )?;
+        stack.pop();
+
+        let base = Rules::new(
+            name.to_owned(),
+            definition.include.clone().unwrap_or_else(|| base.include.clone()),
+            definition.exclude.clone().unwrap_or_else(|| base.exclude.clone()),
+        )?;
+        let rules = Arc::new(base.amended(name.to_owned(), &definition.amendment())?);
+        self.sets.insert(name.to_owned(), rules.clone());
+        Ok(rules)
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{
+    badge::{Badge, BadgeThresholds},
+    Report, VersionInfo,
+};
+
+#[test]
+fn badges_depend_on_the_waste_percentage_and_thresholds() {
+    let thresholds = BadgeThresholds::default();
+    assert_eq!(Badge::new(0, 0, &thresholds), Badge::Lean, "empty crates are lean");
+    assert_eq!(
+        Badge::new(10, 1000, &thresholds),
+        Badge::Lean,
+        "the lean threshold is inclusive"
+    );
+    assert_eq!(Badge::new(100, 1000, &thresholds), Badge::Waste { percent: 10.0 });
+    assert_eq!(Badge::new(300, 1000, &thresholds), Badge::Wasteful { percent: 30.0 });
+    assert_eq!(
+        Badge::new(
+            100,
+            1000,
+            &BadgeThresholds {
+                lean: 10.0,
+                wasteful: 20.0
+            }
+        ),
+        Badge::Lean
+    );
+
+    let svg = Badge::new(300, 1000, &thresholds).to_svg();
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.contains(">30% waste</text>"));
+    assert!(Badge::Lean.to_svg().contains(">lean</text>"));
+}
+
+#[test]
+fn crate_badges_are_based_on_the_latest_version() {
+    let info = |total_bytes, wasted_bytes| {
+        let mut info = VersionInfo::default();
+        info.all.total_bytes = total_bytes;
+        info.waste.total_bytes = wasted_bytes;
+        info
+    };
+    let report = Report::Crate {
+        crate_name: "a".into(),
+        total_size_in_bytes: 0,
+        total_files: 0,
+        info_by_version: vec![
+            ("0.10.0".to_string(), info(100, 0)),
+            ("0.9.0".to_string(), info(100, 50)),
+            ("0.11.0-alpha.1".to_string(), info(100, 100)),
+        ]
+        .into_iter()
+        .collect(),
+        wasted_by_extension: Default::default(),
+        history: Default::default(),
+        changes: Default::default(),
+    };
+    assert_eq!(
+        report.badge(&BadgeThresholds::default()),
+        Some(Badge::Lean),
+        "pre-releases aren't the latest version"
+    );
+}
+
+#[test]
+fn thresholds_of_lean_crates_must_not_be_above_the_ones_of_wasteful_crates() {
+    assert_eq!(
+        BadgeThresholds::new(5.0, 5.0),
+        Ok(BadgeThresholds {
+            lean: 5.0,
+            wasteful: 5.0
+        })
+    );
+    assert!(BadgeThresholds::new(30.0, 25.0).is_err());
+    assert!(BadgeThresholds::new(f64::NAN, 25.0).is_err());
+}
@@ -0,0 +1,90 @@
+use crate::changes::{merge, Change, ChangeKind, RECENT_CHANGES};
+
+fn change(crate_name: &str, version: &str, time: u64) -> Change {
+    Change {
+        crate_name: crate_name.into(),
+        version: version.into(),
+        previous_version: "0.1.0".into(),
+        time,
+        kind: ChangeKind::Improved,
+        total_bytes: 100,
+        wasted_bytes: 10,
+        previous_total_bytes: 100,
+        previous_wasted_bytes: 50,
+    }
+}
+
+#[test]
+fn changes_are_significant_if_they_cross_the_lean_threshold_or_differ_enough() {
+    assert_eq!(ChangeKind::new(30.0, 1.0, 1.0), Some(ChangeKind::BecameLean));
+    assert_eq!(ChangeKind::new(1.5, 1.0, 1.0), Some(ChangeKind::BecameLean));
+    assert_eq!(ChangeKind::new(1.0, 1.5, 1.0), Some(ChangeKind::Regressed));
+    assert_eq!(ChangeKind::new(10.0, 15.0, 1.0), Some(ChangeKind::Regressed));
+    assert_eq!(ChangeKind::new(15.0, 10.0, 1.0), Some(ChangeKind::Improved));
+    assert_eq!(ChangeKind::new(15.0, 11.0, 1.0), None);
+    assert_eq!(ChangeKind::new(0.0, 1.0, 1.0), None, "staying lean isn't a change");
+}
+
+#[test]
+fn merged_changes_keep_the_most_recent_change_of_each_version_first() {
+    assert_eq!(
+        merge(
+            vec![change("a", "1.0.0", 3), change("a", "0.9.0", 1)],
+            vec![change("b", "1.0.0", 2), change("a", "1.0.0", 4)]
+        ),
+        vec![
+            change("a", "1.0.0", 4),
+            change("b", "1.0.0", 2),
+            change("a", "0.9.0", 1)
+        ]
+    );
+
+    let many = (0..RECENT_CHANGES as u64 + 10)
+        .map(|time| change("a", &format!("1.0.{}", time), time))
+        .collect();
+    let merged = merge(many, Vec::new());
+    assert_eq!(merged.len(), RECENT_CHANGES);
+    assert_eq!(merged[0].time, RECENT_CHANGES as u64 + 9);
+}
+
+#[cfg(feature = "html")]
+#[test]
+fn new_versions_are_compared_to_the_version_before_them() {
+    use crate::{Report, VersionInfo};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let krate = |versions: &[(&str, u64)]| Report::Crate {
+        crate_name: "a".into(),
+        total_size_in_bytes: 0,
+        total_files: 0,
+        info_by_version: versions
+            .iter()
+            .map(|(version, wasted_bytes)| {
+                let mut info = VersionInfo::default();
+                info.all.total_bytes = 100;
+                info.waste.total_bytes = *wasted_bytes;
+                (version.to_string(), info)
+            })
+            .collect(),
+        wasted_by_extension: Default::default(),
+        history: Default::default(),
+        changes: Default::default(),
+    };
+    let previous = krate(&[("0.9.0", 50), ("0.10.0", 40)]);
+    let mut new_versions = krate(&[("0.11.0", 0), ("0.12.0", 2), ("0.12.1", 4)]);
+    new_versions.record_changes(&previous, 1.0, UNIX_EPOCH + Duration::from_secs(42));
+
+    let changes: Vec<_> = new_versions
+        .changes()
+        .iter()
+        .map(|c| (c.previous_version.as_str(), c.version.as_str(), c.kind, c.time))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            ("0.10.0", "0.11.0", ChangeKind::BecameLean, 42),
+            ("0.11.0", "0.12.0", ChangeKind::Regressed, 42),
+        ],
+        "0.12.1 isn't significantly different from 0.12.0, and previous versions are never compared"
+    );
+}
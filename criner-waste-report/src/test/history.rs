@@ -0,0 +1,77 @@
+use crate::{
+    history::{merge, record, Snapshot},
+    AggregateFileInfo, Report, VersionInfo,
+};
+use std::time::{Duration, UNIX_EPOCH};
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn snapshot(time: u64, wasted_bytes: u64) -> Snapshot {
+    Snapshot {
+        time,
+        total_bytes: 100,
+        wasted_bytes,
+        potential_gains_bytes: 0,
+        children: 1,
+    }
+}
+
+#[test]
+fn histories_keep_the_latest_snapshot_of_each_day_in_order() {
+    let mut history = vec![snapshot(DAY, 10)];
+    record(&mut history, snapshot(DAY + 60, 20));
+    assert_eq!(history, vec![snapshot(DAY + 60, 20)], "same day, so it's replaced");
+    record(&mut history, snapshot(2 * DAY, 30));
+    assert_eq!(history, vec![snapshot(DAY + 60, 20), snapshot(2 * DAY, 30)]);
+
+    assert_eq!(
+        merge(
+            vec![snapshot(3 * DAY, 3), snapshot(5 * DAY, 5)],
+            vec![snapshot(DAY, 1), snapshot(3 * DAY + 1, 4)]
+        ),
+        vec![snapshot(DAY, 1), snapshot(3 * DAY + 1, 4), snapshot(5 * DAY, 5)]
+    );
+}
+
+#[test]
+fn crate_reports_record_snapshots_of_their_totals() {
+    let info = |wasted_bytes, gained_bytes| VersionInfo {
+        waste: AggregateFileInfo {
+            total_bytes: wasted_bytes,
+            total_files: 1,
+        },
+        potential_gains: Some(AggregateFileInfo {
+            total_bytes: gained_bytes,
+            total_files: 1,
+        }),
+        ..Default::default()
+    };
+    let mut report = Report::Crate {
+        crate_name: "a".into(),
+        total_size_in_bytes: 1000,
+        total_files: 10,
+        info_by_version: vec![("1.0.0".to_string(), info(10, 1)), ("1.1.0".into(), info(20, 2))]
+            .into_iter()
+            .collect(),
+        wasted_by_extension: Default::default(),
+        history: vec![snapshot(DAY, 5)],
+        changes: vec![],
+    };
+    report.record_snapshot(UNIX_EPOCH + Duration::from_secs(2 * DAY));
+    match report {
+        Report::Crate { history, .. } => assert_eq!(
+            history,
+            vec![
+                snapshot(DAY, 5),
+                Snapshot {
+                    time: 2 * DAY,
+                    total_bytes: 1000,
+                    wasted_bytes: 30,
+                    potential_gains_bytes: 3,
+                    children: 2,
+                }
+            ]
+        ),
+        _ => unreachable!("crate report"),
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{
+    history::Snapshot,
+    html::{downloaded_waste_ranking, trend_chart, SearchEntry},
+    AggregateFileInfo, Dict, Report, VersionInfo,
+};
+
+fn info(compressed_waste_bytes: Option<u64>, downloaded_waste_bytes: u64) -> VersionInfo {
+    VersionInfo {
+        compressed_waste_bytes,
+        downloaded_waste_bytes,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn children_are_ranked_by_the_waste_of_all_of_their_downloads() {
+    let info_by_child: Dict<_> = vec![
+        ("a".to_string(), info(Some(100), 100)),
+        ("b".into(), info(Some(1), 5000)),
+        ("c".into(), info(Some(500), 0)),
+        ("d".into(), info(None, 0)),
+        ("e".into(), info(Some(10), 2000)),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        downloaded_waste_ranking(&info_by_child, usize::MAX),
+        vec![
+            ("b".to_string(), 5000, Some(1)),
+            ("e".into(), 2000, Some(10)),
+            ("a".into(), 100, Some(100)),
+        ],
+        "children without downloaded waste aren't ranked, no matter their compressed waste"
+    );
+    assert_eq!(
+        downloaded_waste_ranking(&info_by_child, 1),
+        vec![("b".to_string(), 5000, Some(1))]
+    );
+}
+
+fn bytes(total_bytes: u64) -> AggregateFileInfo {
+    AggregateFileInfo {
+        total_bytes,
+        total_files: 1,
+    }
+}
+
+#[test]
+fn the_search_index_has_an_entry_per_crate_of_a_crate_collection() -> Result<(), Box<dyn std::error::Error>> {
+    let report = Report::CrateCollection {
+        total_size_in_bytes: 300,
+        total_files: 3,
+        info_by_crate: vec![
+            (
+                "a".to_string(),
+                VersionInfo {
+                    all: bytes(200),
+                    waste: bytes(50),
+                    waste_latest_version: Some(("1.0.0".into(), bytes(20))),
+                    potential_gains: Some(bytes(10)),
+                    compressed_waste_bytes: Some(5),
+                    downloaded_waste_bytes: 500,
+                },
+            ),
+            (
+                "b".into(),
+                VersionInfo {
+                    all: bytes(100),
+                    ..Default::default()
+                },
+            ),
+        ]
+        .into_iter()
+        .collect(),
+        wasted_by_extension: Default::default(),
+        history: Default::default(),
+        changes: Default::default(),
+    };
+
+    let mut out = Vec::new();
+    assert!(report.write_search_index(&mut out)?);
+    let script = String::from_utf8(out)?;
+    let index = script
+        .strip_prefix("window.CRINER_SEARCH_INDEX = ")
+        .and_then(|s| s.strip_suffix(";\n"))
+        .expect("the index is assigned to a global for use by the page");
+    assert_eq!(
+        serde_json::from_str::<Vec<SearchEntry>>(index)?,
+        vec![
+            SearchEntry("a".into(), 200, 50, Some(20), 10, 500),
+            SearchEntry("b".into(), 100, 0, None, 0, 0),
+        ]
+    );
+    assert!(
+        index.starts_with(r#"[["a",200,50,20,10,500],"#),
+        "entries are arrays to keep the index small: {}",
+        index
+    );
+    Ok(())
+}
+
+#[test]
+fn there_is_no_search_index_for_crates_and_versions() -> std::io::Result<()> {
+    let report = Report::Crate {
+        crate_name: "a".into(),
+        total_size_in_bytes: 0,
+        total_files: 0,
+        info_by_version: Default::default(),
+        wasted_by_extension: Default::default(),
+        history: Default::default(),
+        changes: Default::default(),
+    };
+    let mut out = Vec::new();
+    assert!(!report.write_search_index(&mut out)?);
+    assert!(out.is_empty());
+    Ok(())
+}
+
+fn snapshot(day: u64, total_bytes: u64, wasted_bytes: u64, children: u64) -> Snapshot {
+    Snapshot {
+        time: day * 24 * 60 * 60,
+        total_bytes,
+        wasted_bytes,
+        potential_gains_bytes: 0,
+        children,
+    }
+}
+
+#[test]
+fn trend_charts_show_snapshots_in_order_of_time() {
+    let chart = trend_chart(&[snapshot(2, 100, 10, 1), snapshot(1, 200, 20, 2)], "crates", None);
+    assert!(
+        chart.contains(">1970-01-02</text>") && chart.contains(">1970-01-03</text>"),
+        "the earliest snapshot comes first: {}",
+        chart
+    );
+    assert!(chart.contains("<title>1970-01-02: 20 B wasted of 200 B in 2 crates, 0 B potentially gained</title>"));
+    assert!(
+        chart.contains(r#"points="70,10 590,95""#),
+        "the line starts at the earliest snapshot on the left: {}",
+        chart
+    );
+}
+
+#[test]
+fn trend_charts_per_child_show_the_average_of_a_child() {
+    let chart = trend_chart(
+        &[snapshot(1, 100, 10, 1), snapshot(2, 400, 40, 4)],
+        "versions",
+        Some("version"),
+    );
+    assert!(chart
+        .contains("<title>1970-01-03: 10 B wasted of 100 B per version in 4 versions, 0 B potentially gained</title>"));
+    assert!(
+        chart.contains(r#"points="70,10 590,10""#),
+        "adding versions of the same size doesn't change the chart: {}",
+        chart
+    );
+}
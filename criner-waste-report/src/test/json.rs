@@ -0,0 +1,89 @@
+use super::from_package::synthetic_package;
+use crate::{
+    json::{Document, FixDocument, ReportDocument, WastedFileDocument, SCHEMA_VERSION},
+    AggregateFileInfo, Report, VersionInfo,
+};
+
+#[test]
+fn version_reports_have_a_versioned_schema_which_round_trips() {
+    let report = Report::from_package(
+        "a",
+        "1.0.0",
+        synthetic_package(
+            &[("Cargo.toml", "[package]\nname = \"a\"\n"), ("src/lib.rs", "")],
+            &["tests/big.bin"],
+        ),
+    );
+    let document = report.to_json_document();
+    assert_eq!(document.schema_version, SCHEMA_VERSION);
+    match &document.report {
+        ReportDocument::Version(version) => {
+            assert_eq!(version.wasted_size_in_bytes, 10);
+            assert_eq!(
+                version.wasted_files,
+                vec![WastedFileDocument {
+                    path: "tests/big.bin".into(),
+                    size_in_bytes: 10,
+                    reason: Some("matches '**/tests/**/*'".into()),
+                }]
+            );
+            assert_eq!(
+                version.suggested_fix,
+                Some(FixDocument {
+                    include: Some(vec!["src/lib.rs".into()]),
+                    exclude: None,
+                    remove_exclude: false,
+                    has_build_script: false,
+                })
+            );
+        }
+        _ => unreachable!("version document"),
+    }
+
+    let mut json = Vec::new();
+    report.write_json(&mut json).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(value["schema_version"], SCHEMA_VERSION);
+    assert_eq!(value["kind"], "version");
+    assert_eq!(serde_json::from_value::<Document>(value).unwrap(), document);
+}
+
+#[test]
+fn crate_reports_list_their_versions() {
+    let info = |wasted_bytes| VersionInfo {
+        all: AggregateFileInfo {
+            total_bytes: 100,
+            total_files: 2,
+        },
+        waste: AggregateFileInfo {
+            total_bytes: wasted_bytes,
+            total_files: 1,
+        },
+        ..Default::default()
+    };
+    let report = Report::Crate {
+        crate_name: "a".into(),
+        total_size_in_bytes: 200,
+        total_files: 4,
+        info_by_version: vec![("1.0.0".to_string(), info(10)), ("1.1.0".into(), info(20))]
+            .into_iter()
+            .collect(),
+        wasted_by_extension: Default::default(),
+        history: vec![],
+        changes: vec![],
+    };
+    match report.to_json_document().report {
+        ReportDocument::Crate(krate) => {
+            assert_eq!(krate.crate_name, "a");
+            assert_eq!(
+                krate
+                    .versions
+                    .iter()
+                    .map(|v| (v.name.as_str(), v.wasted_size_in_bytes))
+                    .collect::<Vec<_>>(),
+                vec![("1.0.0", 10), ("1.1.0", 20)]
+            );
+        }
+        _ => unreachable!("crate document"),
+    }
+}
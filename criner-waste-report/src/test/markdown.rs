@@ -0,0 +1,60 @@
+use crate::{Fix, Report};
+
+fn version_report(wasted_files: Vec<(String, u64)>, manifest_patch: Option<String>) -> Report {
+    Report::Version {
+        crate_name: "a".into(),
+        crate_version: "1.0.0".into(),
+        total_size_in_bytes: 2000,
+        total_files: 3,
+        wasted_files,
+        suggested_fix: Some(Fix::NewInclude {
+            include: vec!["src/**/*".into()],
+            has_build_script: false,
+        }),
+        provenance: None,
+        files_dropped_by_fix: vec!["build|data.txt".into()],
+        manifest_patch,
+        compressed_waste_bytes: Some(500),
+        downloads: None,
+    }
+}
+
+#[test]
+fn version_summaries_list_wasted_files_and_the_fix() {
+    assert_eq!(
+        version_report(vec![("tests/big.bin".into(), 1000)], None).to_markdown(),
+        [
+            "## Waste in a 1.0.0",
+            "",
+            "1 of 3 files are waste, with 1.0 KB of 2.0 KB in total, and each download would be 500 B smaller.",
+            "",
+            "| File | Size | Reason |",
+            "|---|---:|---|",
+            "| `tests/big.bin` | 1.0 KB |  |",
+            "",
+            "### Suggested fix",
+            "",
+            "In the `[package]` section of `Cargo.toml`:",
+            "",
+            "```toml",
+            "include = [\"src/**/*\"]",
+            "```",
+            "",
+            "**Warning**: the suggested fix would stop packaging these files needed to build the crate:",
+            "",
+            "* `build\\|data.txt`",
+            "",
+        ]
+        .join("\n")
+    );
+    assert!(
+        version_report(vec![("a.bin".into(), 1)], Some("--- a/Cargo.toml\n".into()))
+            .to_markdown()
+            .contains("```diff\n--- a/Cargo.toml\n```\n"),
+        "patches are preferred over patterns"
+    );
+    assert_eq!(
+        version_report(vec![], None).to_markdown(),
+        "## Waste in a 1.0.0\n\nNo waste was found.\n"
+    );
+}
@@ -1 +1,16 @@
+#[cfg(feature = "html")]
+mod badge;
+mod changes;
 mod from_package;
+mod history;
+#[cfg(feature = "html")]
+mod html;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "markdown")]
+mod markdown;
+mod patch;
+mod patterns;
+mod reachability;
+mod rules;
+mod validate;
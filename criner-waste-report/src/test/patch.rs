@@ -0,0 +1,148 @@
+use super::{
+    from_package::{synthetic_package, tar_package},
+    patterns::fixture_names,
+};
+use crate::{
+    patch::{manifest_patch, manifest_with_fix},
+    CargoConfig, Fix, Report,
+};
+
+const MANIFEST: &str = r#"[package]
+name = "a"
+version = "1.0.0"
+# keep the crate small
+exclude = [
+    "assets/*", # big pictures
+]
+include = ["src/**/*"]
+
+[dependencies]
+"#;
+
+fn patterns(patterns: &[&str]) -> Vec<String> {
+    patterns.iter().map(|p| p.to_string()).collect()
+}
+
+#[test]
+fn fixes_edit_the_manifest_in_place_and_preserve_formatting() {
+    assert_eq!(
+        manifest_with_fix(
+            MANIFEST,
+            &Fix::EnrichedExclude {
+                exclude: patterns(&["assets/*", "tests/**/*"]),
+                exclude_added: patterns(&["tests/**/*"]),
+                has_build_script: false
+            }
+        )
+        .unwrap(),
+        r#"[package]
+name = "a"
+version = "1.0.0"
+# keep the crate small
+exclude = [
+    "assets/*",
+    "tests/**/*",
+]
+include = ["src/**/*"]
+
+[dependencies]
+"#
+    );
+    assert_eq!(
+        manifest_with_fix(
+            MANIFEST,
+            &Fix::RemoveExcludeAndUseInclude {
+                include_added: patterns(&["README.md"]),
+                include: patterns(&["src/**/*", "README.md"]),
+                include_removed: vec![]
+            }
+        )
+        .unwrap(),
+        r#"[package]
+name = "a"
+version = "1.0.0"
+include = ["src/**/*", "README.md"]
+
+[dependencies]
+"#
+    );
+    assert_eq!(manifest_with_fix("[dependencies]", &Fix::RemoveExclude), None);
+    assert_eq!(manifest_patch("[package]\nname = \"a\"\n", &Fix::RemoveExclude), None);
+}
+
+#[test]
+fn patches_are_unified_diffs_of_the_original_manifest() {
+    let manifest_patch = |files: &[(&str, &str)]| match Report::from_package(
+        "a",
+        "1.0.0",
+        synthetic_package(files, &["README.md", "tests/data.bin"]),
+    ) {
+        Report::Version { manifest_patch, .. } => manifest_patch,
+        _ => unreachable!("version report"),
+    };
+    let normalized = (
+        "Cargo.toml",
+        "# generated by cargo\n[package]\nname = \"a\"\nversion = \"1.0.0\"\n",
+    );
+    let files = [
+        normalized,
+        (
+            "Cargo.toml.orig",
+            "[package]\nname = \"a\"\nversion = \"1.0.0\"\n\n[dependencies]\n",
+        ),
+        ("src/lib.rs", ""),
+    ];
+    assert_eq!(
+        manifest_patch(&files).unwrap(),
+        [
+            "--- a/Cargo.toml",
+            "+++ b/Cargo.toml",
+            "@@ -1,5 +1,6 @@",
+            " [package]",
+            " name = \"a\"",
+            " version = \"1.0.0\"",
+            "+include = [\"src/lib.rs\", \"README.md\"]",
+            " ",
+            " [dependencies]",
+            "",
+        ]
+        .join("\n")
+    );
+    assert_eq!(
+        manifest_patch(&[normalized, ("src/lib.rs", "")]),
+        None,
+        "patches of the normalized manifest wouldn't apply to the one of the author"
+    );
+}
+
+#[test]
+fn patched_manifests_contain_exactly_the_suggested_patterns() {
+    for name in fixture_names() {
+        let package = tar_package(&name);
+        let manifest = package
+            .entries
+            .iter()
+            .find(|(h, _)| h.path.ends_with(b"/Cargo.toml"))
+            .map(|(_, buf)| String::from_utf8(buf.clone()).unwrap())
+            .unwrap();
+        let fix = match Report::from_package("a", "1", package) {
+            Report::Version {
+                suggested_fix: Some(fix),
+                ..
+            } => fix,
+            _ => continue,
+        };
+        let package = CargoConfig::from(manifest_with_fix(&manifest, &fix).unwrap().as_str())
+            .package
+            .unwrap();
+        let (include, exclude) = match fix {
+            Fix::ImprovedInclude { include, .. }
+            | Fix::NewInclude { include, .. }
+            | Fix::RemoveExcludeAndUseInclude { include, .. } => (Some(include), None),
+            Fix::EnrichedExclude { exclude, .. } => (package.include.clone(), Some(exclude)),
+            Fix::RemoveExclude => (package.include.clone(), None),
+        };
+        assert_eq!(package.include, include, "{}", name);
+        assert_eq!(package.exclude, exclude, "{}", name);
+    }
+}
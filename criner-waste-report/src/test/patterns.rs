@@ -0,0 +1,132 @@
+use super::from_package::tar_package;
+use crate::result::{patterns_matcher, tar_path_to_utf8_str, turn_file_paths_into_patterns};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+pub(super) fn fixture_names() -> Vec<String> {
+    let mut names: Vec<_> = std::fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"))
+        .unwrap()
+        .map(|e| {
+            let file_name = e.unwrap().file_name();
+            let file_name = file_name.to_str().unwrap();
+            file_name.strip_suffix(".package.rmp").unwrap().to_owned()
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Class {
+    Target,
+    Excluded,
+    Other,
+}
+
+/// Assign all paths below the same directory at `depth` to the same class, to mimic how includes and excludes
+/// usually affect whole directories.
+fn classify(path: &str, seed: u64, depth: usize) -> Class {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    let mut components = path.split('/');
+    let num_components = path.split('/').count();
+    for component in components.by_ref().take(depth.min(num_components)) {
+        component.hash(&mut hasher);
+    }
+    match hasher.finish() % 3 {
+        0 => Class::Target,
+        1 => Class::Excluded,
+        _ => Class::Other,
+    }
+}
+
+#[test]
+fn patterns_match_exactly_the_given_file_paths() {
+    for fixture in fixture_names() {
+        let package = tar_package(&fixture);
+        let paths: Vec<_> = package
+            .entries_meta_data
+            .iter()
+            .map(|e| tar_path_to_utf8_str(&e.path))
+            .collect();
+        for seed in 0..8 {
+            for depth in 1..5 {
+                let (mut targets, mut excluded, mut others) = (Vec::new(), Vec::new(), Vec::new());
+                for path in paths.iter().cloned() {
+                    match classify(path, seed, depth) {
+                        Class::Target => targets.push(path),
+                        Class::Excluded => excluded.push(path),
+                        Class::Other => others.push(path),
+                    }
+                }
+                let num_targets = targets.len();
+                let patterns = turn_file_paths_into_patterns(targets.clone(), &excluded, &others);
+
+                assert!(
+                    patterns.len() <= num_targets,
+                    "{}: never more patterns than files to match",
+                    fixture
+                );
+                let is_match = patterns_matcher(&patterns);
+                for path in &targets {
+                    assert!(is_match(path), "{}: '{}' must match", fixture, path);
+                }
+                for path in excluded.iter().chain(others.iter()) {
+                    assert!(!is_match(path), "{}: '{}' must not match", fixture, path);
+                }
+                let negations: Vec<_> = patterns
+                    .iter()
+                    .filter_map(|p| p.strip_prefix('!').map(ToOwned::to_owned))
+                    .collect();
+                let is_negated = patterns_matcher(&negations);
+                for path in &others {
+                    assert!(
+                        !is_negated(path),
+                        "{}: '{}' is not supposed to be negated",
+                        fixture,
+                        path
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn directories_without_excluded_files_are_included_as_a_whole() {
+    assert_eq!(
+        turn_file_paths_into_patterns(
+            vec!["data/a.bin", "data/nested/b.bin", "data/nested/c.txt"],
+            &["tests/fixture.bin"],
+            &[]
+        ),
+        vec!["data/**/*"]
+    );
+}
+
+#[test]
+fn files_of_the_same_kind_are_matched_by_extension() {
+    assert_eq!(
+        turn_file_paths_into_patterns(
+            vec!["pregenerated/a.S", "pregenerated/b.S", "pregenerated/c.obj"],
+            &["pregenerated/tmp/a.asm"],
+            &[]
+        ),
+        vec!["pregenerated/*.S", "pregenerated/c.obj"]
+    );
+}
+
+#[test]
+fn excluded_files_are_negated_if_that_needs_less_patterns() {
+    assert_eq!(
+        turn_file_paths_into_patterns(
+            vec!["data/a.bin", "data/b.txt", "data/c.json", "data/d"],
+            &["data/tests/x.bin", "data/tests/y.bin"],
+            &[]
+        ),
+        vec!["data/**/*", "!data/tests/**/*"]
+    );
+}
@@ -0,0 +1,40 @@
+use super::from_package::{synthetic_package, wasted_paths};
+use crate::{rust_source_outline, Report};
+
+#[test]
+fn source_outlines_keep_only_lines_referring_to_other_files() {
+    assert_eq!(
+        rust_source_outline(
+            b"//! docs\n#[path = \"gen/a.rs\"]\npub(crate) mod a;\nfn f() {}\nconst D: &str = include_str!(\"d.txt\");\n"
+        ),
+        b"#[path = \"gen/a.rs\"]\npub(crate) mod a;\nconst D: &str = include_str!(\"d.txt\");\n".to_vec()
+    );
+}
+
+#[test]
+fn modules_are_followed_through_path_attributes_and_nested_declarations() {
+    let report = Report::from_package(
+        "a",
+        "1.0.0",
+        synthetic_package(
+            &[
+                ("Cargo.toml", "[package]\nname = \"a\"\n"),
+                (
+                    "src/lib.rs",
+                    "#[path = \"../gen/generated.rs\"]\nmod generated;\nmod nested;\n// mod commented;\n",
+                ),
+                ("src/nested.rs", "#[cfg(test)]\nmod child;\n"),
+                ("src/nested/child.rs", "mod grandchild;"),
+                ("src/nested/child/grandchild.rs", ""),
+                ("gen/generated.rs", "mod inner;\n"),
+                ("gen/inner/mod.rs", "const D: &[u8] = include_bytes!(\"../data.bin\");"),
+            ],
+            &["gen/data.bin", "gen/unused.rs", "commented.rs"],
+        ),
+    );
+    assert_eq!(
+        wasted_paths(report),
+        vec!["gen/unused.rs", "commented.rs"],
+        "only sources that aren't compiled are waste"
+    );
+}
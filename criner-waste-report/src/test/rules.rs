@@ -0,0 +1,226 @@
+use super::from_package::tar_package;
+use crate::{rules, ExplainedFile, Reason, Report, RuleBook, Rules};
+
+const RULES: &str = r#"
+default = "team"
+
+[sets.team]
+remove_exclude = ["**/assets/**/*"]
+
+[sets.no-tests]
+extends = "team"
+remove_exclude = ["**/tests/**/*"]
+
+[sets.minimal]
+include = ["src/**/*"]
+exclude = ["**/*.png"]
+
+[crates.egui]
+set = "no-tests"
+add_exclude = ["media/screenshots/*"]
+"#;
+
+#[test]
+fn builtin_rules_are_used_by_default() {
+    let book = RuleBook::default();
+    let rules = book.rules_for("anything");
+    assert_eq!(rules.name(), rules::BUILTIN_RULE_SET);
+    assert_eq!(rules.exclude(), Rules::builtin().exclude());
+}
+
+#[test]
+fn rule_sets_extend_each_other_and_crates_can_override_them() {
+    let book = RuleBook::from_toml(RULES).unwrap();
+
+    let team = book.rules_for("any-crate");
+    assert_eq!(team.name(), "team");
+    assert_eq!(team.include(), Rules::builtin().include());
+    assert!(!team.exclude().iter().any(|p| p == "**/assets/**/*"));
+    assert_eq!(team.exclude().len(), Rules::builtin().exclude().len() - 1);
+
+    let egui = book.rules_for("egui");
+    assert_eq!(egui.name(), "no-tests+egui");
+    assert!(!egui
+        .exclude()
+        .iter()
+        .any(|p| p == "**/assets/**/*" || p == "**/tests/**/*"));
+    assert_eq!(egui.exclude().last().map(String::as_str), Some("media/screenshots/*"));
+}
+
+#[test]
+fn rule_sets_can_replace_patterns_entirely() {
+    let book = RuleBook::from_toml(&format!("{}\n[crates.foo]\nset = \"minimal\"", RULES)).unwrap();
+    let rules = book.rules_for("foo");
+    assert_eq!(rules.include(), &["src/**/*".to_string()]);
+    assert_eq!(rules.exclude(), &["**/*.png".to_string()]);
+}
+
+#[test]
+fn fingerprints_change_only_with_the_rules_in_use() {
+    let builtin = RuleBook::default().fingerprint();
+    assert_eq!(RuleBook::from_toml("").unwrap().fingerprint(), builtin);
+    assert_eq!(
+        RuleBook::from_toml("[sets.unused]\nadd_exclude = [\"*.md\"]")
+            .unwrap()
+            .fingerprint(),
+        builtin,
+        "sets nobody uses don't affect any report"
+    );
+
+    let team = RuleBook::from_toml(RULES).unwrap().fingerprint();
+    assert_ne!(team, builtin);
+    assert_eq!(RuleBook::from_toml(RULES).unwrap().fingerprint(), team, "it's stable");
+    assert_ne!(
+        RuleBook::from_toml(&format!("{}\n[crates.foo]\nset = \"minimal\"", RULES))
+            .unwrap()
+            .fingerprint(),
+        team
+    );
+}
+
+#[test]
+fn invalid_rule_books_are_rejected() {
+    for (input, expected) in &[
+        (
+            "default = \"missing\"",
+            "Rule set 'missing' referenced by 'default' does not exist",
+        ),
+        (
+            "[sets.a]\nextends = \"b\"\n[sets.b]\nextends = \"a\"",
+            "Rule set 'a' extends itself, directly or indirectly",
+        ),
+        (
+            "[crates.foo]\nset = \"missing\"",
+            "Rule set 'missing' referenced by 'foo' does not exist",
+        ),
+        ("[sets.a]\nadd_exclude = [\"[\"]", "Invalid pattern '['"),
+        (
+            "[sets.builtin]\nadd_exclude = [\"*.md\"]",
+            "Rule set 'builtin' is reserved and can't be defined",
+        ),
+        ("[set.a]\nadd_exclude = [\"*.md\"]", "Could not parse rules file"),
+    ] {
+        let err = RuleBook::from_toml(input).unwrap_err().to_string();
+        assert!(err.starts_with(expected), "'{}' should start with '{}'", err, expected);
+    }
+    assert!(
+        matches!(
+            RuleBook::from_toml("[sets.a]\nremove_exlude = []"),
+            Err(rules::Error::Toml(_))
+        ),
+        "typos are not silently ignored"
+    );
+}
+
+#[test]
+fn provenance_is_recorded_and_follows_the_rules() {
+    let flagged_by = |rules: &str| -> Vec<(String, Option<String>)> {
+        let book = RuleBook::from_toml(rules).unwrap();
+        match Report::from_package_with_rules(
+            "lw_webdriver",
+            "0.4.1",
+            tar_package("lw_webdriver-0.4.1-extract_crate-1.0.0"),
+            book.rules_for("lw_webdriver"),
+        ) {
+            Report::Version {
+                wasted_files,
+                provenance: Some(provenance),
+                ..
+            } => {
+                assert_eq!(provenance.rule_set, "team");
+                wasted_files
+                    .into_iter()
+                    .map(|(path, _)| {
+                        let pattern = provenance.flagged_by(&path).map(ToOwned::to_owned);
+                        (path, pattern)
+                    })
+                    .collect()
+            }
+            _ => panic!("expected waste"),
+        }
+    };
+
+    assert_eq!(
+        flagged_by(RULES),
+        vec![
+            (".gitignore".into(), None),
+            ("chromedriver".into(), None),
+            ("geckodriver".into(), None),
+            ("tests/tests.rs".into(), Some("**/tests/**/*".into()))
+        ],
+        "all but tests are flagged for not being included"
+    );
+    assert_eq!(
+        flagged_by("default = \"team\"\n[sets.team]\nremove_exclude = [\"**/tests/**/*\"]"),
+        vec![
+            (".gitignore".into(), None),
+            ("chromedriver".into(), None),
+            ("geckodriver".into(), None),
+            ("tests/tests.rs".into(), None)
+        ],
+        "without the exclude rule, tests are still not included"
+    );
+}
+
+#[test]
+fn explanations_give_a_reason_for_each_wasted_file() {
+    let book = RuleBook::from_toml(RULES).unwrap();
+    let report = Report::from_package_with_rules(
+        "lw_webdriver",
+        "0.4.1",
+        tar_package("lw_webdriver-0.4.1-extract_crate-1.0.0"),
+        book.rules_for("lw_webdriver"),
+    );
+    let explanation = report.explain().expect("version report");
+    assert_eq!(explanation.rule_set.as_deref(), Some("team"));
+    assert_eq!(
+        explanation
+            .wasted_files
+            .into_iter()
+            .map(|ExplainedFile { path, reason, .. }| (path, reason))
+            .collect::<Vec<_>>(),
+        vec![
+            (".gitignore".into(), Some(Reason::NotIncluded)),
+            ("chromedriver".into(), Some(Reason::NotIncluded)),
+            ("geckodriver".into(), Some(Reason::NotIncluded)),
+            (
+                "tests/tests.rs".into(),
+                Some(Reason::MatchesExclude {
+                    pattern: "**/tests/**/*".into()
+                })
+            )
+        ]
+    );
+
+    let report = Report::from_package(
+        "gnir",
+        "0.14.0-alpha3",
+        tar_package("gnir-0.14.0-alpha3-extract_crate-1.0.0"),
+    );
+    let explanation = report.explain().expect("version report");
+    assert!(!explanation.wasted_files.is_empty());
+    assert!(
+        explanation.wasted_files.iter().all(|f| f.reason
+            == Some(Reason::ExcludedByManifest {
+                pattern: "pregenerated/tmp".into()
+            })),
+        "files excluded by the manifest are waste if include patterns are present"
+    );
+
+    let report = Report::from_package(
+        "fermium",
+        "20.12.0-alpha2",
+        tar_package("fermium-20.12.0-alpha2-extract_crate-1.0.0"),
+    );
+    let reasons: Vec<_> = report
+        .explain()
+        .expect("version report")
+        .wasted_files
+        .into_iter()
+        .filter_map(|f| f.reason)
+        .collect();
+    assert!(
+        reasons.contains(&Reason::NotReferenced) && !reasons.contains(&Reason::NotIncluded),
+        "files of crates with build script are waste if the build script doesn't reference them either"
+    );
+}
@@ -0,0 +1,114 @@
+use super::from_package::tar_package;
+use crate::{
+    validate::{files_dropped_by_fix, is_packaged, CargoPatterns},
+    Fix, Report,
+};
+
+fn patterns(patterns: &[&str]) -> CargoPatterns {
+    CargoPatterns::new(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>()).unwrap()
+}
+
+#[test]
+fn patterns_are_matched_like_cargo_does() {
+    let p = patterns(&["README.md", "/LICENSE", "src/*.rs", "data/", "docs"]);
+    assert!(p.is_match("README.md"));
+    assert!(
+        p.is_match("nested/README.md"),
+        "patterns without slash match at any depth"
+    );
+    assert!(p.is_match("LICENSE"));
+    assert!(
+        !p.is_match("nested/LICENSE"),
+        "a leading slash anchors to the crate root"
+    );
+    assert!(p.is_match("src/lib.rs"));
+    assert!(!p.is_match("src/a/b.rs"), "a single star doesn't match path separators");
+    assert!(
+        p.is_match("data/a/b.bin"),
+        "matching directories matches everything inside"
+    );
+    assert!(!p.is_match("data"), "a trailing slash only matches directories");
+    assert!(p.is_match("a/docs/index.md"));
+
+    let p = patterns(&["src/**/*", "!src/generated/**", "src/generated/keep.rs"]);
+    assert!(p.is_match("src/a/b.rs"));
+    assert!(
+        !p.is_match("src/generated/a.rs"),
+        "negations exclude what was matched before"
+    );
+    assert!(p.is_match("src/generated/keep.rs"), "the last matching pattern wins");
+}
+
+#[test]
+fn include_patterns_take_precedence_over_exclude_patterns() {
+    let include = patterns(&["src/**/*"]);
+    let exclude = patterns(&["src/"]);
+    assert!(is_packaged("src/lib.rs", Some(&include), Some(&exclude)));
+    assert!(!is_packaged("src/lib.rs", None, Some(&exclude)));
+    assert!(
+        is_packaged("Cargo.toml", Some(&include), None),
+        "the manifest is always packaged"
+    );
+    assert!(is_packaged("anything", None, None));
+}
+
+#[test]
+fn fixes_dropping_required_files_are_detected() {
+    let required = vec!["src/lib.rs".to_string(), "src/a/b.rs".into(), "build.rs".into()];
+    assert_eq!(
+        files_dropped_by_fix(
+            &Fix::NewInclude {
+                include: vec!["src/*.rs".into()],
+                has_build_script: true
+            },
+            None,
+            &required
+        )
+        .unwrap(),
+        vec!["src/a/b.rs", "build.rs"]
+    );
+    assert_eq!(
+        files_dropped_by_fix(
+            &Fix::EnrichedExclude {
+                exclude: vec!["src/a".into()],
+                exclude_added: vec!["src/a".into()],
+                has_build_script: true
+            },
+            None,
+            &required
+        )
+        .unwrap(),
+        vec!["src/a/b.rs"]
+    );
+    assert!(files_dropped_by_fix(
+        &Fix::RemoveExclude,
+        Some(&vec!["src/**/*".into(), "build.rs".into()]),
+        &required
+    )
+    .unwrap()
+    .is_empty());
+    assert!(
+        files_dropped_by_fix(
+            &Fix::NewInclude {
+                include: vec!["src/[".into()],
+                has_build_script: true
+            },
+            None,
+            &required
+        )
+        .is_err(),
+        "invalid patterns are an error instead of being ignored"
+    );
+}
+
+#[test]
+fn suggested_fixes_keep_all_required_files() {
+    for name in super::patterns::fixture_names() {
+        match Report::from_package("a", "1", tar_package(&name)) {
+            Report::Version {
+                files_dropped_by_fix, ..
+            } => assert!(files_dropped_by_fix.is_empty(), "{}: {:?}", name, files_dropped_by_fix),
+            _ => unreachable!("version report"),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Simulate which files `cargo package` puts into a crate, to assure a suggested [`Fix`] keeps all files needed to build it.
+use crate::{
+    result::{find_in_entries, find_paths_mentioned_in_build_script, included_paths_of, reachable_source_files},
+    tar_path_to_utf8_str, CargoConfig, Fix, Patterns, TarHeader,
+};
+use std::{collections::BTreeSet, path::Path};
+
+/// A single pattern of the `include` or `exclude` field in a manifest, interpreted like a line in a `.gitignore` file.
+struct Pattern {
+    negated: bool,
+    directory_only: bool,
+    matcher: globset::GlobMatcher,
+}
+
+impl Pattern {
+    fn new(pattern: &str) -> Result<Self, globset::Error> {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let (directory_only, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let glob = match pattern.strip_prefix('/') {
+            Some(anchored) => anchored.to_owned(),
+            None if pattern.contains('/') => pattern.to_owned(),
+            None if pattern.starts_with("**") => pattern.to_owned(),
+            None => format!("**/{}", pattern),
+        };
+        Ok(Pattern {
+            negated,
+            directory_only,
+            matcher: globset::GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .backslash_escape(true)
+                .build()?
+                .compile_matcher(),
+        })
+    }
+}
+
+/// Patterns as used in the `include` and `exclude` fields of a manifest, which Cargo matches like a `.gitignore` file.
+pub struct CargoPatterns {
+    patterns: Vec<Pattern>,
+}
+
+impl CargoPatterns {
+    pub fn new(patterns: &[String]) -> Result<Self, globset::Error> {
+        Ok(CargoPatterns {
+            patterns: patterns.iter().map(|p| Pattern::new(p)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Return true if the file at `path` is matched by a pattern, or if its closest matched parent directory is.
+    /// The last matching pattern decides, which doesn't match if it is negated.
+    pub fn is_match(&self, path: &str) -> bool {
+        let path = Path::new(path);
+        path.ancestors()
+            .filter(|p| !p.as_os_str().is_empty())
+            .enumerate()
+            .find_map(|(depth, candidate)| {
+                let is_directory = depth > 0;
+                self.patterns
+                    .iter()
+                    .rev()
+                    .find(|p| (is_directory || !p.directory_only) && p.matcher.is_match(candidate))
+            })
+            .is_some_and(|p| !p.negated)
+    }
+}
+
+/// Return true if cargo would put the file at `path` into the crate, given the `include` and `exclude` patterns
+/// of its manifest. Exclude patterns are ignored if there are include patterns.
+pub fn is_packaged(path: &str, include: Option<&CargoPatterns>, exclude: Option<&CargoPatterns>) -> bool {
+    if path == "Cargo.toml" {
+        return true;
+    }
+    match (include.filter(|i| !i.patterns.is_empty()), exclude) {
+        (Some(include), _) => include.is_match(path),
+        (None, Some(exclude)) => !exclude.is_match(path),
+        (None, None) => true,
+    }
+}
+
+/// Return the `include` and `exclude` patterns the manifest would have after applying `fix`, given
+/// the `include` patterns it has right now.
+fn patterns_after(fix: &Fix, manifest_include: Option<&Patterns>) -> (Option<Patterns>, Option<Patterns>) {
+    match fix {
+        Fix::ImprovedInclude { include, .. }
+        | Fix::NewInclude { include, .. }
+        | Fix::RemoveExcludeAndUseInclude { include, .. } => (Some(include.clone()), None),
+        Fix::EnrichedExclude { exclude, .. } => (None, Some(exclude.clone())),
+        Fix::RemoveExclude => (manifest_include.cloned(), None),
+    }
+}
+
+/// Return all `required` files that wouldn't be packaged anymore if `fix` was applied to a manifest with the
+/// given `include` patterns, or an error if one of the resulting patterns is invalid.
+pub fn files_dropped_by_fix(
+    fix: &Fix,
+    manifest_include: Option<&Patterns>,
+    required: &[String],
+) -> Result<Vec<String>, globset::Error> {
+    let (include, exclude) = patterns_after(fix, manifest_include);
+    let compile = |patterns: Option<Patterns>| patterns.map(|p| CargoPatterns::new(&p)).transpose();
+    let (include, exclude) = (compile(include)?, compile(exclude)?);
+    Ok(required
+        .iter()
+        .filter(|path| !is_packaged(path, include.as_ref(), exclude.as_ref()))
+        .cloned()
+        .collect())
+}
+
+/// Return the paths of all files in the crate which are needed to build it, which are its library and binary roots,
+/// all modules reachable from these, the build script and all files these include or mention,
+/// as well as files referenced by the manifest.
+pub(crate) fn required_files(
+    config: &CargoConfig,
+    entries_with_buffer: &[(TarHeader, Vec<u8>)],
+    entries: &[TarHeader],
+) -> Vec<String> {
+    let mut sources: Vec<_> = std::iter::once(config.lib_path().to_owned())
+        .chain(config.bin_paths())
+        .collect();
+    let mut seen = BTreeSet::new();
+    for root in sources.clone() {
+        sources.extend(reachable_source_files(entries_with_buffer, entries, &root, &mut seen));
+    }
+    let mut candidates = Vec::new();
+    for path in sources {
+        candidates.extend(included_paths_of(find_in_entries(entries_with_buffer, entries, &path)));
+        candidates.push(path);
+    }
+    if !config.build_script_disabled() {
+        let build_script = config.actual_or_expected_build_script_path();
+        candidates.extend(find_paths_mentioned_in_build_script(find_in_entries(
+            entries_with_buffer,
+            entries,
+            build_script,
+        )));
+        candidates.push(build_script.to_owned());
+    }
+    candidates.extend(config.referenced_file_paths());
+
+    let existing: BTreeSet<_> = entries.iter().map(|e| tar_path_to_utf8_str(&e.path)).collect();
+    let candidates: BTreeSet<_> = candidates.into_iter().collect();
+    candidates
+        .into_iter()
+        .filter(|path| existing.contains(path.as_str()))
+        .collect()
+}
@@ -0,0 +1,124 @@
+use crate::{
+    assets::{gc_blocking, Retention},
+    engine::work::{cpubound, iobound, schedule},
+    model,
+    persistence::{Db, TableAccess},
+};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+const CRATE_NAME: &str = "abcd";
+
+fn versions(versions: &[&str]) -> Vec<String> {
+    versions.iter().map(|v| v.to_string()).collect()
+}
+
+/// Add all `versions` of the crate to the database at `db_path` along with their downloaded archive, and mark the ones
+/// in `extracted` as extracted. Returns the paths to the archives.
+fn add_versions(db_path: &Path, versions: &[&str], extracted: &[&str]) -> crate::Result<Vec<PathBuf>> {
+    let db = Db::open(db_path)?;
+    let root = prodash::tree::root::Options::default().create();
+    let mut progress = root.add_child("db");
+    let download_task = iobound::default_persisted_download_task();
+    let mut archives = Vec::new();
+    for version in versions {
+        db.open_crates()?.upsert(
+            &mut progress,
+            CRATE_NAME,
+            &model::CrateVersion {
+                name: CRATE_NAME.into(),
+                kind: model::ChangeKind::Added,
+                version: version.to_string(),
+                checksum: String::new(),
+                features: Default::default(),
+                dependencies: Vec::new(),
+            },
+        )?;
+        let archive = schedule::download_file_path(
+            &db_path.join("assets"),
+            CRATE_NAME,
+            version,
+            &download_task.process,
+            &download_task.version,
+            "crate",
+        );
+        std::fs::create_dir_all(archive.parent().expect("archive in crate directory"))?;
+        std::fs::write(&archive, version.as_bytes())?;
+        archives.push(archive);
+    }
+    for version in extracted {
+        let task = model::Task {
+            state: model::TaskState::Complete,
+            ..cpubound::default_persisted_extraction_task()
+        };
+        let mut key = String::new();
+        task.fq_key(CRATE_NAME, version, &mut key);
+        db.open_tasks()?.insert(&mut progress, &key, &task)?;
+    }
+    Ok(archives)
+}
+
+#[test]
+fn retention_parses_from_str() {
+    assert_eq!(Retention::from_str("all"), Ok(Retention::KeepAll));
+    assert_eq!(Retention::from_str("none"), Ok(Retention::KeepNone));
+    assert_eq!(Retention::from_str("3"), Ok(Retention::KeepLatest(3)));
+    assert!(Retention::from_str("some").is_err());
+    assert!(Retention::from_str("-1").is_err());
+}
+
+#[test]
+fn retention_keeps_the_latest_versions() {
+    let all = versions(&["1.0.0", "1.1.0", "2.0.0"]);
+    assert!(Retention::KeepAll.collectable(&all).is_empty());
+    assert_eq!(Retention::KeepNone.collectable(&all), &all[..]);
+    assert_eq!(Retention::KeepLatest(1).collectable(&all), &all[..2]);
+    assert_eq!(Retention::KeepLatest(0).collectable(&all), &all[..]);
+    assert!(
+        Retention::KeepLatest(5).collectable(&all).is_empty(),
+        "there are less versions than to keep"
+    );
+    assert!(Retention::KeepNone.collectable(&[]).is_empty());
+}
+
+#[test]
+fn only_archives_of_extracted_versions_are_removed() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let archives = add_versions(dir.path(), &["1.0.0", "1.1.0", "1.2.0"], &["1.0.0", "1.2.0"])?;
+
+    let outcome = gc_blocking(dir.path(), Retention::KeepLatest(1), true)?;
+    assert_eq!(outcome.removed_archives, vec![(archives[0].clone(), 5)]);
+    assert_eq!(outcome.removed_bytes(), 5);
+    assert_eq!(
+        outcome.archives_pending_extraction, 1,
+        "1.1.0 wasn't extracted and is kept"
+    );
+    assert!(archives.iter().all(|a| a.is_file()), "a dry-run deletes nothing");
+
+    let outcome = gc_blocking(dir.path(), Retention::KeepLatest(1), false)?;
+    assert_eq!(outcome.removed_archives.len(), 1);
+    assert_eq!(outcome.archives_pending_extraction, 1);
+    assert!(!archives[0].exists());
+    assert!(archives[1].is_file() && archives[2].is_file());
+
+    let outcome = gc_blocking(dir.path(), Retention::KeepNone, false)?;
+    assert_eq!(
+        outcome.removed_archives,
+        vec![(archives[2].clone(), 5)],
+        "archives which are gone already are skipped"
+    );
+    assert_eq!(outcome.archives_pending_extraction, 1);
+    assert!(archives[1].is_file());
+    Ok(())
+}
+
+#[test]
+fn there_has_to_be_a_database() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("criner.db");
+    assert!(gc_blocking(&db_path, Retention::KeepNone, true).is_err());
+    assert!(!db_path.exists(), "no database is created");
+    Ok(())
+}
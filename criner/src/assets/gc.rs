@@ -0,0 +1,122 @@
+use crate::{
+    engine::work::{cpubound, iobound, schedule},
+    model,
+    persistence::{self, key_value_iter, new_key_value_query_old_to_new, TableAccess},
+    Result,
+};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Determines which downloaded crate archives to keep in the assets directory.
+///
+/// Only archives of crate versions which were extracted successfully are ever considered for removal, as these
+/// are the only ones we don't need anymore to compute our results. Should an extraction have to run again,
+/// the archive will be downloaded on demand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retention {
+    /// Keep all archives
+    KeepAll,
+    /// Remove all archives which were extracted
+    KeepNone,
+    /// Keep the archives of the given amount of most recent versions of each crate
+    KeepLatest(usize),
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention::KeepLatest(1)
+    }
+}
+
+impl FromStr for Retention {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "all" => Retention::KeepAll,
+            "none" => Retention::KeepNone,
+            n => Retention::KeepLatest(n.parse().map_err(|_| {
+                format!(
+                    "Expected 'all', 'none' or the amount of latest versions to keep, got '{}'",
+                    n
+                )
+            })?),
+        })
+    }
+}
+
+impl Retention {
+    /// Returns the versions whose archives may be removed, with versions sorted from oldest to most recent.
+    pub(crate) fn collectable<'a>(&self, versions: &'a [String]) -> &'a [String] {
+        match self {
+            Retention::KeepAll => &[],
+            Retention::KeepNone => versions,
+            Retention::KeepLatest(n) => &versions[..versions.len().saturating_sub(*n)],
+        }
+    }
+}
+
+/// The result of a garbage collection run
+#[derive(Debug, Default)]
+pub struct Outcome {
+    /// The archives which were removed, or would have been removed during a dry-run, along with their size in bytes
+    pub removed_archives: Vec<(PathBuf, u64)>,
+    /// The amount of archives we didn't remove as their extraction didn't complete yet
+    pub archives_pending_extraction: usize,
+}
+
+impl Outcome {
+    pub fn removed_bytes(&self) -> u64 {
+        self.removed_archives.iter().map(|(_, size)| size).sum()
+    }
+}
+
+/// Remove crate archives from the assets directory of the database at `db_path` according to the `retention` policy.
+/// If `dry_run` is true, no file will be deleted. Fails if there is no database at `db_path`.
+pub fn gc_blocking(db_path: impl AsRef<Path>, retention: Retention, dry_run: bool) -> Result<Outcome> {
+    let assets_dir = db_path.as_ref().join("assets");
+    let db = persistence::Db::open_existing(db_path)?;
+    let tasks = db.open_tasks()?;
+    let connection = db.open_connection_no_async_with_busy_wait()?;
+    let mut statement = new_key_value_query_old_to_new(persistence::CrateTable::table_name(), &connection)?;
+
+    let download_task = iobound::default_persisted_download_task();
+    let extraction_task = cpubound::default_persisted_extraction_task();
+    let mut key_buf = String::new();
+    let mut outcome = Outcome::default();
+    for res in key_value_iter::<model::Crate>(&mut statement)? {
+        let (crate_name, krate) = res?;
+        for version in retention.collectable(&krate.versions) {
+            let archive = schedule::download_file_path(
+                &assets_dir,
+                &crate_name,
+                version,
+                &download_task.process,
+                &download_task.version,
+                "crate",
+            );
+            let size = match std::fs::metadata(&archive) {
+                Ok(m) => m.len(),
+                Err(_) => continue,
+            };
+
+            key_buf.clear();
+            extraction_task.fq_key(&crate_name, version, &mut key_buf);
+            let extracted = tasks
+                .get(&key_buf)?
+                .is_some_and(|t| matches!(t.state, model::TaskState::Complete));
+            if !extracted {
+                outcome.archives_pending_extraction += 1;
+                continue;
+            }
+
+            if !dry_run {
+                std::fs::remove_file(&archive)?;
+            }
+            outcome.removed_archives.push((archive, size));
+        }
+    }
+    Ok(outcome)
+}
@@ -0,0 +1,6 @@
+mod gc;
+
+pub use gc::{gc_blocking, Outcome, Retention};
+
+#[cfg(test)]
+mod assets_test;
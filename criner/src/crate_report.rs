@@ -0,0 +1,39 @@
+//! Generate the reports of a single crate on demand, from the task results already in the database.
+use crate::{
+    engine::{report::waste, stage},
+    persistence::Db,
+    Result,
+};
+use std::path::{Path, PathBuf};
+
+pub use crate::engine::report::generic::CrateReportOutcome as Outcome;
+
+/// Write the waste reports of the given `version` of `crate_name`, or of all of its versions that aren't recently
+/// yanked, into `out_dir`, or publish them from the reports directory of the database at `db_path` according to
+/// `publish_options`.
+///
+/// Unless `out_dir` is set, versions which weren't reported before are added to the crate and top-level reports, just
+/// like the report stage of `mine` would do, so that they won't be counted again when it runs next.
+pub fn run_blocking(
+    db_path: impl AsRef<Path>,
+    crate_name: &str,
+    version: Option<&str>,
+    out_dir: Option<PathBuf>,
+    options: waste::Options,
+    publish_options: stage::report::PublishOptions,
+) -> Result<Outcome> {
+    let db = Db::open(db_path.as_ref())?;
+    let assets_dir = db_path.as_ref().join("assets");
+    let root = prodash::tree::root::Options::default().create();
+    let mut progress = root.add_child(format!("report {}", crate_name));
+    futures_lite::future::block_on(stage::report::generate_crate(
+        &db,
+        &mut progress,
+        &assets_dir,
+        out_dir,
+        crate_name,
+        version,
+        &options,
+        &publish_options,
+    ))
+}
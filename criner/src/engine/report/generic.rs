@@ -1,6 +1,6 @@
 use crate::persistence::{CrateVersionTable, TableAccess};
 use crate::{
-    error::Result,
+    error::{Error, Result},
     model, persistence,
     persistence::{new_key_insertion, ReportsTree},
 };
@@ -48,6 +48,17 @@ pub type WriteCallbackState = Option<async_channel::Sender<WriteRequest>>;
 pub type WriteCallback =
     fn(WriteRequest, &WriteCallbackState) -> futures_util::future::BoxFuture<Result<WriteInstruction>>;
 
+/// The versions of a crate whose reports were written by [`Generator::write_crate()`]
+#[derive(Debug, Default)]
+pub struct CrateReportOutcome {
+    /// Versions which were reported for the first time and added to the crate and top-level reports
+    pub new_versions: Vec<String>,
+    /// Versions which were reported before, whose reports were written again
+    pub regenerated_versions: Vec<String>,
+    /// Versions which couldn't be reported as there is no result for them yet
+    pub versions_without_result: Vec<String>,
+}
+
 #[async_trait]
 pub trait Aggregate
 where
@@ -64,17 +75,34 @@ where
 pub trait Generator {
     type Report: Aggregate + Send + Sync + Clone;
     type DBResult: Send;
+    /// Options to affect how reports are generated, passed along to each invocation of `generate_report()`
+    type Options: Send + Sync;
 
     fn name() -> &'static str;
     fn version() -> &'static str;
 
+    /// Identifies `options` which make reports differ from the ones generated with the default options, or `None`
+    /// if they don't. It becomes part of the report keys, so reports are generated anew whenever it changes.
+    fn options_fingerprint(_options: &Self::Options) -> Option<String> {
+        None
+    }
+
     fn fq_result_key(crate_name: &str, crate_version: &str, key_buf: &mut String);
-    fn fq_report_key(crate_name: &str, crate_version: &str, key_buf: &mut String) {
-        ReportsTree::key_buf(crate_name, crate_version, Self::name(), Self::version(), key_buf);
+    fn fq_report_key(crate_name: &str, crate_version: &str, options: &Self::Options, key_buf: &mut String) {
+        match Self::options_fingerprint(options) {
+            Some(fingerprint) => ReportsTree::key_buf(
+                crate_name,
+                crate_version,
+                Self::name(),
+                &format!("{}+{}", Self::version(), fingerprint),
+                key_buf,
+            ),
+            None => ReportsTree::key_buf(crate_name, crate_version, Self::name(), Self::version(), key_buf),
+        }
     }
 
     fn get_result(
-        connection: persistence::ThreadSafeConnection,
+        results: &persistence::TaskResultTable,
         crate_name: &str,
         crate_version: &str,
         key_buf: &mut String,
@@ -118,17 +146,7 @@ pub trait Generator {
                 Some(previous_report) => previous_report.merge(report),
                 None => report,
             };
-            {
-                complete_and_write_report(
-                    &mut report,
-                    Vec::new(),
-                    &mut progress,
-                    out_dir.join("index.html"),
-                    write,
-                    &write_state,
-                )
-                .await?;
-            }
+            Self::write_top_level_files(&mut report, &out_dir, &mut progress, write, &write_state).await?;
             if let Some(cd) = cache_dir {
                 report.store_current_state(&cd, &mut progress).await?;
             }
@@ -136,33 +154,209 @@ pub trait Generator {
         Ok(())
     }
 
+    /// Write the html page of the top-level `report` along with all other top-level files into `out_dir`.
+    async fn write_top_level_files(
+        report: &mut Self::Report,
+        out_dir: &Path,
+        progress: &mut prodash::tree::Item,
+        write: WriteCallback,
+        write_state: &WriteCallbackState,
+    ) -> Result<()> {
+        let mut out = complete_and_write_report(
+            report,
+            Vec::new(),
+            progress,
+            out_dir.join("index.html"),
+            write,
+            write_state,
+        )
+        .await?;
+        type TopLevelFile<R> = fn(&R, &mut Vec<u8>) -> Result<Option<&'static str>>;
+        let top_level_files: [TopLevelFile<Self::Report>; 3] =
+            [Self::search_index, Self::changes_page, Self::changes_feed];
+        for write_top_level_file in top_level_files {
+            out.clear();
+            if let Some(file_name) = write_top_level_file(report, &mut out)? {
+                progress.blocked("sending report to writer", None);
+                out = write_report(out, progress, out_dir.join(file_name), write, write_state).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generate the report for a crate version from its `result`, which was downloaded `downloads` times if known.
     async fn generate_report(
         crate_name: &str,
         crate_version: &str,
         result: Self::DBResult,
+        downloads: Option<u64>,
+        options: &Self::Options,
         progress: &mut prodash::tree::Item,
     ) -> Result<Self::Report>;
 
+    /// Write an index of all children of the top-level `report` into `out` for use by its html page, and return the
+    /// name of the file to place next to it, or `None` if there is no index.
+    fn search_index(_report: &Self::Report, _out: &mut Vec<u8>) -> Result<Option<&'static str>> {
+        Ok(None)
+    }
+
+    /// Write a page listing the recent changes of the top-level `report` into `out`, and return the name of the
+    /// file to place next to its html page, or `None` if there is no such page.
+    fn changes_page(_report: &Self::Report, _out: &mut Vec<u8>) -> Result<Option<&'static str>> {
+        Ok(None)
+    }
+
+    /// Write a feed of the recent changes of the top-level `report` into `out`, and return the name of the file to
+    /// place next to its html page, or `None` if there is no feed.
+    fn changes_feed(_report: &Self::Report, _out: &mut Vec<u8>) -> Result<Option<&'static str>> {
+        Ok(None)
+    }
+
+    /// Record the changes of the crate `report`, which contains only newly reported versions, compared to the
+    /// `previous` report of the same crate.
+    fn record_changes(_report: &mut Self::Report, _previous: &Self::Report, _options: &Self::Options) {}
+
+    /// Write a badge of the crate `report` into `out` to be placed next to its html page, and return the name of its
+    /// file, or `None` if there is no badge.
+    fn crate_badge(
+        _report: &Self::Report,
+        _options: &Self::Options,
+        _out: &mut Vec<u8>,
+    ) -> Result<Option<&'static str>> {
+        Ok(None)
+    }
+
+    /// Serialize a machine-readable form of the version `report` into `out` to be placed next to its html page,
+    /// or return false if there is none or it wasn't requested in `options`.
+    fn version_json(_report: &Self::Report, _options: &Self::Options, _out: &mut Vec<u8>) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Write a Markdown summary of the version `report` into `out` to be placed next to its html page,
+    /// or return false if there is none or it wasn't requested in `options`.
+    fn version_markdown(_report: &Self::Report, _options: &Self::Options, _out: &mut Vec<u8>) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Write a patch to apply to the version's crate, like a fix to its manifest, into `out` to be placed next to
+    /// its html page, or return false if there is none.
+    fn version_patch(_report: &Self::Report, _options: &Self::Options, _out: &mut Vec<u8>) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Write the html page of the version `report` along with all other files of the version into `crate_dir`.
+    #[allow(clippy::too_many_arguments)]
+    async fn write_version_files(
+        report: &mut Self::Report,
+        crate_dir: &Path,
+        version: &str,
+        options: &Self::Options,
+        mut out: Vec<u8>,
+        progress: &mut prodash::tree::Item,
+        write: WriteCallback,
+        write_state: &WriteCallbackState,
+    ) -> Result<Vec<u8>> {
+        out = complete_and_write_report(
+            report,
+            out,
+            progress,
+            version_html_path(crate_dir, version),
+            write,
+            write_state,
+        )
+        .await?;
+
+        type VersionFile<R, O> = fn(&R, &O, &mut Vec<u8>) -> Result<bool>;
+        let version_files: [(&str, VersionFile<Self::Report, Self::Options>); 3] = [
+            ("json", Self::version_json),
+            ("md", Self::version_markdown),
+            ("patch", Self::version_patch),
+        ];
+        for (extension, write_version_file) in version_files {
+            out.clear();
+            if write_version_file(report, options, &mut out)? {
+                progress.blocked("sending report to writer", None);
+                out = write_report(
+                    out,
+                    progress,
+                    version_file_path(crate_dir, version, extension),
+                    write,
+                    write_state,
+                )
+                .await?;
+            }
+        }
+        Ok(out)
+    }
+
+    /// Write the html page and badge of a crate into `crate_dir`, showing its `previous_state` merged with the
+    /// `crate_report` of its newly reported versions, and store the result in `cache_dir` if set.
+    /// Returns the `crate_report` with its changes recorded, ready to be merged into the top-level report.
+    #[allow(clippy::too_many_arguments)]
+    async fn write_crate_files(
+        mut crate_report: Option<Self::Report>,
+        previous_state: Option<Self::Report>,
+        crate_dir: &Path,
+        cache_dir: Option<&Path>,
+        options: &Self::Options,
+        mut out: Vec<u8>,
+        progress: &mut prodash::tree::Item,
+        write: WriteCallback,
+        write_state: &WriteCallbackState,
+    ) -> Result<(Option<Self::Report>, Vec<u8>)> {
+        if let (Some(crate_report), Some(previous_state)) = (crate_report.as_mut(), previous_state.as_ref()) {
+            Self::record_changes(crate_report, previous_state, options);
+        }
+        let mut state = match (previous_state, crate_report.as_ref()) {
+            (Some(previous_state), Some(crate_report)) => previous_state.merge(crate_report.clone()),
+            (Some(previous_state), None) => previous_state,
+            (None, Some(crate_report)) => crate_report.clone(),
+            (None, None) => return Ok((None, out)),
+        };
+        out = complete_and_write_report(
+            &mut state,
+            out,
+            progress,
+            crate_html_path(crate_dir),
+            write,
+            write_state,
+        )
+        .await?;
+        out.clear();
+        if let Some(file_name) = Self::crate_badge(&state, options, &mut out)? {
+            progress.blocked("sending badge to writer", None);
+            out = write_report(out, progress, crate_dir.join(file_name), write, write_state).await?;
+        }
+        if let Some(cd) = cache_dir {
+            state.store_current_state(cd, progress).await?;
+        }
+        Ok((crate_report, out))
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn write_files(
         db: persistence::Db,
         out_dir: PathBuf,
         cache_dir: Option<PathBuf>,
         krates: Vec<(String, Vec<u8>)>,
+        options: std::sync::Arc<Self::Options>,
         mut progress: prodash::tree::Item,
         write: WriteCallback,
         write_state: WriteCallbackState,
     ) -> Result<Option<Self::Report>> {
         let mut chunk_report = None::<Self::Report>;
         let crate_versions = db.open_crate_versions()?;
+        let crates_io_crates = db.open_crates_io_crates()?;
         let mut reports_to_mark_done = Vec::new();
         let mut out_buf = Vec::new();
         {
-            let connection = db.open_connection()?;
+            let results = db.open_results()?;
             let reports = db.open_reports()?;
             let mut key_buf = String::with_capacity(32);
             // delaying writes works because we don't have overlap on work
             for (name, krate) in krates.into_iter() {
                 let c: model::Crate = krate.as_slice().into();
+                let crates_io_crate = crates_io_crates.get(&name)?;
                 let crate_dir = crate_dir(&out_dir, &name);
                 progress.init(Some(c.versions.len()), Some("versions".into()));
                 progress.set_name(&name);
@@ -177,7 +371,7 @@ pub trait Generator {
                     progress.inc();
 
                     key_buf.clear();
-                    Self::fq_report_key(&name, version, &mut key_buf);
+                    Self::fq_report_key(&name, version, &options, &mut key_buf);
 
                     // If we have no cache, assume we are globbed (yes, I know…sigh), so always produce reports
                     // but don't invalidate data in caches by reading or writing them. Mostly used for testing
@@ -187,15 +381,19 @@ pub trait Generator {
                         let reports_key = key_buf.clone();
                         key_buf.clear();
 
-                        if let Some(result) = Self::get_result(connection.clone(), &name, version, &mut key_buf)? {
+                        if let Some(result) = Self::get_result(&results, &name, version, &mut key_buf)? {
+                            let downloads = downloads_of(crates_io_crate.as_ref(), version);
                             let mut version_report =
-                                Self::generate_report(&name, version, result, &mut progress).await?;
+                                Self::generate_report(&name, version, result, downloads, &options, &mut progress)
+                                    .await?;
 
-                            out_buf = complete_and_write_report(
+                            out_buf = Self::write_version_files(
                                 &mut version_report,
+                                &crate_dir,
+                                version,
+                                &options,
                                 out_buf,
                                 &mut progress,
-                                version_html_path(&crate_dir, version),
                                 write,
                                 &write_state,
                             )
@@ -210,69 +408,194 @@ pub trait Generator {
                         }
                     }
                 }
-                if let Some(mut crate_report) = crate_report {
+                if let Some(crate_report) = crate_report {
                     let previous_state = match cache_dir.as_ref() {
                         Some(cd) => crate_report.load_previous_state(cd, &mut progress).await,
                         None => None,
                     };
-                    match previous_state {
-                        Some(previous_state) => {
-                            let mut absolute_state = previous_state.merge(crate_report.clone());
-                            out_buf = complete_and_write_report(
-                                &mut absolute_state,
-                                out_buf,
-                                &mut progress,
-                                crate_html_path(&crate_dir),
-                                write,
-                                &write_state,
-                            )
-                            .await?;
-                            if let Some(cd) = cache_dir.as_ref() {
-                                absolute_state.store_current_state(cd, &mut progress).await?;
-                            };
-                        }
-                        None => {
-                            out_buf = complete_and_write_report(
-                                &mut crate_report,
-                                out_buf,
-                                &mut progress,
-                                crate_html_path(&crate_dir),
-                                write,
-                                &write_state,
-                            )
-                            .await?;
-                            if let Some(cd) = cache_dir.as_ref() {
-                                crate_report.store_current_state(cd, &mut progress).await?;
-                            }
-                        }
-                    }
-                    chunk_report = Some(match chunk_report {
-                        Some(chunk_report) => chunk_report.merge(crate_report),
-                        None => crate_report,
-                    });
+                    let (crate_report, buf) = Self::write_crate_files(
+                        Some(crate_report),
+                        previous_state,
+                        &crate_dir,
+                        cache_dir.as_deref(),
+                        &options,
+                        out_buf,
+                        &mut progress,
+                        write,
+                        &write_state,
+                    )
+                    .await?;
+                    out_buf = buf;
+                    chunk_report = match (chunk_report, crate_report) {
+                        (Some(chunk_report), Some(crate_report)) => Some(chunk_report.merge(crate_report)),
+                        (chunk_report, crate_report) => chunk_report.or(crate_report),
+                    };
                 }
             }
         }
 
-        if !reports_to_mark_done.is_empty() {
-            let mut connection = db.open_connection_no_async_with_busy_wait()?;
-            progress.blocked("wait for write lock", None);
-            let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
-            progress.init(
-                Some(reports_to_mark_done.len()),
-                Some("report done markers written".into()),
-            );
-            {
-                let mut statement = new_key_insertion(ReportsTree::table_name(), &transaction)?;
-                for key in reports_to_mark_done.iter() {
-                    statement.execute(params![key])?;
-                    progress.inc();
+        mark_reports_done(&db, &reports_to_mark_done, &mut progress)?;
+        Ok(chunk_report)
+    }
+
+    /// Generate the reports of the given `version` of `crate_name`, or of all its versions that aren't recently
+    /// yanked, and write them into `out_dir`, along with the crate and top-level pages including them.
+    /// If `update_cache` is set, versions which weren't reported before are added to the crate and top-level reports
+    /// stored in `cache_dir` and marked done afterwards. Without a top-level report they are left for the next
+    /// reporting run of all crates, which would otherwise not count them.
+    #[allow(clippy::too_many_arguments)]
+    async fn write_crate(
+        db: &persistence::Db,
+        out_dir: &Path,
+        cache_dir: &Path,
+        update_cache: bool,
+        crate_name: &str,
+        version: Option<&str>,
+        options: &Self::Options,
+        progress: &mut prodash::tree::Item,
+        write: WriteCallback,
+        write_state: &WriteCallbackState,
+    ) -> Result<CrateReportOutcome> {
+        let krate = db
+            .open_crates()?
+            .get(crate_name)?
+            .ok_or_else(|| Error::Message(format!("Crate '{}' is unknown", crate_name)))?;
+        let crates_io_crate = db.open_crates_io_crates()?.get(crate_name)?;
+        let results = db.open_results()?;
+        let reports = db.open_reports()?;
+        let mut key_buf = String::with_capacity(32);
+
+        let versions = match version {
+            Some(version) => {
+                if !krate.versions.iter().any(|v| v == version) {
+                    return Err(Error::Message(format!(
+                        "Version {} of crate '{}' is unknown",
+                        version, crate_name
+                    )));
+                }
+                vec![version.to_owned()]
+            }
+            None => {
+                let num_versions =
+                    all_but_recently_yanked(crate_name, &krate.versions, &db.open_crate_versions()?, &mut key_buf)?;
+                krate.versions.iter().take(num_versions).cloned().collect()
+            }
+        };
+
+        let crate_dir = crate_dir(out_dir, crate_name);
+        let mut outcome = CrateReportOutcome::default();
+        let mut crate_report = None::<Self::Report>;
+        let mut any_version_report = None::<Self::Report>;
+        let mut reports_to_mark_done = Vec::new();
+        let mut out_buf = Vec::new();
+        progress.init(Some(versions.len()), Some("versions".into()));
+        for version in versions {
+            progress.inc();
+            key_buf.clear();
+            Self::fq_report_key(crate_name, &version, options, &mut key_buf);
+            let reports_key = key_buf.clone();
+            key_buf.clear();
+
+            let result = match Self::get_result(&results, crate_name, &version, &mut key_buf)? {
+                Some(result) => result,
+                None => {
+                    outcome.versions_without_result.push(version);
+                    continue;
                 }
+            };
+            let downloads = downloads_of(crates_io_crate.as_ref(), &version);
+            let mut version_report =
+                Self::generate_report(crate_name, &version, result, downloads, options, progress).await?;
+            out_buf = Self::write_version_files(
+                &mut version_report,
+                &crate_dir,
+                &version,
+                options,
+                out_buf,
+                progress,
+                write,
+                write_state,
+            )
+            .await?;
+
+            if reports.is_done(&reports_key) {
+                // already part of the crate and top-level reports, which must not count it twice
+                outcome.regenerated_versions.push(version);
+                any_version_report.get_or_insert(version_report);
+            } else {
+                crate_report = Some(match crate_report {
+                    Some(crate_report) => crate_report.merge(version_report),
+                    None => version_report,
+                });
+                reports_to_mark_done.push(reports_key);
+                outcome.new_versions.push(version);
             }
-            transaction.commit()?;
         }
-        Ok(chunk_report)
+
+        let previous_state = match crate_report.as_ref().or(any_version_report.as_ref()) {
+            Some(report) => report.load_previous_state(cache_dir, progress).await,
+            None => None,
+        };
+        let top_level_state = match crate_report {
+            Some(_) => Self::Report::load_previous_top_level_state(cache_dir, progress).await,
+            None => None,
+        };
+        let update_cache = update_cache && (crate_report.is_none() || top_level_state.is_some());
+        let (crate_report, _) = Self::write_crate_files(
+            crate_report,
+            previous_state,
+            &crate_dir,
+            if update_cache { Some(cache_dir) } else { None },
+            options,
+            out_buf,
+            progress,
+            write,
+            write_state,
+        )
+        .await?;
+
+        match (crate_report, top_level_state) {
+            (Some(crate_report), Some(top_level_report)) => {
+                let mut report = top_level_report.merge(crate_report);
+                Self::write_top_level_files(&mut report, out_dir, progress, write, write_state).await?;
+                if update_cache {
+                    report.store_current_state(cache_dir, progress).await?;
+                    mark_reports_done(db, &reports_to_mark_done, progress)?;
+                }
+            }
+            (Some(_), None) => progress.info(format!(
+                "There is no top-level report yet - the new versions of '{}' will be added once reports are generated for all crates",
+                crate_name
+            )),
+            (None, _) => {}
+        }
+        Ok(outcome)
+    }
+}
+
+/// Remember that the reports with the given `keys` were generated, so that they won't be generated again.
+fn mark_reports_done(db: &persistence::Db, keys: &[String], progress: &mut prodash::tree::Item) -> Result<()> {
+    if !keys.is_empty() {
+        let mut connection = db.open_connection_no_async_with_busy_wait()?;
+        progress.blocked("wait for write lock", None);
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        progress.init(Some(keys.len()), Some("report done markers written".into()));
+        {
+            let mut statement = new_key_insertion(ReportsTree::table_name(), &transaction)?;
+            for key in keys.iter() {
+                statement.execute(params![key])?;
+                progress.inc();
+            }
+        }
+        transaction.commit()?;
     }
+    Ok(())
+}
+
+fn downloads_of(crates_io_crate: Option<&model::db_dump::Crate>, version: &str) -> Option<u64> {
+    crates_io_crate
+        .and_then(|c| c.versions.iter().find(|v| v.semver == *version))
+        .map(|v| u64::from(v.downloads))
 }
 
 fn crate_dir(base: &Path, crate_name: &str) -> PathBuf {
@@ -282,6 +605,9 @@ fn crate_dir(base: &Path, crate_name: &str) -> PathBuf {
 fn version_html_path(crate_dir: &Path, version: &str) -> PathBuf {
     crate_dir.join(format!("{}.html", version))
 }
+fn version_file_path(crate_dir: &Path, version: &str, extension: &str) -> PathBuf {
+    crate_dir.join(format!("{}.{}", version, extension))
+}
 fn crate_html_path(crate_dir: &Path) -> PathBuf {
     crate_dir.join("index.html")
 }
@@ -297,6 +623,16 @@ async fn complete_and_write_report(
     out.clear();
     report.complete(progress, &mut out).await?;
     progress.blocked("sending report to writer", None);
+    write_report(out, progress, path, write, write_state).await
+}
+
+async fn write_report(
+    out: Vec<u8>,
+    progress: &mut prodash::tree::Item,
+    path: impl AsRef<Path>,
+    write: WriteCallback,
+    write_state: &WriteCallbackState,
+) -> Result<Vec<u8>> {
     match write(
         WriteRequest {
             path: path.as_ref().to_path_buf(),
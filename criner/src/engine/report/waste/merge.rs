@@ -1,7 +1,7 @@
-use super::{AggregateFileInfo, AggregateVersionInfo, Dict, Fix, Report, VersionInfo, WastedFile};
+use super::{changes, history, AggregateFileInfo, AggregateVersionInfo, Dict, Fix, Report, VersionInfo, WastedFile};
 use crate::Result;
 use async_trait::async_trait;
-use criner_waste_report::{add_optional_aggregate, html::NO_EXT_MARKER};
+use criner_waste_report::{add_optional_aggregate, add_optional_bytes, html::NO_EXT_MARKER, is_later_version};
 use std::{
     collections::BTreeMap,
     ops::AddAssign,
@@ -87,6 +87,8 @@ pub fn version_to_new_version_map(
     total_files: u64,
     wasted_files: &[WastedFile],
     potential_gains: Option<AggregateFileInfo>,
+    compressed_waste_bytes: Option<u64>,
+    downloads: Option<u64>,
 ) -> Dict<VersionInfo> {
     let mut m = BTreeMap::new();
     m.insert(
@@ -102,6 +104,8 @@ pub fn version_to_new_version_map(
             },
             potential_gains,
             waste_latest_version: None,
+            compressed_waste_bytes,
+            downloaded_waste_bytes: compressed_waste_bytes.unwrap_or_default() * downloads.unwrap_or_default(),
         },
     );
     m
@@ -111,27 +115,30 @@ pub fn crate_collection_info_from_version_info(
     crate_name: String,
     info_by_version: Dict<VersionInfo>,
 ) -> Dict<AggregateVersionInfo> {
-    let (_, v) = info_by_version.into_iter().fold(
-        (String::new(), AggregateVersionInfo::default()),
-        |(mut previous_name, mut a), (version_name, v)| {
+    let v = info_by_version
+        .into_iter()
+        .fold(AggregateVersionInfo::default(), |mut a, (version_name, v)| {
             let VersionInfo {
                 waste,
                 all,
                 potential_gains,
                 waste_latest_version: _unused_and_always_none,
+                compressed_waste_bytes,
+                downloaded_waste_bytes,
             } = v;
             a.waste.add_assign(waste.clone());
             a.all.add_assign(all);
+            a.compressed_waste_bytes = add_optional_bytes(a.compressed_waste_bytes, compressed_waste_bytes);
+            a.downloaded_waste_bytes += downloaded_waste_bytes;
             a.potential_gains = add_optional_aggregate(a.potential_gains.clone(), potential_gains);
-            a.waste_latest_version = if version_name > previous_name {
-                previous_name = version_name.clone();
-                Some((version_name, waste))
-            } else {
-                a.waste_latest_version
+            a.waste_latest_version = match a.waste_latest_version {
+                Some((latest, latest_waste)) if !is_later_version(&version_name, &latest) => {
+                    Some((latest, latest_waste))
+                }
+                _ => Some((version_name, waste)),
             };
-            (previous_name, a)
-        },
-    );
+            a
+        });
 
     let mut m = BTreeMap::new();
     m.insert(crate_name, v);
@@ -144,12 +151,15 @@ pub fn collection_from_crate(
     total_files: u64,
     info_by_version: Dict<VersionInfo>,
     wasted_by_extension: Dict<AggregateFileInfo>,
+    changes: changes::Changes,
 ) -> Report {
     Report::CrateCollection {
         total_size_in_bytes,
         total_files,
         info_by_crate: crate_collection_info_from_version_info(crate_name, info_by_version),
         wasted_by_extension,
+        history: Vec::new(),
+        changes,
     }
 }
 
@@ -162,6 +172,11 @@ pub fn crate_from_version(version: Report) -> Report {
             total_files,
             wasted_files,
             suggested_fix,
+            provenance: _,
+            files_dropped_by_fix: _,
+            manifest_patch: _,
+            compressed_waste_bytes,
+            downloads,
         } => Report::Crate {
             crate_name,
             info_by_version: version_to_new_version_map(
@@ -170,10 +185,14 @@ pub fn crate_from_version(version: Report) -> Report {
                 total_files,
                 &wasted_files,
                 fix_to_wasted_files_aggregate(suggested_fix),
+                compressed_waste_bytes,
+                downloads,
             ),
             total_size_in_bytes,
             total_files,
             wasted_by_extension: into_map_by_extension(wasted_files),
+            history: Vec::new(),
+            changes: Vec::new(),
         },
         _ => unreachable!("must only be called with version variant"),
     }
@@ -198,6 +217,8 @@ impl crate::engine::report::generic::Aggregate for Report {
                     total_files: lhs_tf,
                     info_by_version,
                     wasted_by_extension,
+                    history,
+                    changes,
                 },
                 Version {
                     crate_name: rhs_crate_name,
@@ -206,6 +227,11 @@ impl crate::engine::report::generic::Aggregate for Report {
                     total_files: rhs_tf,
                     wasted_files,
                     suggested_fix,
+                    provenance,
+                    files_dropped_by_fix,
+                    manifest_patch,
+                    compressed_waste_bytes,
+                    downloads,
                 },
             ) => {
                 if lhs_crate_name == rhs_crate_name {
@@ -221,21 +247,36 @@ impl crate::engine::report::generic::Aggregate for Report {
                                 rhs_tf,
                                 &wasted_files,
                                 fix_to_wasted_files_aggregate(suggested_fix),
+                                compressed_waste_bytes,
+                                downloads,
                             ),
                         ),
                         wasted_by_extension: vec_into_map_by_extension(wasted_by_extension, wasted_files),
+                        history,
+                        changes,
                     }
                 } else {
-                    collection_from_crate(lhs_crate_name, lhs_tsb, lhs_tf, info_by_version, wasted_by_extension).merge(
-                        Version {
-                            crate_name: rhs_crate_name,
-                            crate_version,
-                            total_size_in_bytes: rhs_tsb,
-                            total_files: rhs_tf,
-                            wasted_files,
-                            suggested_fix,
-                        },
+                    collection_from_crate(
+                        lhs_crate_name,
+                        lhs_tsb,
+                        lhs_tf,
+                        info_by_version,
+                        wasted_by_extension,
+                        changes,
                     )
+                    .merge(Version {
+                        crate_name: rhs_crate_name,
+                        crate_version,
+                        total_size_in_bytes: rhs_tsb,
+                        total_files: rhs_tf,
+                        wasted_files,
+                        suggested_fix,
+                        provenance,
+                        files_dropped_by_fix,
+                        manifest_patch,
+                        compressed_waste_bytes,
+                        downloads,
+                    })
                 }
             }
             (
@@ -245,6 +286,8 @@ impl crate::engine::report::generic::Aggregate for Report {
                     total_files: lhs_tf,
                     info_by_version: lhs_ibv,
                     wasted_by_extension: lhs_wbe,
+                    history: lhs_history,
+                    changes: lhs_changes,
                 },
                 Crate {
                     crate_name: rhs_crate_name,
@@ -252,15 +295,19 @@ impl crate::engine::report::generic::Aggregate for Report {
                     total_files: rhs_tf,
                     info_by_version: rhs_ibv,
                     wasted_by_extension: rhs_wbe,
+                    history: rhs_history,
+                    changes: rhs_changes,
                 },
             ) => {
                 if lhs_crate_name != rhs_crate_name {
-                    collection_from_crate(lhs_crate_name, lhs_tsb, lhs_tf, lhs_ibv, lhs_wbe).merge(Crate {
+                    collection_from_crate(lhs_crate_name, lhs_tsb, lhs_tf, lhs_ibv, lhs_wbe, lhs_changes).merge(Crate {
                         crate_name: rhs_crate_name,
                         total_size_in_bytes: rhs_tsb,
                         total_files: rhs_tf,
                         info_by_version: rhs_ibv,
                         wasted_by_extension: rhs_wbe,
+                        history: rhs_history,
+                        changes: rhs_changes,
                     })
                 } else {
                     Crate {
@@ -269,6 +316,8 @@ impl crate::engine::report::generic::Aggregate for Report {
                         total_files: lhs_tf + rhs_tf,
                         info_by_version: map_into_map(lhs_ibv, rhs_ibv),
                         wasted_by_extension: map_into_map(lhs_wbe, rhs_wbe),
+                        history: history::merge(lhs_history, rhs_history),
+                        changes: changes::merge(lhs_changes, rhs_changes),
                     }
                 }
             }
@@ -278,18 +327,24 @@ impl crate::engine::report::generic::Aggregate for Report {
                     total_files: lhs_tf,
                     info_by_crate: lhs_ibc,
                     wasted_by_extension: lhs_wbe,
+                    history: lhs_history,
+                    changes: lhs_changes,
                 },
                 CrateCollection {
                     total_size_in_bytes: rhs_tsb,
                     total_files: rhs_tf,
                     info_by_crate: rhs_ibc,
                     wasted_by_extension: rhs_wbe,
+                    history: rhs_history,
+                    changes: rhs_changes,
                 },
             ) => CrateCollection {
                 total_size_in_bytes: lhs_tsb + rhs_tsb,
                 total_files: lhs_tf + rhs_tf,
                 info_by_crate: map_into_map(lhs_ibc, rhs_ibc),
                 wasted_by_extension: map_into_map(lhs_wbe, rhs_wbe),
+                history: history::merge(lhs_history, rhs_history),
+                changes: changes::merge(lhs_changes, rhs_changes),
             },
             (
                 CrateCollection {
@@ -297,6 +352,8 @@ impl crate::engine::report::generic::Aggregate for Report {
                     total_files: lhs_tf,
                     info_by_crate,
                     wasted_by_extension: lhs_wbe,
+                    history,
+                    changes: lhs_changes,
                 },
                 Crate {
                     crate_name,
@@ -304,6 +361,9 @@ impl crate::engine::report::generic::Aggregate for Report {
                     total_files: rhs_tf,
                     info_by_version,
                     wasted_by_extension: rhs_wbe,
+                    // the history of crates is shown only on their own page
+                    history: _,
+                    changes: rhs_changes,
                 },
             ) => CrateCollection {
                 total_size_in_bytes: lhs_tsb + rhs_tsb,
@@ -313,6 +373,8 @@ impl crate::engine::report::generic::Aggregate for Report {
                     info_by_crate,
                     crate_collection_info_from_version_info(crate_name, info_by_version),
                 ),
+                history,
+                changes: changes::merge(lhs_changes, rhs_changes),
             },
         }
     }
@@ -320,6 +382,7 @@ impl crate::engine::report::generic::Aggregate for Report {
     async fn complete(&mut self, _progress: &mut prodash::tree::Item, out: &mut Vec<u8>) -> Result<()> {
         use horrorshow::Template;
 
+        self.record_snapshot(std::time::SystemTime::now());
         let report = self.clone();
         report.write_to_io(out)?;
         Ok(())
@@ -8,19 +8,65 @@ mod merge;
 
 pub struct Generator;
 
+/// A format to write version reports in, next to their html page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionFormat {
+    /// The JSON document with a versioned schema, as `<version>.json`
+    Json,
+    /// A summary for use in issues and pull requests, as `<version>.md`
+    Markdown,
+}
+
+impl std::str::FromStr for VersionFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "json" => VersionFormat::Json,
+            "markdown" | "md" => VersionFormat::Markdown,
+            _ => return Err(format!("Unknown format '{}', expected one of 'json' or 'markdown'", s)),
+        })
+    }
+}
+
+/// Options to affect how waste reports are generated
+#[derive(Default, Clone)]
+pub struct Options {
+    /// The rules to classify files as waste, per crate
+    pub rules: RuleBook,
+    /// The formats to write version reports in, in addition to html
+    pub formats: Vec<VersionFormat>,
+    /// The thresholds deciding how the badge of each crate looks like
+    pub badge_thresholds: badge::BadgeThresholds,
+}
+
 // NOTE: When multiple reports should be combined, this must become a compound generator which combines
 // multiple implementations into one, statically.
 #[async_trait]
 impl super::generic::Generator for Generator {
     type Report = Report;
     type DBResult = TaskResult;
+    type Options = Options;
 
     fn name() -> &'static str {
         "waste"
     }
 
     fn version() -> &'static str {
-        "1.0.0"
+        "1.3.0"
+    }
+
+    fn options_fingerprint(options: &Self::Options) -> Option<String> {
+        let mut parts = Vec::new();
+        let rules = options.rules.fingerprint();
+        if rules != RuleBook::default().fingerprint() {
+            parts.push(format!("{:016x}", rules));
+        }
+        let thresholds = &options.badge_thresholds;
+        if *thresholds != badge::BadgeThresholds::default() {
+            parts.push(format!("badge-{}-{}", thresholds.lean, thresholds.wasteful));
+        }
+        (!parts.is_empty()).then(|| parts.join("-"))
     }
 
     fn fq_result_key(crate_name: &str, crate_version: &str, key_buf: &mut String) {
@@ -33,35 +79,93 @@ impl super::generic::Generator for Generator {
     }
 
     fn get_result(
-        connection: persistence::ThreadSafeConnection,
+        results: &persistence::TaskResultTable,
         crate_name: &str,
         crate_version: &str,
         key_buf: &mut String,
     ) -> Result<Option<TaskResult>> {
         Self::fq_result_key(crate_name, crate_version, key_buf);
-        let table = persistence::TaskResultTable { inner: connection };
-        table.get(&key_buf)
+        results.get(&key_buf)
     }
 
     async fn generate_report(
         crate_name: &str,
         crate_version: &str,
         result: TaskResult,
+        downloads: Option<u64>,
+        options: &Self::Options,
         _progress: &mut prodash::tree::Item,
     ) -> Result<Self::Report> {
-        Ok(match result {
+        let mut report = match result {
             TaskResult::ExplodedCrate {
                 entries_meta_data,
                 selected_entries,
-            } => Report::from_package(
+            } => Report::from_package_with_rules(
                 crate_name,
                 crate_version,
                 TarPackage {
                     entries_meta_data,
                     entries: selected_entries,
                 },
+                options.rules.rules_for(crate_name),
             ),
             _ => unreachable!("caller must assure we are always an exploded entry"),
+        };
+        if let Report::Version { downloads: d, .. } = &mut report {
+            *d = downloads;
+        }
+        Ok(report)
+    }
+
+    fn search_index(report: &Self::Report, out: &mut Vec<u8>) -> Result<Option<&'static str>> {
+        Ok(report.write_search_index(out)?.then_some(html::SEARCH_INDEX_FILE_NAME))
+    }
+
+    fn changes_page(report: &Self::Report, out: &mut Vec<u8>) -> Result<Option<&'static str>> {
+        Ok(report.write_changes_page(out)?.then_some(html::CHANGES_PAGE_FILE_NAME))
+    }
+
+    fn changes_feed(report: &Self::Report, out: &mut Vec<u8>) -> Result<Option<&'static str>> {
+        Ok(report.write_changes_feed(out)?.then_some(html::CHANGES_FEED_FILE_NAME))
+    }
+
+    fn record_changes(report: &mut Self::Report, previous: &Self::Report, options: &Self::Options) {
+        report.record_changes(previous, options.badge_thresholds.lean, std::time::SystemTime::now());
+    }
+
+    fn crate_badge(report: &Self::Report, options: &Self::Options, out: &mut Vec<u8>) -> Result<Option<&'static str>> {
+        Ok(report.badge(&options.badge_thresholds).map(|badge| {
+            out.extend_from_slice(badge.to_svg().as_bytes());
+            badge::BADGE_FILE_NAME
+        }))
+    }
+
+    fn version_json(report: &Self::Report, options: &Self::Options, out: &mut Vec<u8>) -> Result<bool> {
+        if !options.formats.contains(&VersionFormat::Json) {
+            return Ok(false);
+        }
+        report.write_json(out).map_err(std::io::Error::from)?;
+        Ok(true)
+    }
+
+    fn version_markdown(report: &Self::Report, options: &Self::Options, out: &mut Vec<u8>) -> Result<bool> {
+        if !options.formats.contains(&VersionFormat::Markdown) {
+            return Ok(false);
+        }
+        out.extend_from_slice(report.to_markdown().as_bytes());
+        Ok(true)
+    }
+
+    fn version_patch(report: &Self::Report, _options: &Self::Options, out: &mut Vec<u8>) -> Result<bool> {
+        Ok(match report {
+            Report::Version {
+                manifest_patch: Some(patch),
+                ..
+            } => {
+                out.extend_from_slice(patch.as_bytes());
+                true
+            }
+            _ => false,
         })
     }
 }
@@ -1,6 +1,10 @@
 use crate::{
     engine::report::generic::Aggregate,
-    engine::report::waste::{AggregateFileInfo, Fix, PotentialWaste, Report, VersionInfo},
+    engine::report::waste::{
+        changes::{Change, ChangeKind},
+        history::Snapshot,
+        AggregateFileInfo, Fix, PotentialWaste, Report, VersionInfo,
+    },
     model::TarHeader,
 };
 use common_macros::b_tree_map;
@@ -14,6 +18,11 @@ fn crate_merging_version_equivalent_to_version_merging_crate() {
         total_size_in_bytes: 1,
         total_files: 4,
         wasted_files: vec![("a.a".into(), 20)],
+        files_dropped_by_fix: vec![],
+        manifest_patch: None,
+        compressed_waste_bytes: None,
+        downloads: None,
+        provenance: None,
         suggested_fix: Some(Fix::RemoveExclude),
     };
 
@@ -27,6 +36,8 @@ fn crate_merging_version_equivalent_to_version_merging_crate() {
             "b".into()  => AggregateFileInfo {total_files: 3, total_bytes: 80},
             "c".into()  => AggregateFileInfo {total_files: 1, total_bytes: 90},
         },
+        history: vec![],
+        changes: vec![],
     };
     assert_eq!(version.clone().merge(krate.clone()), krate.merge(version));
 }
@@ -47,12 +58,16 @@ fn crate_and_crate_of_different_name() {
                         total_files: 8
                     }),
                     waste_latest_version: None,
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 },
                 "2".into() => VersionInfo {
                     all: AggregateFileInfo { total_files: 4, total_bytes: 1 },
                     waste: AggregateFileInfo { total_files: 3, total_bytes: 50 },
                     potential_gains: None,
                     waste_latest_version: None,
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 }
             },
             wasted_by_extension: b_tree_map! {
@@ -60,6 +75,8 @@ fn crate_and_crate_of_different_name() {
                 "b".into()  => AggregateFileInfo {total_files: 2, total_bytes: 20},
                 "c".into()  => AggregateFileInfo {total_files: 3, total_bytes: 30},
             },
+            history: vec![],
+            changes: vec![],
         }
         .merge(Report::Crate {
             crate_name: "b".into(),
@@ -71,6 +88,8 @@ fn crate_and_crate_of_different_name() {
                     waste: AggregateFileInfo { total_files: 6, total_bytes: 150 },
                     potential_gains: None,
                     waste_latest_version: None,
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 }
             },
             wasted_by_extension: b_tree_map! {
@@ -78,6 +97,8 @@ fn crate_and_crate_of_different_name() {
                 "b".into()  => AggregateFileInfo {total_files: 2, total_bytes: 20},
                 "d".into()  => AggregateFileInfo {total_files: 1, total_bytes: 10},
             },
+            history: vec![],
+            changes: vec![],
         }),
         Report::CrateCollection {
             total_size_in_bytes: 12,
@@ -90,13 +111,17 @@ fn crate_and_crate_of_different_name() {
                         total_bytes: 2,
                         total_files: 8
                     }),
-                    waste_latest_version: Some(("2".into(), AggregateFileInfo { total_files: 3, total_bytes: 50 }))
+                    waste_latest_version: Some(("2".into(), AggregateFileInfo { total_files: 3, total_bytes: 50 })),
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 },
                 "b".into() => VersionInfo {
                     all: AggregateFileInfo { total_files: 8, total_bytes: 10 },
                     waste: AggregateFileInfo { total_files: 6, total_bytes: 150 },
                     potential_gains: None,
-                    waste_latest_version: Some(("2".into(), AggregateFileInfo { total_files: 6, total_bytes: 150 }))
+                    waste_latest_version: Some(("2".into(), AggregateFileInfo { total_files: 6, total_bytes: 150 })),
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 }
             },
             wasted_by_extension: b_tree_map! {
@@ -105,6 +130,8 @@ fn crate_and_crate_of_different_name() {
                 "c".into()  => AggregateFileInfo {total_files: 3, total_bytes: 30},
                 "d".into()  => AggregateFileInfo {total_files: 1, total_bytes: 10},
             },
+            history: vec![],
+            changes: vec![],
         }
     );
 }
@@ -122,7 +149,9 @@ fn two_crate_collections() {
                     total_files: 5,
                     total_bytes: 10,
                 }),
-                waste_latest_version: Some(("3".into(), AggregateFileInfo { total_files: 1, total_bytes: 20},))
+                waste_latest_version: Some(("3".into(), AggregateFileInfo { total_files: 1, total_bytes: 20},)),
+                compressed_waste_bytes: None,
+                downloaded_waste_bytes: 0,
             },
         },
         wasted_by_extension: b_tree_map! {
@@ -131,6 +160,8 @@ fn two_crate_collections() {
             "c".into()  => AggregateFileInfo {total_files: 3, total_bytes: 30},
             "d".into()  => AggregateFileInfo {total_files: 1, total_bytes: 10},
         },
+        history: vec![],
+        changes: vec![],
     };
     let rhs_collection = Report::CrateCollection {
         total_size_in_bytes: 12,
@@ -143,13 +174,17 @@ fn two_crate_collections() {
                     total_files: 50,
                     total_bytes: 100,
                 }),
-                waste_latest_version: Some(("4".into(), AggregateFileInfo { total_files: 2, total_bytes: 40}))
+                waste_latest_version: Some(("4".into(), AggregateFileInfo { total_files: 2, total_bytes: 40})),
+                compressed_waste_bytes: None,
+                downloaded_waste_bytes: 0,
             },
             "b".into() => VersionInfo {
                 all: AggregateFileInfo { total_files: 8, total_bytes: 10 },
                 waste: AggregateFileInfo { total_files: 6, total_bytes: 150 },
                 potential_gains: None,
-                waste_latest_version: Some(("1".into(), AggregateFileInfo { total_files: 3, total_bytes: 50}))
+                waste_latest_version: Some(("1".into(), AggregateFileInfo { total_files: 3, total_bytes: 50})),
+                compressed_waste_bytes: None,
+                downloaded_waste_bytes: 0,
             },
         },
         wasted_by_extension: b_tree_map! {
@@ -159,6 +194,8 @@ fn two_crate_collections() {
             "d".into()  => AggregateFileInfo {total_files: 1, total_bytes: 10},
             "e".into()  => AggregateFileInfo {total_files: 4, total_bytes: 2},
         },
+        history: vec![],
+        changes: vec![],
     };
     assert_eq!(
         lhs_collection.merge(rhs_collection),
@@ -173,13 +210,17 @@ fn two_crate_collections() {
                         total_files: 55,
                         total_bytes: 110
                     }),
-                    waste_latest_version: Some(("4".into(), AggregateFileInfo { total_files: 2, total_bytes: 40}))
+                    waste_latest_version: Some(("4".into(), AggregateFileInfo { total_files: 2, total_bytes: 40})),
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 },
                 "b".into() => VersionInfo {
                     all: AggregateFileInfo { total_files: 8, total_bytes: 10 },
                     waste: AggregateFileInfo { total_files: 6, total_bytes: 150 },
                     potential_gains: None,
-                    waste_latest_version: Some(("1".into(), AggregateFileInfo { total_files: 3, total_bytes: 50}))
+                    waste_latest_version: Some(("1".into(), AggregateFileInfo { total_files: 3, total_bytes: 50})),
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 }
             },
             wasted_by_extension: b_tree_map! {
@@ -189,6 +230,8 @@ fn two_crate_collections() {
                 "d".into()  => AggregateFileInfo {total_files: 1*2, total_bytes: 10*2},
                 "e".into()  => AggregateFileInfo {total_files: 4, total_bytes: 2},
             },
+            history: vec![],
+            changes: vec![],
         }
     );
 }
@@ -209,6 +252,8 @@ fn crate_and_crate_of_same_name() {
                         total_bytes: 100
                     }),
                     waste_latest_version: None,
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 }
             },
             wasted_by_extension: b_tree_map! {
@@ -216,6 +261,8 @@ fn crate_and_crate_of_same_name() {
                 "b".into()  => AggregateFileInfo {total_files: 2, total_bytes: 20},
                 "c".into()  => AggregateFileInfo {total_files: 3, total_bytes: 30},
             },
+            history: vec![],
+            changes: vec![],
         }
         .merge(Report::Crate {
             crate_name: "a".into(),
@@ -230,6 +277,8 @@ fn crate_and_crate_of_same_name() {
                         total_bytes: 10
                     }),
                     waste_latest_version: None,
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 }
             },
             wasted_by_extension: b_tree_map! {
@@ -237,6 +286,8 @@ fn crate_and_crate_of_same_name() {
                 "b".into()  => AggregateFileInfo {total_files: 2, total_bytes: 20},
                 "d".into()  => AggregateFileInfo {total_files: 1, total_bytes: 10},
             },
+            history: vec![],
+            changes: vec![],
         }),
         Report::Crate {
             crate_name: "a".to_string(),
@@ -251,6 +302,8 @@ fn crate_and_crate_of_same_name() {
                         total_bytes: 100
                     }),
                     waste_latest_version: None,
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 },
                 "2".into() => VersionInfo {
                     all: AggregateFileInfo { total_files: 8, total_bytes: 10 },
@@ -260,6 +313,8 @@ fn crate_and_crate_of_same_name() {
                         total_bytes: 10
                     }),
                     waste_latest_version: None,
+                    compressed_waste_bytes: None,
+                    downloaded_waste_bytes: 0,
                 }
             },
             wasted_by_extension: b_tree_map! {
@@ -268,6 +323,8 @@ fn crate_and_crate_of_same_name() {
                 "c".into()  => AggregateFileInfo {total_files: 3, total_bytes: 30},
                 "d".into()  => AggregateFileInfo {total_files: 1, total_bytes: 10},
             },
+            history: vec![],
+            changes: vec![],
         }
     );
 }
@@ -281,6 +338,11 @@ fn two_versions_of_same_crate() {
             total_size_in_bytes: 1,
             total_files: 4,
             wasted_files: vec![("a.a".into(), 20), ("b/a.b".into(), 20), ("c/a.b".into(), 10)],
+            files_dropped_by_fix: vec![],
+            manifest_patch: None,
+            compressed_waste_bytes: None,
+            downloads: None,
+            provenance: None,
             suggested_fix: Some(Fix::ImprovedInclude {
                 include: vec![],
                 include_removed: vec![],
@@ -289,7 +351,8 @@ fn two_versions_of_same_crate() {
                     potential_waste: vec![TarHeader {
                         path: (&b"a/d.c"[..]).into(),
                         size: 10,
-                        entry_type: 0
+                        entry_type: 0,
+                        compressed_size: None
                     }]
                 }),
                 has_build_script: false
@@ -301,6 +364,11 @@ fn two_versions_of_same_crate() {
             total_size_in_bytes: 2,
             total_files: 5,
             wasted_files: vec![("a.a".into(), 40), ("c/a.b".into(), 50), ("d/a.c".into(), 90)],
+            files_dropped_by_fix: vec![],
+            manifest_patch: None,
+            compressed_waste_bytes: None,
+            downloads: None,
+            provenance: None,
             suggested_fix: Some(Fix::ImprovedInclude {
                 include: vec![],
                 include_removed: vec![],
@@ -309,7 +377,8 @@ fn two_versions_of_same_crate() {
                     potential_waste: vec![TarHeader {
                         path: (&b"a/d.c"[..]).into(),
                         size: 100,
-                        entry_type: 0
+                        entry_type: 0,
+                        compressed_size: None
                     }]
                 }),
                 has_build_script: false
@@ -325,12 +394,16 @@ fn two_versions_of_same_crate() {
                                 waste: AggregateFileInfo { total_files: 3, total_bytes: 50 },
                                 potential_gains: Some(AggregateFileInfo {total_files: 1, total_bytes: 10}),
                                 waste_latest_version: None,
+                                compressed_waste_bytes: None,
+                                downloaded_waste_bytes: 0,
                               },
                  "2".into() => VersionInfo {
                                 all: AggregateFileInfo { total_files: 5, total_bytes: 2 },
                                 waste: AggregateFileInfo { total_files: 3, total_bytes: 180 },
                                 potential_gains: Some(AggregateFileInfo {total_files: 1, total_bytes: 100}),
                                 waste_latest_version: None,
+                                compressed_waste_bytes: None,
+                                downloaded_waste_bytes: 0,
                               },
             },
             wasted_by_extension: b_tree_map! {
@@ -338,6 +411,8 @@ fn two_versions_of_same_crate() {
                 "b".into()  => AggregateFileInfo {total_files: 3, total_bytes: 80},
                 "c".into()  => AggregateFileInfo {total_files: 1, total_bytes: 90},
             },
+            history: vec![],
+            changes: vec![],
         }
     );
 }
@@ -351,6 +426,11 @@ fn two_versions_of_different_crate() {
             total_size_in_bytes: 1,
             total_files: 4,
             wasted_files: vec![("a.a".into(), 20), ("b/a.b".into(), 20), ("c/a.b".into(), 10)],
+            files_dropped_by_fix: vec![],
+            manifest_patch: None,
+            compressed_waste_bytes: None,
+            downloads: None,
+            provenance: None,
             suggested_fix: Some(Fix::ImprovedInclude {
                 include: vec![],
                 include_removed: vec![],
@@ -359,7 +439,8 @@ fn two_versions_of_different_crate() {
                     potential_waste: vec![TarHeader {
                         path: (&b"a/b.c"[..]).into(),
                         size: 10,
-                        entry_type: 0
+                        entry_type: 0,
+                        compressed_size: None
                     }]
                 }),
                 has_build_script: false
@@ -371,6 +452,11 @@ fn two_versions_of_different_crate() {
             total_size_in_bytes: 2,
             total_files: 5,
             wasted_files: vec![("a.a".into(), 40), ("c/a.b".into(), 50), ("d/a.c".into(), 90)],
+            files_dropped_by_fix: vec![],
+            manifest_patch: None,
+            compressed_waste_bytes: None,
+            downloads: None,
+            provenance: None,
             suggested_fix: Some(Fix::ImprovedInclude {
                 include: vec![],
                 include_removed: vec![],
@@ -379,7 +465,8 @@ fn two_versions_of_different_crate() {
                     potential_waste: vec![TarHeader {
                         path: (&b"a/d.c"[..]).into(),
                         size: 100,
-                        entry_type: 0
+                        entry_type: 0,
+                        compressed_size: None
                     }]
                 }),
                 has_build_script: false
@@ -393,13 +480,17 @@ fn two_versions_of_different_crate() {
                                 all: AggregateFileInfo { total_files: 4, total_bytes: 1 },
                                 waste: AggregateFileInfo { total_files: 3, total_bytes: 50 },
                                 potential_gains: Some(AggregateFileInfo{total_files: 1, total_bytes: 10}),
-                                waste_latest_version: Some(("1".into(), AggregateFileInfo { total_files: 3, total_bytes: 50 }))
+                                waste_latest_version: Some(("1".into(), AggregateFileInfo { total_files: 3, total_bytes: 50 })),
+                                compressed_waste_bytes: None,
+                                downloaded_waste_bytes: 0,
                               },
                  "b".into() => VersionInfo {
                                 all: AggregateFileInfo { total_files: 5, total_bytes: 2 },
                                 waste: AggregateFileInfo { total_files: 3, total_bytes: 180 },
                                 potential_gains: Some(AggregateFileInfo{total_files: 1, total_bytes: 100}),
-                                waste_latest_version: Some(("1".into(), AggregateFileInfo { total_files: 3, total_bytes: 180 }))
+                                waste_latest_version: Some(("1".into(), AggregateFileInfo { total_files: 3, total_bytes: 180 })),
+                                compressed_waste_bytes: None,
+                                downloaded_waste_bytes: 0,
                               },
             },
             wasted_by_extension: b_tree_map! {
@@ -407,6 +498,158 @@ fn two_versions_of_different_crate() {
                 "b".into()  => AggregateFileInfo {total_files: 3, total_bytes: 80},
                 "c".into()  => AggregateFileInfo {total_files: 1, total_bytes: 90},
             },
+            history: vec![],
+            changes: vec![],
         }
     );
 }
+
+#[test]
+fn download_waste_is_weighted_by_the_downloads_of_each_version() {
+    let version = |crate_name: &str, crate_version: &str, compressed_waste_bytes, downloads| Report::Version {
+        crate_name: crate_name.into(),
+        crate_version: crate_version.into(),
+        total_size_in_bytes: 200,
+        total_files: 2,
+        wasted_files: vec![("a.a".into(), 100)],
+        files_dropped_by_fix: vec![],
+        manifest_patch: None,
+        compressed_waste_bytes,
+        downloads,
+        provenance: None,
+        suggested_fix: Some(Fix::RemoveExclude),
+    };
+    let krate = version("a", "1", Some(20), Some(3)).merge(version("a", "2", Some(10), None));
+    match &krate {
+        Report::Crate { info_by_version, .. } => {
+            let download_waste = |v: &VersionInfo| (v.compressed_waste_bytes, v.downloaded_waste_bytes);
+            assert_eq!(download_waste(&info_by_version["1"]), (Some(20), 60));
+            assert_eq!(
+                download_waste(&info_by_version["2"]),
+                (Some(10), 0),
+                "unknown downloads don't count"
+            );
+        }
+        _ => unreachable!("crate report"),
+    }
+    match krate.merge(version("b", "1", None, Some(5))) {
+        Report::CrateCollection { info_by_crate, .. } => {
+            assert_eq!(info_by_crate["a"].compressed_waste_bytes, Some(30));
+            assert_eq!(info_by_crate["a"].downloaded_waste_bytes, 60);
+            assert_eq!(
+                info_by_crate["b"].downloaded_waste_bytes, 0,
+                "unknown compressed sizes don't count"
+            );
+            assert_eq!(
+                info_by_crate["b"].compressed_waste_bytes, None,
+                "unknown compressed sizes stay unknown"
+            );
+        }
+        _ => unreachable!("collection report"),
+    }
+}
+
+#[test]
+fn histories_of_the_same_report_are_combined_and_crate_histories_stay_with_their_crate() {
+    let snapshot = |time| Snapshot {
+        time,
+        total_bytes: 10,
+        wasted_bytes: 5,
+        potential_gains_bytes: 0,
+        children: 1,
+    };
+    let krate = |name: &str, history| Report::Crate {
+        crate_name: name.into(),
+        total_size_in_bytes: 10,
+        total_files: 1,
+        info_by_version: BTreeMap::new(),
+        wasted_by_extension: BTreeMap::new(),
+        history,
+        changes: vec![],
+    };
+    let day = 24 * 60 * 60;
+    match krate("a", vec![snapshot(day)]).merge(krate("a", vec![snapshot(2 * day)])) {
+        Report::Crate { history, .. } => assert_eq!(history, vec![snapshot(day), snapshot(2 * day)]),
+        _ => unreachable!("crate report"),
+    }
+    match krate("a", vec![snapshot(day)]).merge(krate("b", vec![snapshot(2 * day)])) {
+        Report::CrateCollection { history, .. } => assert!(history.is_empty()),
+        _ => unreachable!("collection report"),
+    }
+}
+
+#[test]
+fn changes_of_crates_are_collected_by_their_collection() {
+    let change = |crate_name: &str, time| Change {
+        crate_name: crate_name.into(),
+        version: "1.0.0".into(),
+        previous_version: "0.9.0".into(),
+        time,
+        kind: ChangeKind::BecameLean,
+        total_bytes: 10,
+        wasted_bytes: 0,
+        previous_total_bytes: 10,
+        previous_wasted_bytes: 5,
+    };
+    let krate = |name: &str, time| Report::Crate {
+        crate_name: name.into(),
+        total_size_in_bytes: 10,
+        total_files: 1,
+        info_by_version: BTreeMap::new(),
+        wasted_by_extension: BTreeMap::new(),
+        history: vec![],
+        changes: vec![change(name, time)],
+    };
+    let collection = krate("a", 1).merge(krate("b", 2)).merge(krate("c", 3));
+    assert_eq!(
+        collection.changes(),
+        &[change("c", 3), change("b", 2), change("a", 1)][..],
+        "the most recent changes come first"
+    );
+    match krate("a", 1).merge(krate("a", 2)) {
+        Report::Crate { changes, .. } => assert_eq!(changes, vec![change("a", 2)], "the same version changed twice"),
+        _ => unreachable!("crate report"),
+    }
+}
+
+#[test]
+fn the_latest_version_of_a_crate_is_its_latest_release() {
+    let version = |crate_name: &str, crate_version: &str, wasted_bytes| Report::Version {
+        crate_name: crate_name.into(),
+        crate_version: crate_version.into(),
+        total_size_in_bytes: 200,
+        total_files: 2,
+        wasted_files: vec![("a.a".into(), wasted_bytes)],
+        files_dropped_by_fix: vec![],
+        manifest_patch: None,
+        compressed_waste_bytes: None,
+        downloads: None,
+        provenance: None,
+        suggested_fix: None,
+    };
+    let collection = version("a", "0.9.0", 10)
+        .merge(version("a", "0.10.0", 20))
+        .merge(version("a", "0.11.0-alpha.1", 30))
+        .merge(version("b", "1.0.0-beta.2", 40).merge(version("b", "1.0.0-beta.10", 50)));
+    match collection {
+        Report::CrateCollection { info_by_crate, .. } => {
+            let latest_waste = |crate_name: &str| {
+                info_by_crate[crate_name]
+                    .waste_latest_version
+                    .as_ref()
+                    .map(|(version, waste)| (version.as_str(), waste.total_bytes))
+            };
+            assert_eq!(
+                latest_waste("a"),
+                Some(("0.10.0", 20)),
+                "versions are compared numerically, and pre-releases aren't the latest version"
+            );
+            assert_eq!(
+                latest_waste("b"),
+                Some(("1.0.0-beta.10", 50)),
+                "without releases, the latest pre-release is the latest version"
+            );
+        }
+        _ => unreachable!("collection report"),
+    }
+}
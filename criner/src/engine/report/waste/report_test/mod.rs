@@ -1 +1,2 @@
 mod merge;
+mod options;
@@ -0,0 +1,41 @@
+use crate::engine::report::{
+    generic::Generator as _,
+    waste::{badge::BadgeThresholds, Generator, Options, RuleBook, VersionFormat},
+};
+
+#[test]
+fn only_options_changing_reports_are_fingerprinted() {
+    assert_eq!(Generator::options_fingerprint(&Options::default()), None);
+    assert_eq!(
+        Generator::options_fingerprint(&Options {
+            formats: vec![VersionFormat::Markdown],
+            ..Default::default()
+        }),
+        None,
+        "formats only affect which files are written"
+    );
+
+    let thresholds = Options {
+        badge_thresholds: BadgeThresholds::new(2.0, 25.0).expect("valid thresholds"),
+        ..Default::default()
+    };
+    assert_eq!(
+        Generator::options_fingerprint(&thresholds).as_deref(),
+        Some("badge-2-25")
+    );
+
+    let rules = RuleBook::from_toml("default = \"team\"\n[sets.team]\nremove_exclude = [\"**/assets/**/*\"]")
+        .expect("valid rules");
+    let rules_fingerprint = format!("{:016x}", rules.fingerprint());
+    assert_eq!(
+        Generator::options_fingerprint(&Options {
+            rules: rules.clone(),
+            ..Default::default()
+        }),
+        Some(rules_fingerprint.clone())
+    );
+    assert_eq!(
+        Generator::options_fingerprint(&Options { rules, ..thresholds }),
+        Some(format!("{}-badge-2-25", rules_fingerprint))
+    );
+}
@@ -1,4 +1,10 @@
-use crate::{engine::stage, error::Result, model, persistence::Db, utils::*};
+use crate::{
+    engine::{report, stage},
+    error::Result,
+    model,
+    persistence::Db,
+    utils::*,
+};
 use futures_util::{
     future::{Either, FutureExt},
     stream::StreamExt,
@@ -11,6 +17,9 @@ use std::{
     time::{Duration, SystemTime},
 };
 
+pub use report::waste::{badge::BadgeThresholds, Options as WasteReportOptions, RuleBook, VersionFormat};
+pub use stage::report::{PublishOptions, PushAuth};
+
 pub struct StageRunSettings {
     /// Wait for the given duration after the stage ran
     pub every: Duration,
@@ -42,6 +51,8 @@ pub async fn non_blocking(
     fetch_settings: StageRunSettings,
     process_settings: StageRunSettings,
     report_settings: GlobStageRunSettings,
+    waste_report_options: report::waste::Options,
+    publish_options: PublishOptions,
     download_crates_io_database_every_24_hours_starting_at: Option<time::Time>,
     assets_dir: PathBuf,
 ) -> Result<()> {
@@ -123,6 +134,8 @@ pub async fn non_blocking(
     ));
 
     let stage = report_settings;
+    let waste_report_options = Arc::new(waste_report_options);
+    let publish_options = Arc::new(publish_options);
     let report_handle = crate::spawn(repeat_every_s(
         stage.run.every.as_secs() as usize,
         {
@@ -138,6 +151,8 @@ pub async fn non_blocking(
                 let assets_dir = assets_dir.clone();
                 let glob = stage.glob.clone();
                 let interrupt_control = interrupt_control.clone();
+                let waste_report_options = waste_report_options.clone();
+                let publish_options = publish_options.clone();
                 async move {
                     let ctrl = interrupt_control;
                     ctrl.send(Interruptible::Deferred).await.ok(); // there might be no TUI
@@ -148,6 +163,8 @@ pub async fn non_blocking(
                         glob.clone(),
                         deadline,
                         cpu_o_bound_processors,
+                        waste_report_options,
+                        &publish_options,
                     )
                     .await;
                     ctrl.send(Interruptible::Instantly).await.ok(); // there might be no TUI
@@ -185,6 +202,7 @@ impl From<Interruptible> for prodash::render::tui::Event {
 /// For convenience, run the engine and block until done.
 pub fn blocking(
     db: impl AsRef<Path>,
+    storage: Option<crate::StorageKind>,
     crates_io_path: impl AsRef<Path>,
     deadline: Option<SystemTime>,
     io_bound_processors: u32,
@@ -194,13 +212,18 @@ pub fn blocking(
     fetch_settings: StageRunSettings,
     process_settings: StageRunSettings,
     report_settings: GlobStageRunSettings,
+    waste_report_options: report::waste::Options,
+    publish_options: PublishOptions,
     download_crates_io_database_every_24_hours_starting_at: Option<time::Time>,
     root: Arc<prodash::tree::Root>,
     gui: Option<prodash::render::tui::Options>,
 ) -> Result<()> {
     let start_of_computation = SystemTime::now();
     let assets_dir = db.as_ref().join("assets");
-    let db = Db::open(db)?;
+    let db = match storage {
+        Some(storage) => Db::open_with_storage(db, storage)?,
+        None => Db::open(db)?,
+    };
     std::fs::create_dir_all(&assets_dir)?;
     let (interrupt_control_sink, interrupt_control_stream) = async_channel::bounded::<Interruptible>(1);
 
@@ -218,6 +241,8 @@ pub fn blocking(
         fetch_settings,
         process_settings,
         report_settings,
+        waste_report_options,
+        publish_options,
         download_crates_io_database_every_24_hours_starting_at,
         assets_dir,
     );
@@ -4,32 +4,432 @@ use crate::{
     {Error, Result},
 };
 use futures_util::{future::BoxFuture, FutureExt};
+use gix::{
+    bstr::{BStr, BString, ByteSlice},
+    objs::{
+        tree::{Entry, EntryKind, EntryMode},
+        Kind, WriteTo,
+    },
+    refs::transaction::PreviousValue,
+    remote::Direction,
+    ObjectId,
+};
+use gix_pack::data::output;
 use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
     path::{Path, PathBuf},
-    sync::atomic::{AtomicU64, Ordering},
+    process::Command,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
     time::{SystemTime, UNIX_EPOCH},
 };
 
-static TOTAL_LOOSE_OBJECTS_WRITTEN: AtomicU64 = AtomicU64::new(0);
-
-fn file_index_entry(path: PathBuf, file_size: usize) -> git2::IndexEntry {
-    use std::os::unix::ffi::OsStringExt;
-    git2::IndexEntry {
-        ctime: git2::IndexTime::new(0, 0),
-        mtime: git2::IndexTime::new(0, 0),
-        dev: 0,
-        ino: 0,
-        mode: 0o100_644,
-        uid: 0,
-        gid: 0,
-        file_size: file_size as u32,
-        id: git2::Oid::zero(),
-        flags: 0,
-        flags_extended: 0,
-        path: path.into_os_string().into_vec(),
+static TOTAL_BLOBS_WRITTEN: AtomicU64 = AtomicU64::new(0);
+static TOTAL_BLOBS_SKIPPED: AtomicU64 = AtomicU64::new(0);
+/// The amount of bytes of objects to keep in memory before writing them into a pack
+const MAX_UNPACKED_BYTES: usize = 256 * 1024 * 1024;
+/// The amount of packs in a repository above which they are consolidated
+const MAX_PACKS: usize = 16;
+/// The amount of objects to keep in memory at a time while consolidating packs
+const CONSOLIDATED_OBJECTS_PER_CHUNK: usize = 4096;
+/// Answers the passphrase prompt of SSH without the passphrase ever being written to disk
+const ASKPASS_SCRIPT: &str = "#!/bin/sh\nprintf '%s\\n' \"$CRINER_REPORT_PUSH_SSH_PASSPHRASE\"\n";
+
+/// How to authenticate when pushing reports to remotes which aren't on the local file system
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushAuth {
+    /// Use HTTP basic authentication with the username and password in the `CRINER_REPORT_PUSH_HTTP_USERNAME`
+    /// and `CRINER_REPORT_PUSH_HTTP_PASSWORD` environment variables
+    HttpUserPass,
+    /// Use the SSH private key at the given path, whose passphrase is read from the
+    /// `CRINER_REPORT_PUSH_SSH_PASSPHRASE` environment variable if set
+    SshKey(PathBuf),
+    /// Use the keys of a running SSH agent
+    SshAgent,
+}
+
+/// Options to affect how reports are published, either by committing them to the git repository they are written
+/// into and pushing them, or by writing them into a static site
+#[derive(Debug, Clone)]
+pub struct PublishOptions {
+    /// If set, reports are written into this directory instead of the report directory, without using git
+    pub site_dir: Option<PathBuf>,
+    /// The name of the remote to push to, or `None` to use the upstream remote of the current branch or 'origin'
+    pub remote: Option<String>,
+    /// The branch on the remote to push to
+    pub branch: String,
+    pub auth: PushAuth,
+    /// The name of the author and committer of each commit
+    pub author_name: String,
+    /// The email of the author and committer of each commit
+    pub author_email: String,
+    /// The message of each commit, in which `{files}` is replaced with the amount of files changed in the reporting
+    /// run, `{total_files}` with the amount of files in the repository and `{time}` with the time of the commit
+    pub message: String,
+    /// If false, commits are only created locally
+    pub push: bool,
+}
+
+impl Default for PublishOptions {
+    fn default() -> Self {
+        PublishOptions {
+            site_dir: None,
+            remote: None,
+            branch: "main".into(),
+            auth: PushAuth::HttpUserPass,
+            author_name: "Criner".into(),
+            author_email: "https://github.com/the-lean-crate/criner".into(),
+            message: "update {files} reports".into(),
+            push: true,
+        }
     }
 }
 
+impl PublishOptions {
+    fn commit_message(&self, files: u64, total_files: usize, time: SystemTime) -> String {
+        self.message
+            .replace("{files}", &files.to_string())
+            .replace("{total_files}", &total_files.to_string())
+            .replace("{time}", &humantime::format_rfc3339_seconds(time).to_string())
+    }
+
+    /// Configure the `git` program to authenticate as configured, and to fail instead of prompting for anything.
+    /// `git_dir` receives the script answering the passphrase prompt of SSH, if a passphrase is set.
+    fn configure_auth(&self, git: &mut Command, git_dir: &Path) -> Result<()> {
+        git.env("GIT_TERMINAL_PROMPT", "0").stdin(std::process::Stdio::null());
+        match &self.auth {
+            PushAuth::HttpUserPass => {
+                // fail early with a helpful message, the helper only reads the variables
+                env_var("CRINER_REPORT_PUSH_HTTP_USERNAME")?;
+                env_var("CRINER_REPORT_PUSH_HTTP_PASSWORD")?;
+                git.args([
+                    "-c",
+                    "credential.helper=",
+                    "-c",
+                    concat!(
+                        "credential.helper=!f() { ",
+                        r#"echo "username=$CRINER_REPORT_PUSH_HTTP_USERNAME"; "#,
+                        r#"echo "password=$CRINER_REPORT_PUSH_HTTP_PASSWORD"; "#,
+                        "}; f"
+                    ),
+                ]);
+            }
+            PushAuth::SshKey(private_key) => {
+                let ssh = format!(
+                    "ssh -i '{}' -o IdentitiesOnly=yes",
+                    private_key.display().to_string().replace('\'', r"'\''")
+                );
+                if std::env::var_os("CRINER_REPORT_PUSH_SSH_PASSPHRASE").is_some() {
+                    // ssh reads passphrases only from a terminal or a program, which only reads the variable
+                    let askpass = git_dir.join("criner-ssh-askpass");
+                    std::fs::write(&askpass, ASKPASS_SCRIPT)?;
+                    std::fs::set_permissions(&askpass, std::fs::Permissions::from_mode(0o700))?;
+                    git.env("GIT_SSH_COMMAND", format!("{} -o NumberOfPasswordPrompts=1", ssh))
+                        .env("SSH_ASKPASS", askpass)
+                        .env("SSH_ASKPASS_REQUIRE", "force");
+                } else {
+                    git.env("GIT_SSH_COMMAND", format!("{} -o BatchMode=yes", ssh));
+                }
+            }
+            PushAuth::SshAgent => {
+                git.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Objects to add to a repository, kept in memory and written into packs instead of one loose object per file
+struct Pack {
+    repo: gix::Repository,
+    objects: Vec<(ObjectId, Kind, Vec<u8>)>,
+    ids: HashSet<ObjectId>,
+    unpacked_bytes: usize,
+}
+
+impl Pack {
+    fn new(repo: &gix::Repository) -> Self {
+        Pack {
+            repo: repo.clone(),
+            objects: Vec::new(),
+            ids: HashSet::new(),
+            unpacked_bytes: 0,
+        }
+    }
+
+    fn contains(&self, id: &ObjectId) -> bool {
+        self.ids.contains(id) || self.repo.has_object(id)
+    }
+
+    /// Add the object with `id`, `kind` and `data` unless the repository has it already.
+    fn add_with_id(&mut self, id: ObjectId, kind: Kind, data: Vec<u8>) -> Result<()> {
+        if self.contains(&id) {
+            return Ok(());
+        }
+        self.ids.insert(id);
+        self.unpacked_bytes += data.len();
+        self.objects.push((id, kind, data));
+        if self.unpacked_bytes > MAX_UNPACKED_BYTES {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, object: &impl WriteTo) -> Result<ObjectId> {
+        let mut data = Vec::new();
+        object.write_to(&mut data)?;
+        let id = gix::objs::compute_hash(self.repo.object_hash(), object.kind(), &data);
+        self.add_with_id(id, object.kind(), data)?;
+        Ok(id)
+    }
+
+    /// Write all objects added since the last call into a new pack of the repository, and consolidate its packs if
+    /// there are too many.
+    fn flush(&mut self) -> Result<()> {
+        if self.objects.is_empty() {
+            return Ok(());
+        }
+        let entries = self
+            .objects
+            .iter()
+            .map(|(id, kind, data)| {
+                output::Entry::from_data(&output::Count::from_data(*id, None), &gix::objs::Data::new(*kind, data))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        write_pack(&self.repo, entries.len() as u32, std::iter::once(Ok(entries)))?;
+        self.objects.clear();
+        self.unpacked_bytes = 0;
+        consolidate_packs(&self.repo)
+    }
+}
+
+/// Write a pack with `num_entries` objects, produced in chunks by `chunks`, into the pack directory of `repo`.
+fn write_pack(
+    repo: &gix::Repository,
+    num_entries: u32,
+    chunks: impl Iterator<Item = Result<Vec<output::Entry>>>,
+) -> Result<gix_pack::bundle::write::Outcome> {
+    let pack_dir = repo.common_dir().join("objects").join("pack");
+    let object_hash = repo.object_hash();
+    // the pack is streamed through a file as it is only indexed once complete, which may not fit into memory
+    let tmp_path = pack_dir.join(format!("tmp_criner_{}.pack", std::process::id()));
+    let res = (|| {
+        let mut error = None;
+        {
+            let mut out = std::io::BufWriter::new(std::fs::File::create(&tmp_path)?);
+            let chunks = chunks.map_while(|chunk| match chunk {
+                Ok(chunk) => Some(Ok::<_, std::convert::Infallible>(chunk)),
+                Err(err) => {
+                    error = Some(err);
+                    None
+                }
+            });
+            for written in output::bytes::FromEntriesIter::new(
+                chunks,
+                &mut out,
+                num_entries,
+                gix_pack::data::Version::V2,
+                object_hash,
+            ) {
+                written?;
+            }
+            std::io::Write::flush(&mut out)?;
+        }
+        if let Some(err) = error {
+            return Err(err);
+        }
+        let outcome = gix_pack::Bundle::write_to_directory(
+            &mut std::io::BufReader::new(std::fs::File::open(&tmp_path)?),
+            Some(&pack_dir),
+            &mut gix::progress::Discard,
+            &AtomicBool::new(false),
+            None::<gix::objs::find::Never>,
+            gix_pack::bundle::write::Options {
+                object_hash,
+                ..Default::default()
+            },
+        )?;
+        // only we write into the repository, so there is nothing to protect the pack from until refs point to it
+        if let Some(keep_path) = outcome.keep_path.as_ref() {
+            std::fs::remove_file(keep_path)?;
+        }
+        Ok(outcome)
+    })();
+    std::fs::remove_file(&tmp_path).ok();
+    res
+}
+
+/// Merge all packs of `repo` but the largest one into a single pack once there are more than `MAX_PACKS`, as each
+/// pack makes looking up objects slower. Packs with a `.keep` file are left alone, just like `git repack` does.
+fn consolidate_packs(repo: &gix::Repository) -> Result<()> {
+    let pack_dir = repo.common_dir().join("objects").join("pack");
+    let mut packs = Vec::new();
+    for entry in std::fs::read_dir(&pack_dir)? {
+        let index_path = entry?.path();
+        if index_path.extension().is_some_and(|ext| ext == "idx") && !index_path.with_extension("keep").exists() {
+            packs.push((index_path.with_extension("pack").metadata()?.len(), index_path));
+        }
+    }
+    if packs.len() <= MAX_PACKS {
+        return Ok(());
+    }
+    packs.sort();
+    packs.pop();
+
+    let mut ids = BTreeSet::new();
+    for (_, index_path) in &packs {
+        ids.extend(
+            gix_pack::index::File::at(index_path, repo.object_hash())?
+                .iter()
+                .map(|e| e.oid),
+        );
+    }
+    let num_entries = ids.len() as u32;
+    let ids = ids.into_iter().collect::<Vec<_>>();
+    let outcome = write_pack(
+        repo,
+        num_entries,
+        ids.chunks(CONSOLIDATED_OBJECTS_PER_CHUNK).map(|ids| {
+            ids.iter()
+                .map(|id| {
+                    let object = repo.find_object(*id)?;
+                    Ok(output::Entry::from_data(
+                        &output::Count::from_data(*id, None),
+                        &gix::objs::Data::new(object.kind, &object.data),
+                    )?)
+                })
+                .collect::<Result<Vec<_>>>()
+        }),
+    )?;
+    for (_, index_path) in packs {
+        if outcome.index_path.as_ref() == Some(&index_path) {
+            continue;
+        }
+        for extension in &["pack", "rev", "bitmap", "idx"] {
+            match std::fs::remove_file(index_path.with_extension(extension)) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// All files of a tree by their path, which serves as in-memory index
+type Files = BTreeMap<BString, (EntryMode, ObjectId)>;
+
+fn files_of(index: &gix::index::State) -> Files {
+    index
+        .entries()
+        .iter()
+        .filter(|e| e.stage() == gix::index::entry::Stage::Unconflicted)
+        .filter_map(|e| {
+            e.mode
+                .to_tree_entry_mode()
+                .map(|mode| (e.path(index).to_owned(), (mode, e.id)))
+        })
+        .collect()
+}
+
+/// Add the trees of all `files`, sorted by their path relative to the tree, to `pack` and return the root tree id.
+fn write_tree(files: &[(&BStr, EntryMode, ObjectId)], pack: &mut Pack) -> Result<ObjectId> {
+    let mut tree = gix::objs::Tree::empty();
+    let mut remaining = files;
+    while let Some(&(path, mode, oid)) = remaining.first() {
+        match path.find_byte(b'/') {
+            None => {
+                tree.entries.push(Entry {
+                    mode,
+                    filename: path.into(),
+                    oid,
+                });
+                remaining = &remaining[1..];
+            }
+            Some(slash) => {
+                let directory = &path[..=slash];
+                let children: Vec<_> = remaining
+                    .iter()
+                    .take_while(|(path, ..)| path.starts_with(directory))
+                    .map(|(path, mode, oid)| (path[directory.len()..].as_bstr(), *mode, *oid))
+                    .collect();
+                remaining = &remaining[children.len()..];
+                tree.entries.push(Entry {
+                    mode: EntryKind::Tree.into(),
+                    filename: path[..slash].into(),
+                    oid: write_tree(&children, pack)?,
+                });
+            }
+        }
+    }
+    tree.entries.sort();
+    pack.add(&tree)
+}
+
+/// Add `tree` with all trees and blobs it contains to `pack`, if the repository of `pack` doesn't have it yet.
+fn add_missing_tree(repo: &gix::Repository, tree: ObjectId, pack: &mut Pack) -> Result<()> {
+    if pack.contains(&tree) {
+        return Ok(());
+    }
+    let tree = repo.find_object(tree)?.detach();
+    for entry in gix::objs::TreeRef::from_bytes(&tree.data)?.entries {
+        if entry.mode.is_tree() {
+            add_missing_tree(repo, entry.oid.to_owned(), pack)?;
+        } else if entry.mode.is_blob_or_symlink() && !pack.contains(&entry.oid.to_owned()) {
+            let blob = repo.find_object(entry.oid)?.detach();
+            pack.add_with_id(blob.id, blob.kind, blob.data)?;
+        }
+    }
+    // Objects are added after everything they refer to, so that a repository never has incomplete objects
+    pack.add_with_id(tree.id, tree.kind, tree.data)
+}
+
+/// Push `commit` from `repo` into the repository at `path` as `branch`, overwriting it.
+fn push_to_local_repository(repo: &gix::Repository, commit: ObjectId, path: &Path, branch: &str) -> Result<()> {
+    let remote = gix::open(path)?;
+    let mut pack = Pack::new(&remote);
+    let mut commits = Vec::new();
+    let mut next = vec![commit];
+    while let Some(id) = next.pop() {
+        if pack.contains(&id) || commits.iter().any(|(c, _)| *c == id) {
+            continue;
+        }
+        let commit = repo.find_object(id)?.detach();
+        next.extend(gix::objs::CommitRef::from_bytes(&commit.data)?.parents());
+        commits.push((id, commit));
+    }
+    for (id, commit) in commits.into_iter().rev() {
+        add_missing_tree(repo, gix::objs::CommitRef::from_bytes(&commit.data)?.tree(), &mut pack)?;
+        pack.add_with_id(id, commit.kind, commit.data)?;
+    }
+    pack.flush()?;
+    remote.reference(
+        format!("refs/heads/{}", branch),
+        commit,
+        PreviousValue::Any,
+        "push: forced-update",
+    )?;
+    Ok(())
+}
+
+/// Push with the `git` program, for all remotes which can't be pushed to with gitoxide yet.
+fn push_with_git(repo: &gix::Repository, remote_name: &str, refspec: &str, options: &PublishOptions) -> Result<()> {
+    let mut git = Command::new("git");
+    options.configure_auth(&mut git, repo.git_dir())?;
+    let output = git
+        .arg("--git-dir")
+        .arg(repo.git_dir())
+        .args(["push", remote_name, refspec])
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::Message(format!(
+            "git push to '{}' failed: {}",
+            remote_name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
 fn env_var(name: &str) -> Result<String> {
     std::env::var(name).map_err(|e| match e {
         std::env::VarError::NotPresent => crate::Error::Message(format!("environment variable {:?} must be set", name)),
@@ -43,169 +443,171 @@ fn env_var(name: &str) -> Result<String> {
 pub fn select_callback(
     processors: u32,
     report_dir: &Path,
+    options: &PublishOptions,
     mut progress: prodash::tree::Item,
 ) -> (
     WriteCallback,
     WriteCallbackState,
     Option<std::thread::JoinHandle<Result<()>>>,
 ) {
-    match git2::Repository::open(report_dir) {
+    // The author of commits is also the one recorded in the reflog
+    let identity = gix::open::Options::default().config_overrides([
+        format!("committer.name={}", options.author_name),
+        format!("committer.email={}", options.author_email),
+    ]);
+    match gix::open_opts(report_dir, identity) {
         Ok(repo) => {
             let (tx, rx) = async_channel::bounded(processors as usize);
             let is_bare_repo = repo.is_bare();
             let report_dir = report_dir.to_owned();
+            let options = options.clone();
             let handle = std::thread::spawn(move || -> Result<()> {
                 let res = (|| {
                     progress.init(None, Some("files stored in index".into()));
-                    let mut index = {
-                        let mut i = repo.index()?;
-                        if is_bare_repo {
-                            if let Ok(tree_oid) = repo
-                                .head()
-                                .and_then(|h| h.resolve())
-                                .and_then(|h| h.peel_to_tree())
-                                .map(|t| t.id())
-                            {
-                                progress.info(format!("reading latest tree into in-memory index: {}", tree_oid));
+                    let head_tree = repo.head_tree_id().ok().map(|id| id.detach());
+                    let mut files = if is_bare_repo {
+                        match head_tree {
+                            Some(tree_id) => {
+                                progress.info(format!("reading latest tree into in-memory index: {}", tree_id));
                                 progress.blocked("reading tree into in-memory index", None);
-                                i.read_tree(&repo.find_tree(tree_oid).expect("a tree object to exist"))?;
+                                let files = files_of(&*repo.index_from_tree(&tree_id)?);
                                 progress.done("read tree into memory index");
+                                files
                             }
+                            None => Files::new(),
                         }
-                        i
+                    } else {
+                        let index = repo.index_or_empty()?;
+                        files_of(&index)
                     };
-                    let mut req_count = 0u64;
+                    let mut pack = Pack::new(&repo);
+                    let (mut req_count, mut blob_count) = (0u64, 0u64);
                     while let Ok(WriteRequest { path, content }) = futures_lite::future::block_on(rx.recv()) {
                         let path = path.strip_prefix(&report_dir)?;
                         req_count += 1;
-                        let entry = file_index_entry(path.to_owned(), content.len());
-                        index.add_frombuffer(&entry, &content)?;
                         progress.set(req_count as usize);
+                        let id = gix::objs::compute_hash(repo.object_hash(), Kind::Blob, &content);
+                        let path = BString::from(path.as_os_str().as_bytes());
+                        if files.get(&path).map(|(_, existing)| *existing) == Some(id) {
+                            continue;
+                        }
+                        pack.add_with_id(id, Kind::Blob, content)?;
+                        files.insert(path, (EntryKind::Blob.into(), id));
+                        blob_count += 1;
                     }
 
                     progress.init(Some(5), Some("steps".into()));
-                    let tree_oid = {
+                    let tree_id = {
                         progress.set(1);
                         progress.blocked("writing tree", None);
                         progress.info(format!(
-                            "writing tree with {} new entries and a total of {} entries",
-                            req_count,
-                            index.len()
+                            "writing tree with {} changed entries and a total of {} entries",
+                            blob_count,
+                            files.len()
                         ));
-                        let oid = index.write_tree()?;
+                        let files: Vec<_> = files
+                            .iter()
+                            .map(|(path, (mode, id))| (path.as_bstr(), *mode, *id))
+                            .collect();
+                        let oid = write_tree(&files, &mut pack)?;
                         progress.done("Tree written successfully");
                         oid
                     };
 
-                    TOTAL_LOOSE_OBJECTS_WRITTEN.fetch_add(req_count, Ordering::SeqCst);
+                    TOTAL_BLOBS_WRITTEN.fetch_add(blob_count, Ordering::SeqCst);
+                    TOTAL_BLOBS_SKIPPED.fetch_add(req_count - blob_count, Ordering::SeqCst);
                     progress.info(format!(
-                        "Wrote {} loose blob objects since program start",
-                        TOTAL_LOOSE_OBJECTS_WRITTEN.load(Ordering::Relaxed)
+                        "Wrote {} changed blobs into packs and skipped {} unchanged ones since program start",
+                        TOTAL_BLOBS_WRITTEN.load(Ordering::Relaxed),
+                        TOTAL_BLOBS_SKIPPED.load(Ordering::Relaxed)
                     ));
 
-                    if !is_bare_repo {
-                        progress.set(2);
-                        progress.blocked("writing new index", None);
-                        repo.set_index(&mut index)?;
-                    }
-                    drop(index);
-
-                    if let Ok(current_tree) = repo
-                        .head()
-                        .and_then(|h| h.resolve())
-                        .and_then(|h| h.peel_to_tree())
-                        .map(|t| t.id())
-                    {
-                        if current_tree == tree_oid {
-                            progress.info("Skipping git commit as there was no change");
-                            return Ok(());
-                        }
+                    if head_tree == Some(tree_id) {
+                        progress.info("Skipping git commit as there was no change");
+                        return Ok(());
                     }
 
-                    {
-                        progress.set(3);
+                    let total_files = files.len();
+                    let commit_id = {
+                        progress.set(2);
                         progress.blocked("writing commit", None);
-                        let current_time =
-                            git2::Time::new(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64, 0);
-                        let signature =
-                            git2::Signature::new("Criner", "https://github.com/the-lean-crate/criner", &current_time)?;
-                        let parent = repo
-                            .head()
-                            .and_then(|h| h.resolve())
-                            .and_then(|h| h.peel_to_commit())
-                            .ok();
-                        let mut parent_store = Vec::with_capacity(1);
-
-                        repo.commit(
-                            Some("HEAD"),
-                            &signature,
-                            &signature,
-                            &format!("update {} reports", req_count),
-                            &repo.find_tree(tree_oid).expect("tree just written to be found"),
-                            match parent.as_ref() {
-                                Some(parent) => {
-                                    parent_store.push(parent);
-                                    &parent_store
-                                }
-                                None => &[],
-                            },
+                        let now = SystemTime::now();
+                        let signature = gix::actor::Signature {
+                            name: options.author_name.as_str().into(),
+                            email: options.author_email.as_str().into(),
+                            time: gix::date::Time::new(now.duration_since(UNIX_EPOCH)?.as_secs() as i64, 0),
+                        };
+                        let message = options.commit_message(blob_count, total_files, now);
+                        let commit_id = pack.add(&gix::objs::Commit {
+                            tree: tree_id,
+                            parents: repo.head_id().ok().map(|id| id.detach()).into_iter().collect(),
+                            author: signature.clone(),
+                            committer: signature,
+                            encoding: None,
+                            message: message.as_str().into(),
+                            extra_headers: Vec::new(),
+                        })?;
+
+                        // Only point to the commit once all objects are stored, to never leave the repository broken
+                        progress.set(3);
+                        progress.blocked("writing pack", None);
+                        pack.flush()?;
+                        if !is_bare_repo {
+                            repo.index_from_tree(&tree_id)?.write(Default::default())?;
+                        }
+                        let branch = repo
+                            .head_name()?
+                            .map(|name| name.as_bstr().to_string())
+                            .unwrap_or_else(|| "HEAD".into());
+                        repo.reference(
+                            branch.as_str(),
+                            commit_id,
+                            PreviousValue::Any,
+                            format!("commit: {}", message),
                         )?;
                         progress.done("Commit created");
-                    }
+                        commit_id
+                    };
+                    drop(pack);
 
+                    if !options.push {
+                        progress.info("Skipping git push as pushing is disabled");
+                        return Ok(());
+                    }
                     progress.set(4);
                     progress.blocked("pushing changes", None);
-                    let remote_name = repo
-                        .branch_upstream_remote(
-                            repo.head()
-                                .and_then(|h| h.resolve())?
-                                .name()
-                                .expect("branch name is valid utf8"),
-                        )
-                        .map(|b| b.as_str().expect("valid utf8").to_string())
-                        .unwrap_or_else(|_| "origin".into());
+                    let remote_name = match options.remote.clone() {
+                        Some(name) => name,
+                        None => repo
+                            .head_name()?
+                            .and_then(|name| {
+                                repo.branch_remote_name(name.shorten(), Direction::Push)
+                                    .map(|remote| remote.as_bstr().to_string())
+                            })
+                            .unwrap_or_else(|| "origin".into()),
+                    };
+                    let url = repo
+                        .find_remote(remote_name.as_str())?
+                        .url(Direction::Push)
+                        .cloned()
+                        .ok_or_else(|| Error::Message(format!("remote '{}' has no url to push to", remote_name)))?;
 
                     futures_lite::future::block_on(enforce_threaded(
                         SystemTime::now() + std::time::Duration::from_secs(60 * 60),
-                        {
-                            let mut progress = progress.add_child("git push");
-                            move || -> crate::Result<_> {
-                                let mut remote = repo.find_remote(&remote_name)?;
-                                let mut callbacks = git2::RemoteCallbacks::new();
-                                let mut subprogress = progress.add_child("git credentials");
-                                let sideband = progress.add_child("git sideband");
-                                let username = env_var("CRINER_REPORT_PUSH_HTTP_USERNAME")?;
-                                let password = env_var("CRINER_REPORT_PUSH_HTTP_PASSWORD")?;
-                                callbacks
-                                        .transfer_progress(|p| {
-                                            progress.set_name(format!(
-                                                "Git pushing changes ({} received)",
-                                                bytesize::ByteSize(p.received_bytes() as u64)
-                                            ));
-                                            progress.init(
-                                                Some(p.total_deltas() + p.total_objects()),
-                                                Some("objects".into()),
-                                            );
-                                            progress
-                                                .set(p.indexed_deltas() + p.received_objects() );
-                                            true
-                                        })
-                                        .sideband_progress(move |line| {
-                                            sideband.set_name(std::str::from_utf8(line).map(|s| s.trim()).unwrap_or(""));
-                                            true
-                                        }).credentials(move |url, username_from_url, allowed_types| {
-                                        subprogress.info(format!("Setting userpass plaintext credentials, allowed are {:?} for {:?} (username = {:?}", allowed_types, url, username_from_url));
-                                        git2::Cred::userpass_plaintext(&username, &password)
-                                    });
-                                remote.push(
-                                    &["+HEAD:refs/heads/main"],
-                                    Some(
-                                        git2::PushOptions::new()
-                                            .packbuilder_parallelism(0)
-                                            .remote_callbacks(callbacks),
-                                    ),
-                                )?;
-                                Ok(())
+                        move || -> crate::Result<_> {
+                            match url.scheme {
+                                gix::url::Scheme::File => push_to_local_repository(
+                                    &repo,
+                                    commit_id,
+                                    &gix::path::from_bstr(url.path.as_bstr()),
+                                    &options.branch,
+                                ),
+                                _ => push_with_git(
+                                    &repo,
+                                    &remote_name,
+                                    &format!("+HEAD:refs/heads/{}", options.branch),
+                                    &options,
+                                ),
                             }
                         },
                     ))??;
@@ -5,10 +5,41 @@ use crate::{
     {Error, Result},
 };
 use futures_util::FutureExt;
-use std::{path::PathBuf, time::SystemTime};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 
 mod git;
+mod site;
+
+pub use git::{PublishOptions, PushAuth};
+
+fn waste_report_dir(assets_dir: &Path) -> PathBuf {
+    use report::generic::Generator;
+    assets_dir
+        .parent()
+        .expect("assets directory to be in criner.db")
+        .join("reports")
+        .join(report::waste::Generator::name())
+}
+
+/// The name of the directory with the incremental cache, next to the reports
+const CACHE_DIR_NAME: &str = "__incremental_cache__";
+
+/// Reports generated with different options must not be merged, so each set of options gets its own cache.
+fn cache_dir(waste_report_dir: &Path, options: &report::waste::Options) -> PathBuf {
+    use report::generic::Generator;
+    let dir = waste_report_dir.join(CACHE_DIR_NAME);
+    match report::waste::Generator::options_fingerprint(options) {
+        Some(fingerprint) => dir.join(fingerprint),
+        None => dir,
+    }
+}
 
+#[allow(clippy::too_many_arguments)]
 pub async fn generate(
     db: persistence::Db,
     mut progress: prodash::tree::Item,
@@ -16,13 +47,11 @@ pub async fn generate(
     glob: Option<String>,
     deadline: Option<SystemTime>,
     cpu_o_bound_processors: u32,
+    options: Arc<report::waste::Options>,
+    publish_options: &PublishOptions,
 ) -> Result<()> {
     use report::generic::Generator;
     let krates = db.open_crates()?;
-    let output_dir = assets_dir
-        .parent()
-        .expect("assets directory to be in criner.db")
-        .join("reports");
     let glob_str = glob.as_deref();
     let num_crates = krates.count_filtered(glob_str) as usize;
     let chunk_size = 500.min(num_crates);
@@ -51,26 +80,42 @@ pub async fn generate(
         (tx_task, rx_result)
     };
 
-    let waste_report_dir = output_dir.join(report::waste::Generator::name());
+    let waste_report_dir = waste_report_dir(&assets_dir);
     blocking::unblock({
         let dir = waste_report_dir.clone();
         move || std::fs::create_dir_all(dir)
     })
     .await?;
     use crate::engine::report::generic::WriteCallback;
+    let mut site_handle = None;
     let (cache_dir, (git_handle, git_state, maybe_join_handle)) = match glob.as_ref() {
         Some(_) => (None, (git::not_available as WriteCallback, None, None)),
         None => {
-            let cd = waste_report_dir.join("__incremental_cache__");
+            let cd = cache_dir(&waste_report_dir, &options);
             blocking::unblock({
                 let cd = cd.clone();
                 move || std::fs::create_dir_all(cd)
             })
             .await?;
-            (
-                Some(cd),
-                git::select_callback(cpu_o_bound_processors, &waste_report_dir, progress.add_child("git")),
-            )
+            let callback = match publish_options.site_dir.as_ref() {
+                Some(site_dir) => {
+                    let (callback, state, handle) = site::select_callback(
+                        cpu_o_bound_processors,
+                        &waste_report_dir,
+                        site_dir,
+                        progress.add_child("site"),
+                    );
+                    site_handle = Some((site_dir.clone(), handle));
+                    (callback, state, None)
+                }
+                None => git::select_callback(
+                    cpu_o_bound_processors,
+                    &waste_report_dir,
+                    publish_options,
+                    progress.add_child("git"),
+                ),
+            };
+            (Some(cd), callback)
         }
     };
     let merge_reports = crate::spawn({
@@ -91,6 +136,7 @@ pub async fn generate(
     let mut fetched_crates = 0;
     let mut chunk = Vec::<(String, Vec<u8>)>::with_capacity(chunk_size as usize);
     let mut cid = 0;
+    let mut reported_crates = BTreeSet::new();
     loop {
         let abort_loop = {
             progress.blocked("fetching chunk of crates to schedule", None);
@@ -109,6 +155,9 @@ pub async fn generate(
                     .filter_map(|r| r.ok()),
             );
             fetched_crates += chunk.len();
+            if site_handle.is_some() {
+                reported_crates.extend(chunk.iter().map(|(name, _)| name.clone()));
+            }
 
             chunk.len() != chunk_size as usize
         };
@@ -124,6 +173,7 @@ pub async fn generate(
                 waste_report_dir.clone(),
                 cache_dir.clone(),
                 chunk,
+                options.clone(),
                 progress.add_child(""),
                 git_handle,
                 git_state.clone(),
@@ -143,9 +193,105 @@ pub async fn generate(
 
     if let Some(handle) = maybe_join_handle {
         progress.blocked("waiting for git to finish", None);
-        if handle.join().is_err() {
+        if blocking::unblock(move || handle.join()).await.is_err() {
             progress.fail("git failed with unknown error");
         }
     };
+    if let Some((site_dir, handle)) = site_handle {
+        progress.blocked("waiting for site to be written", None);
+        let changed = blocking::unblock(move || handle.join())
+            .await
+            .map_err(|_| Error::Message("writing the site failed with unknown error".into()))??;
+        let manifest = blocking::unblock(move || site::finish(&site_dir, changed, Some(&reported_crates))).await?;
+        progress.info(format!(
+            "Published site with {} changed and {} deleted files",
+            manifest.changed.len(),
+            manifest.deleted.len()
+        ));
+    }
     Ok(())
 }
+
+/// Generate the waste reports of the given `version` of `crate_name` or of all of its versions, and write them into
+/// `out_dir` without touching the incremental cache.
+/// If `out_dir` is unset, they are published like the ones of `generate()` instead, and the incremental cache in the
+/// reports directory of the database is kept up to date.
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_crate(
+    db: &persistence::Db,
+    progress: &mut prodash::tree::Item,
+    assets_dir: &Path,
+    out_dir: Option<PathBuf>,
+    crate_name: &str,
+    version: Option<&str>,
+    options: &report::waste::Options,
+    publish_options: &PublishOptions,
+) -> Result<report::generic::CrateReportOutcome> {
+    use report::generic::Generator;
+    let waste_report_dir = waste_report_dir(assets_dir);
+    let cache_dir = cache_dir(&waste_report_dir, options);
+    if let Some(out_dir) = out_dir {
+        return report::waste::Generator::write_crate(
+            db,
+            &out_dir,
+            &cache_dir,
+            false,
+            crate_name,
+            version,
+            options,
+            progress,
+            git::not_available,
+            &None,
+        )
+        .await;
+    }
+
+    blocking::unblock({
+        let cd = cache_dir.clone();
+        move || std::fs::create_dir_all(cd)
+    })
+    .await?;
+    let mut site_handle = None;
+    let (write, write_state, maybe_join_handle) = match publish_options.site_dir.as_ref() {
+        Some(site_dir) => {
+            let (callback, state, handle) =
+                site::select_callback(1, &waste_report_dir, site_dir, progress.add_child("site"));
+            site_handle = Some((site_dir.clone(), handle));
+            (callback, state, None)
+        }
+        None => git::select_callback(1, &waste_report_dir, publish_options, progress.add_child("git")),
+    };
+    let outcome = report::waste::Generator::write_crate(
+        db,
+        &waste_report_dir,
+        &cache_dir,
+        true,
+        crate_name,
+        version,
+        options,
+        progress,
+        write,
+        &write_state,
+    )
+    .await;
+    drop(write_state);
+
+    if let Some(handle) = maybe_join_handle {
+        progress.blocked("waiting for git to finish", None);
+        if blocking::unblock(move || handle.join()).await.is_err() {
+            progress.fail("git failed with unknown error");
+        }
+    };
+    if let Some((site_dir, handle)) = site_handle {
+        progress.blocked("waiting for site to be written", None);
+        let changed = blocking::unblock(move || handle.join())
+            .await
+            .map_err(|_| Error::Message("writing the site failed with unknown error".into()))??;
+        let manifest = blocking::unblock(move || site::finish(&site_dir, changed, None)).await?;
+        progress.info(format!("Published site with {} changed files", manifest.changed.len()));
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod report_test;
@@ -0,0 +1,220 @@
+use crate::{
+    engine::{
+        report::{
+            generic::{Aggregate, CrateReportOutcome, Generator},
+            waste::{self, Report},
+        },
+        stage::report::{generate_crate, PublishOptions},
+    },
+    persistence,
+};
+use std::path::Path;
+
+const CRATE_NAME: &str = "a";
+
+fn add_version(db: &persistence::Db, version: &str, files: &[(&str, u64)]) -> crate::Result<()> {
+    crate::test_utils::add_version(db, CRATE_NAME, version, files, Vec::new())
+}
+
+fn generate(db: &persistence::Db, db_path: &Path, version: Option<&str>) -> crate::Result<CrateReportOutcome> {
+    generate_with(db, db_path, version, None, &Default::default())
+}
+
+fn generate_with(
+    db: &persistence::Db,
+    db_path: &Path,
+    version: Option<&str>,
+    out_dir: Option<&Path>,
+    options: &waste::Options,
+) -> crate::Result<CrateReportOutcome> {
+    let root = prodash::tree::root::Options::default().create();
+    futures_lite::future::block_on(generate_crate(
+        db,
+        &mut root.add_child("report"),
+        &db_path.join("assets"),
+        out_dir.map(ToOwned::to_owned),
+        CRATE_NAME,
+        version,
+        options,
+        &PublishOptions {
+            push: false,
+            ..Default::default()
+        },
+    ))
+}
+
+fn store_empty_top_level_state(cache_dir: &Path) -> crate::Result<()> {
+    std::fs::create_dir_all(cache_dir)?;
+    futures_lite::future::block_on(
+        Report::CrateCollection {
+            total_size_in_bytes: 0,
+            total_files: 0,
+            info_by_crate: Default::default(),
+            wasted_by_extension: Default::default(),
+            history: Default::default(),
+            changes: Default::default(),
+        }
+        .store_current_state(
+            cache_dir,
+            &mut prodash::tree::root::Options::default().create().add_child(""),
+        ),
+    )
+}
+
+fn cached_state(cache_dir: &Path, top_level: bool) -> Option<Report> {
+    let root = prodash::tree::root::Options::default().create();
+    let mut progress = root.add_child("cache");
+    futures_lite::future::block_on(async {
+        if top_level {
+            Report::load_previous_top_level_state(cache_dir, &mut progress).await
+        } else {
+            empty_crate_report().load_previous_state(cache_dir, &mut progress).await
+        }
+    })
+}
+
+fn empty_crate_report() -> Report {
+    Report::Crate {
+        crate_name: CRATE_NAME.into(),
+        total_size_in_bytes: 0,
+        total_files: 0,
+        info_by_version: Default::default(),
+        wasted_by_extension: Default::default(),
+        history: Default::default(),
+        changes: Default::default(),
+    }
+}
+
+fn total_files(report: Option<Report>) -> u64 {
+    match report.expect("cached report") {
+        Report::Version { total_files, .. }
+        | Report::Crate { total_files, .. }
+        | Report::CrateCollection { total_files, .. } => total_files,
+    }
+}
+
+#[test]
+fn new_versions_are_merged_once_and_reported_versions_are_only_rewritten() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = persistence::Db::open(dir.path())?;
+    add_version(&db, "1.0.0", &[("src/lib.rs", 10), ("Cargo.toml", 5)])?;
+    add_version(&db, "1.1.0", &[("src/lib.rs", 10), ("Cargo.toml", 5), ("README.md", 1)])?;
+    let report_dir = dir.path().join("reports").join(waste::Generator::name());
+    let cache_dir = report_dir.join("__incremental_cache__");
+    store_empty_top_level_state(&cache_dir)?;
+
+    let outcome = generate(&db, dir.path(), Some("1.0.0"))?;
+    assert_eq!(outcome.new_versions, vec!["1.0.0"]);
+    assert!(report_dir.join(CRATE_NAME).join("1.0.0.html").is_file());
+    assert!(!report_dir.join(CRATE_NAME).join("1.1.0.html").exists());
+    assert_eq!(total_files(cached_state(&cache_dir, false)), 2);
+    assert_eq!(total_files(cached_state(&cache_dir, true)), 2);
+
+    let outcome = generate(&db, dir.path(), None)?;
+    assert_eq!(outcome.new_versions, vec!["1.1.0"]);
+    assert_eq!(outcome.regenerated_versions, vec!["1.0.0"]);
+    assert_eq!(total_files(cached_state(&cache_dir, false)), 5);
+    assert_eq!(total_files(cached_state(&cache_dir, true)), 5);
+
+    std::fs::remove_file(report_dir.join(CRATE_NAME).join("index.html"))?;
+    let outcome = generate(&db, dir.path(), None)?;
+    assert!(outcome.new_versions.is_empty());
+    assert_eq!(outcome.regenerated_versions, vec!["1.0.0", "1.1.0"]);
+    assert!(
+        report_dir.join(CRATE_NAME).join("index.html").is_file(),
+        "the crate page is written from its cached state"
+    );
+    assert_eq!(total_files(cached_state(&cache_dir, false)), 5);
+    assert_eq!(total_files(cached_state(&cache_dir, true)), 5);
+
+    assert!(
+        generate(&db, dir.path(), Some("2.0.0")).is_err(),
+        "unknown versions are an error"
+    );
+    Ok(())
+}
+
+#[test]
+fn changed_rules_report_all_versions_anew_with_a_cache_of_their_own() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = persistence::Db::open(dir.path())?;
+    add_version(&db, "1.0.0", &[("src/lib.rs", 10), ("Cargo.toml", 5)])?;
+    let cache_dir = dir
+        .path()
+        .join("reports")
+        .join(waste::Generator::name())
+        .join("__incremental_cache__");
+    store_empty_top_level_state(&cache_dir)?;
+
+    let outcome = generate(&db, dir.path(), None)?;
+    assert_eq!(outcome.new_versions, vec!["1.0.0"]);
+
+    let options = waste::Options {
+        rules: waste::RuleBook::from_toml("[sets.mine]\nremove_exclude = []\n[crates.a]\nset = \"mine\"")
+            .expect("valid rules"),
+        ..Default::default()
+    };
+    let fingerprint = waste::Generator::options_fingerprint(&options).expect("rules differ from the builtin ones");
+    store_empty_top_level_state(&cache_dir.join(&fingerprint))?;
+    let outcome = generate_with(&db, dir.path(), None, None, &options)?;
+    assert_eq!(
+        outcome.new_versions,
+        vec!["1.0.0"],
+        "reports of other rules don't count"
+    );
+    assert_eq!(total_files(cached_state(&cache_dir, false)), 2);
+    assert_eq!(total_files(cached_state(&cache_dir.join(&fingerprint), false)), 2);
+
+    let outcome = generate_with(&db, dir.path(), None, None, &options)?;
+    assert_eq!(outcome.regenerated_versions, vec!["1.0.0"]);
+    let outcome = generate(&db, dir.path(), None)?;
+    assert_eq!(outcome.regenerated_versions, vec!["1.0.0"]);
+    Ok(())
+}
+
+#[test]
+fn new_versions_stay_unreported_until_there_is_a_top_level_report() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = persistence::Db::open(dir.path())?;
+    add_version(&db, "1.0.0", &[("src/lib.rs", 10), ("Cargo.toml", 5)])?;
+    let report_dir = dir.path().join("reports").join(waste::Generator::name());
+    let cache_dir = report_dir.join("__incremental_cache__");
+
+    let outcome = generate(&db, dir.path(), None)?;
+    assert_eq!(outcome.new_versions, vec!["1.0.0"]);
+    assert!(report_dir.join(CRATE_NAME).join("index.html").is_file());
+    assert!(
+        cached_state(&cache_dir, false).is_none(),
+        "the crate report is left to the next run of all crates"
+    );
+
+    store_empty_top_level_state(&cache_dir)?;
+    let outcome = generate(&db, dir.path(), None)?;
+    assert_eq!(outcome.new_versions, vec!["1.0.0"], "the version wasn't counted before");
+    assert_eq!(total_files(cached_state(&cache_dir, false)), 2);
+    assert_eq!(total_files(cached_state(&cache_dir, true)), 2);
+    Ok(())
+}
+
+#[test]
+fn reports_written_elsewhere_leave_the_cache_untouched() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db = persistence::Db::open(dir.path())?;
+    add_version(&db, "1.0.0", &[("src/lib.rs", 10), ("Cargo.toml", 5)])?;
+    let report_dir = dir.path().join("reports").join(waste::Generator::name());
+    let cache_dir = report_dir.join("__incremental_cache__");
+    store_empty_top_level_state(&cache_dir)?;
+    let out_dir = dir.path().join("out");
+
+    let outcome = generate_with(&db, dir.path(), None, Some(&out_dir), &Default::default())?;
+    assert_eq!(outcome.new_versions, vec!["1.0.0"]);
+    assert!(out_dir.join(CRATE_NAME).join("1.0.0.html").is_file());
+    assert!(out_dir.join(CRATE_NAME).join("index.html").is_file());
+    assert!(!report_dir.join(CRATE_NAME).exists());
+    assert!(cached_state(&cache_dir, false).is_none());
+    assert_eq!(total_files(cached_state(&cache_dir, true)), 0);
+
+    let outcome = generate(&db, dir.path(), None)?;
+    assert_eq!(outcome.new_versions, vec!["1.0.0"], "the version wasn't marked done");
+    Ok(())
+}
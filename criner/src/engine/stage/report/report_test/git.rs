@@ -0,0 +1,255 @@
+use crate::engine::{
+    report::generic::WriteRequest,
+    stage::report::{git::select_callback, PublishOptions},
+};
+use std::path::Path;
+
+/// Create a repository to write reports into, with a bare repository as its 'origin' remote.
+fn repositories(bare: bool) -> crate::Result<(tempfile::TempDir, gix::Repository, gix::Repository)> {
+    let dir = tempfile::tempdir()?;
+    let remote = gix::init_bare(dir.path().join("remote.git")).expect("remote to be created");
+    let reports = dir.path().join("reports");
+    let reports = if bare {
+        gix::init_bare(reports)
+    } else {
+        gix::init(reports)
+    }
+    .expect("repository to be created");
+    let mut config = std::fs::OpenOptions::new()
+        .append(true)
+        .open(reports.git_dir().join("config"))?;
+    std::io::Write::write_all(
+        &mut config,
+        format!(
+            "[remote \"origin\"]\n\turl = {}\n\tfetch = +refs/heads/*:refs/remotes/origin/*\n",
+            dir.path().join("remote.git").display()
+        )
+        .as_bytes(),
+    )?;
+    let reports = gix::open(reports.git_dir())?;
+    Ok((dir, reports, remote))
+}
+
+fn report_dir(repo: &gix::Repository) -> &Path {
+    repo.work_dir().unwrap_or_else(|| repo.git_dir())
+}
+
+fn publish(report_dir: &Path, options: &PublishOptions, files: &[(&str, &str)]) -> crate::Result<()> {
+    let root = prodash::tree::root::Options::default().create();
+    let (write, state, handle) = select_callback(1, report_dir, options, root.add_child("git"));
+    for (path, content) in files {
+        futures_lite::future::block_on(write(
+            WriteRequest {
+                path: report_dir.join(path),
+                content: content.as_bytes().to_vec(),
+            },
+            &state,
+        ))?;
+    }
+    drop(state);
+    handle.expect("a git repository").join().expect("no panic")
+}
+
+fn commit_at<'repo>(repo: &'repo gix::Repository, reference: &str) -> crate::Result<gix::Commit<'repo>> {
+    Ok(repo
+        .find_reference(reference)?
+        .peel_to_id_in_place()
+        .expect("reference to point to an object")
+        .object()?
+        .into_commit())
+}
+
+fn id_at(commit: &gix::Commit<'_>, path: &str) -> crate::Result<gix::ObjectId> {
+    Ok(commit
+        .tree()
+        .expect("commit to have a tree")
+        .lookup_entry_by_path(path, &mut Vec::new())?
+        .expect("file to be in tree")
+        .oid()
+        .to_owned())
+}
+
+fn content_at(repo: &gix::Repository, commit: &gix::Commit<'_>, path: &str) -> crate::Result<Vec<u8>> {
+    Ok(repo.find_object(id_at(commit, path)?)?.detach().data)
+}
+
+#[test]
+fn reports_are_committed_and_pushed_to_the_configured_branch() -> crate::Result<()> {
+    let (_dir, reports, remote) = repositories(false)?;
+    let options = PublishOptions {
+        branch: "reports".into(),
+        author_name: "Reporter".into(),
+        author_email: "reporter@example.com".into(),
+        message: "{files} of {total_files} files".into(),
+        ..Default::default()
+    };
+    publish(
+        report_dir(&reports),
+        &options,
+        &[("index.html", "a"), ("a/index.html", "b")],
+    )?;
+
+    let commit = commit_at(&remote, "refs/heads/reports")?;
+    assert_eq!(commit.message_raw_sloppy(), "2 of 2 files");
+    assert_eq!(commit.author()?.name, "Reporter");
+    assert_eq!(commit.committer()?.email, "reporter@example.com");
+    assert_eq!(content_at(&remote, &commit, "a/index.html")?, b"b");
+    assert!(
+        remote.find_reference("refs/heads/main").is_err(),
+        "only the configured branch is pushed"
+    );
+    Ok(())
+}
+
+#[test]
+fn reports_are_only_committed_if_pushing_is_disabled() -> crate::Result<()> {
+    let (_dir, reports, remote) = repositories(false)?;
+    let options = PublishOptions {
+        push: false,
+        ..Default::default()
+    };
+    publish(report_dir(&reports), &options, &[("index.html", "a")])?;
+
+    let commit = commit_at(&reports, "HEAD")?;
+    assert_eq!(commit.message_raw_sloppy(), "update 1 reports");
+    assert!(remote.find_reference("refs/heads/main").is_err(), "nothing was pushed");
+    Ok(())
+}
+
+#[test]
+fn reports_are_committed_into_bare_repositories_and_pushed_incrementally() -> crate::Result<()> {
+    let (_dir, reports, remote) = repositories(true)?;
+    let options = PublishOptions::default();
+    publish(
+        report_dir(&reports),
+        &options,
+        &[("index.html", "a"), ("a/index.html", "b")],
+    )?;
+    publish(report_dir(&reports), &options, &[("a/b/index.html", "c")])?;
+
+    let commit = commit_at(&remote, "refs/heads/main")?;
+    assert_eq!(commit.id, commit_at(&reports, "HEAD")?.id);
+    assert_eq!(
+        content_at(&remote, &commit, "a/index.html")?,
+        b"b",
+        "files of the previous commit are kept"
+    );
+    assert_eq!(content_at(&remote, &commit, "a/b/index.html")?, b"c");
+    let parent = commit.parent_ids().next().expect("a parent").detach();
+    assert_eq!(
+        content_at(&remote, &remote.find_object(parent)?.into_commit(), "index.html")?,
+        b"a"
+    );
+    assert!(
+        !report_dir(&reports).join("index.html").exists(),
+        "no files are written"
+    );
+    Ok(())
+}
+
+fn loose_objects(git_dir: &Path) -> crate::Result<usize> {
+    let mut count = 0;
+    for entry in std::fs::read_dir(git_dir.join("objects"))? {
+        let entry = entry?;
+        if entry.file_name().len() == 2 {
+            count += std::fs::read_dir(entry.path())?.count();
+        }
+    }
+    Ok(count)
+}
+
+#[test]
+fn reports_are_written_into_packs_and_unchanged_reports_are_skipped() -> crate::Result<()> {
+    let (_dir, reports, remote) = repositories(false)?;
+    let options = PublishOptions {
+        message: "{files} files".into(),
+        ..Default::default()
+    };
+    publish(
+        report_dir(&reports),
+        &options,
+        &[("index.html", "a"), ("a/index.html", "b")],
+    )?;
+    assert_eq!(loose_objects(reports.git_dir())?, 0, "all objects are packed");
+    let first = commit_at(&reports, "HEAD")?.id;
+
+    publish(
+        report_dir(&reports),
+        &options,
+        &[("index.html", "a"), ("a/index.html", "b")],
+    )?;
+    assert_eq!(
+        commit_at(&reports, "HEAD")?.id,
+        first,
+        "nothing changed, so there is no new commit"
+    );
+
+    publish(
+        report_dir(&reports),
+        &options,
+        &[("index.html", "a"), ("a/index.html", "c")],
+    )?;
+    let commit = commit_at(&reports, "HEAD")?;
+    assert_eq!(commit.parent_ids().next().map(|id| id.detach()), Some(first));
+    assert_eq!(commit.message_raw_sloppy(), "1 files", "only changed files are counted");
+    assert_eq!(content_at(&reports, &commit, "a/index.html")?, b"c");
+    assert_eq!(loose_objects(reports.git_dir())?, 0);
+    assert_eq!(loose_objects(remote.git_dir())?, 0, "pushed objects are packed");
+
+    assert_eq!(
+        reports.open_index()?.entry_by_path("a/index.html".into()).map(|e| e.id),
+        Some(id_at(&commit, "a/index.html")?),
+        "the index of the working tree matches the commit"
+    );
+    Ok(())
+}
+
+fn packs(git_dir: &Path) -> crate::Result<usize> {
+    Ok(std::fs::read_dir(git_dir.join("objects").join("pack"))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "pack"))
+        .count())
+}
+
+#[test]
+fn packs_are_consolidated_instead_of_piling_up() -> crate::Result<()> {
+    let (_dir, reports, remote) = repositories(false)?;
+    let options = PublishOptions::default();
+    let runs = 40;
+    for run in 0..runs {
+        publish(
+            report_dir(&reports),
+            &options,
+            &[("index.html", "a"), ("a/index.html", &run.to_string())],
+        )?;
+    }
+    for repo in &[&reports, &remote] {
+        let num_packs = packs(repo.git_dir())?;
+        assert!(
+            num_packs < runs / 2,
+            "{} packs after {} runs, which add one each",
+            num_packs,
+            runs
+        );
+        let repo = gix::open(repo.git_dir())?;
+        let mut commit = commit_at(&repo, "refs/heads/main")?;
+        for run in (0..runs).rev() {
+            assert_eq!(content_at(&repo, &commit, "a/index.html")?, run.to_string().as_bytes());
+            let parent = commit.parent_ids().next().map(|id| id.detach());
+            match parent {
+                Some(parent) => commit = repo.find_object(parent)?.into_commit(),
+                None => assert_eq!(run, 0, "all commits are reachable"),
+            }
+        }
+    }
+    assert!(
+        std::fs::read_dir(reports.git_dir().join("objects").join("pack"))?
+            .filter_map(|entry| entry.ok())
+            .all(|entry| entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext == "pack" || ext == "idx")),
+        "no keep files or temporary packs are left behind"
+    );
+    Ok(())
+}
@@ -0,0 +1,3 @@
+mod generate_crate;
+mod git;
+mod site;
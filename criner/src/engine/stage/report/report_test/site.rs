@@ -0,0 +1,103 @@
+use crate::engine::{
+    report::generic::WriteRequest,
+    stage::report::site::{finish, select_callback, Manifest, MANIFEST_FILE_NAME},
+};
+use std::{collections::BTreeSet, path::Path};
+
+fn publish(report_dir: &Path, site_dir: &Path, files: &[(&str, &str)]) -> crate::Result<Manifest> {
+    let root = prodash::tree::root::Options::default().create();
+    let (write, state, handle) = select_callback(1, report_dir, site_dir, root.add_child("site"));
+    for (path, content) in files {
+        futures_lite::future::block_on(write(
+            WriteRequest {
+                path: report_dir.join(path),
+                content: content.as_bytes().to_vec(),
+            },
+            &state,
+        ))?;
+    }
+    drop(state);
+    let changed = handle.join().expect("no panic")?;
+    let reported_crates: BTreeSet<_> = files
+        .iter()
+        .filter_map(|(path, _)| path.split_once('/').map(|(name, _)| name.to_owned()))
+        .collect();
+    finish(site_dir, changed, Some(&reported_crates))
+}
+
+#[test]
+fn only_changed_files_are_written_and_unreported_crates_are_deleted() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let (report_dir, site_dir) = (dir.path().join("reports"), dir.path().join("site"));
+
+    let manifest = publish(
+        &report_dir,
+        &site_dir,
+        &[("index.html", "a"), ("a/index.html", "b"), ("b/index.html", "c")],
+    )?;
+    assert_eq!(
+        manifest,
+        Manifest {
+            changed: vec!["a/index.html".into(), "b/index.html".into(), "index.html".into()],
+            deleted: vec![],
+        }
+    );
+    assert_eq!(std::fs::read_to_string(site_dir.join("a/index.html"))?, "b");
+    assert!(!report_dir.exists(), "nothing is written into the report directory");
+
+    let manifest = publish(
+        &report_dir,
+        &site_dir,
+        &[("index.html", "a"), ("a/index.html", "changed")],
+    )?;
+    assert_eq!(
+        manifest,
+        Manifest {
+            changed: vec!["a/index.html".into()],
+            deleted: vec!["b/index.html".into()],
+        }
+    );
+    assert_eq!(std::fs::read_to_string(site_dir.join("a/index.html"))?, "changed");
+    assert!(!site_dir.join("b").exists());
+
+    let written: Manifest =
+        serde_json::from_slice(&std::fs::read(site_dir.join(MANIFEST_FILE_NAME))?).expect("valid manifest");
+    assert_eq!(written, manifest);
+    Ok(())
+}
+
+#[test]
+fn new_sites_start_out_with_all_reports_published_before() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let (report_dir, site_dir) = (dir.path().join("reports"), dir.path().join("site"));
+    for (path, content) in &[
+        ("index.html", "a"),
+        ("a/index.html", "b"),
+        ("b/1.0.0.html", "c"),
+        ("__incremental_cache__/a.json", "{}"),
+        (".git/HEAD", ""),
+    ] {
+        let path = report_dir.join(path);
+        std::fs::create_dir_all(path.parent().expect("parent directory"))?;
+        std::fs::write(path, content)?;
+    }
+
+    let manifest = publish(
+        &report_dir,
+        &site_dir,
+        &[("a/index.html", "changed"), ("b/1.0.0.html", "c")],
+    )?;
+    assert_eq!(
+        manifest.changed,
+        vec!["a/index.html", "b/1.0.0.html", "index.html"],
+        "the cache and hidden files aren't published"
+    );
+    assert_eq!(std::fs::read_to_string(site_dir.join("index.html"))?, "a");
+    assert_eq!(std::fs::read_to_string(site_dir.join("a/index.html"))?, "changed");
+
+    std::fs::write(report_dir.join("c.html"), "d")?;
+    let manifest = publish(&report_dir, &site_dir, &[("a/index.html", "changed")])?;
+    assert!(manifest.changed.is_empty(), "existing sites are only updated");
+    assert!(!site_dir.join("c.html").exists());
+    Ok(())
+}
@@ -0,0 +1,213 @@
+//! Publishing of reports into a directory to be served as static site, writing only files whose content changed.
+use crate::{
+    engine::report::generic::{WriteCallback, WriteCallbackState, WriteInstruction, WriteRequest},
+    {Error, Result},
+};
+use futures_util::{future::BoxFuture, FutureExt};
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+/// The name of the manifest written into the site directory after each reporting run
+pub const MANIFEST_FILE_NAME: &str = ".criner-manifest.json";
+
+/// The files affected by a reporting run, for use by scripts deploying the site.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Manifest {
+    /// The paths of all files which were created or changed, relative to the site directory
+    pub changed: Vec<String>,
+    /// The paths of all files of crates which aren't reported anymore and were deleted, relative to the site directory
+    pub deleted: Vec<String>,
+}
+
+/// Write `content` into a temporary file next to `path` and move it into place, so that readers never see partial
+/// files.
+fn write_atomically(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let file_name = path.file_name().expect("a file path").to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name));
+    std::fs::write(&tmp_path, content)?;
+    std::fs::rename(tmp_path, path)
+}
+
+fn is_unchanged(path: &Path, content: &[u8]) -> bool {
+    let has_same_len = path.metadata().is_ok_and(|m| m.len() == content.len() as u64);
+    has_same_len && std::fs::read(path).is_ok_and(|existing| existing == content)
+}
+
+fn files_in(directory: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files_in(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn relative_path(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .expect("path to be in base directory")
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Write the blobs of `tree` and all of its subtrees into `site_dir`, and add their paths prefixed with `prefix` to
+/// `seeded`.
+fn seed_from_tree(
+    repo: &gix::Repository,
+    tree: gix::ObjectId,
+    prefix: &str,
+    site_dir: &Path,
+    seeded: &mut Vec<String>,
+) -> Result<()> {
+    let tree = repo.find_object(tree)?.detach();
+    for entry in gix::objs::TreeRef::from_bytes(&tree.data)?.entries {
+        let path = format!("{}{}", prefix, entry.filename);
+        if entry.mode.is_tree() {
+            seed_from_tree(repo, entry.oid.to_owned(), &format!("{}/", path), site_dir, seeded)?;
+        } else if entry.mode.is_blob() {
+            let blob = repo.find_object(entry.oid)?;
+            let file_path = site_dir.join(&path);
+            std::fs::create_dir_all(file_path.parent().expect("file path with parent directory"))?;
+            write_atomically(&file_path, &blob.data)?;
+            seeded.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Copy all reports published before into `site_dir` and return their paths relative to it. These are the files in
+/// `report_dir`, or the ones of the latest commit if it is a bare git repository.
+///
+/// Only crates with new versions are reported in each run, so without it a new site would lack all other crates.
+fn seed(report_dir: &Path, site_dir: &Path) -> Result<Vec<String>> {
+    let mut seeded = Vec::new();
+    if !report_dir.is_dir() {
+        return Ok(seeded);
+    }
+    match gix::open(report_dir) {
+        Ok(repo) if repo.is_bare() => {
+            if let Ok(tree) = repo.head_tree_id() {
+                seed_from_tree(&repo, tree.detach(), "", site_dir, &mut seeded)?;
+            }
+        }
+        _ => {
+            let mut files = Vec::new();
+            for entry in std::fs::read_dir(report_dir)? {
+                let path = entry?.path();
+                let name = path.file_name().expect("entries to have a name").to_string_lossy();
+                if name.starts_with('.') || name == super::CACHE_DIR_NAME {
+                    continue;
+                }
+                if path.is_dir() {
+                    files_in(&path, &mut files)?;
+                } else {
+                    files.push(path);
+                }
+            }
+            for path in files {
+                let relative_path = relative_path(&path, report_dir);
+                let file_path = site_dir.join(&relative_path);
+                std::fs::create_dir_all(file_path.parent().expect("file path with parent directory"))?;
+                write_atomically(&file_path, &std::fs::read(&path)?)?;
+                seeded.push(relative_path);
+            }
+        }
+    }
+    Ok(seeded)
+}
+
+/// Return a callback to publish all reports written into `report_dir` to `site_dir` instead, along with the handle
+/// of the thread writing them, which returns the paths of all changed files relative to `site_dir`.
+/// If the site wasn't published before, it starts out with all reports in `report_dir`.
+pub fn select_callback(
+    processors: u32,
+    report_dir: &Path,
+    site_dir: &Path,
+    mut progress: prodash::tree::Item,
+) -> (
+    WriteCallback,
+    WriteCallbackState,
+    std::thread::JoinHandle<Result<Vec<String>>>,
+) {
+    let (tx, rx) = async_channel::bounded(processors as usize);
+    let report_dir = report_dir.to_owned();
+    let site_dir = site_dir.to_owned();
+    let handle = std::thread::spawn(move || -> Result<Vec<String>> {
+        let mut changed = Vec::new();
+        if !site_dir.join(MANIFEST_FILE_NAME).is_file() {
+            progress.blocked("copying previously published reports", None);
+            changed = seed(&report_dir, &site_dir)?;
+            progress.info(format!("Copied {} previously published reports", changed.len()));
+        }
+        progress.init(None, Some("files changed".into()));
+        let mut unchanged = 0;
+        while let Ok(WriteRequest { path, content }) = futures_lite::future::block_on(rx.recv()) {
+            let relative_path = relative_path(&path, &report_dir);
+            let path = site_dir.join(&relative_path);
+            if is_unchanged(&path, &content) {
+                unchanged += 1;
+                continue;
+            }
+            std::fs::create_dir_all(path.parent().expect("file path with parent directory"))?;
+            write_atomically(&path, &content)?;
+            changed.push(relative_path);
+            progress.set(changed.len());
+        }
+        progress.done(format!(
+            "Wrote {} changed files and skipped {} unchanged ones",
+            changed.len(),
+            unchanged
+        ));
+        Ok(changed)
+    });
+    (publish, Some(tx), handle)
+}
+
+/// Delete the pages of all crates in `site_dir` which are not in `reported_crates`, if set, and write the manifest
+/// of all `changed` and deleted files.
+pub fn finish(
+    site_dir: &Path,
+    mut changed: Vec<String>,
+    reported_crates: Option<&BTreeSet<String>>,
+) -> Result<Manifest> {
+    std::fs::create_dir_all(site_dir)?;
+    let mut deleted = Vec::new();
+    if let Some(reported_crates) = reported_crates {
+        for entry in std::fs::read_dir(site_dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type()?.is_dir() && !name.starts_with('.') && !reported_crates.contains(&name) {
+                let mut files = Vec::new();
+                files_in(&entry.path(), &mut files)?;
+                deleted.extend(files.iter().map(|path| relative_path(path, site_dir)));
+                std::fs::remove_dir_all(entry.path())?;
+            }
+        }
+    }
+    changed.sort();
+    changed.dedup();
+    deleted.sort();
+    let manifest = Manifest { changed, deleted };
+    write_atomically(
+        &site_dir.join(MANIFEST_FILE_NAME),
+        &serde_json::to_vec_pretty(&manifest).map_err(std::io::Error::from)?,
+    )?;
+    Ok(manifest)
+}
+
+pub fn publish(req: WriteRequest, send: &WriteCallbackState) -> BoxFuture<'_, Result<WriteInstruction>> {
+    async move {
+        send.as_ref()
+            .expect("send to be available if a site is published")
+            .send(req)
+            .await
+            .map_err(Error::send_msg("Site Write Request"))?;
+        Ok(WriteInstruction::Skip)
+    }
+    .boxed()
+}
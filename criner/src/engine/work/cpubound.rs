@@ -1,8 +1,8 @@
-use crate::engine::report::waste::{tar_path_to_utf8_str, CargoConfig};
+use crate::engine::report::waste::{rust_source_outline, tar_path_to_utf8_str, CargoConfig};
 use crate::{error::Result, model, persistence, Error};
 use async_trait::async_trait;
 use std::io::Seek;
-use std::{fs::File, io::BufReader, io::Read, path::PathBuf, time::SystemTime};
+use std::{fs::File, io::BufReader, io::Read, io::Write, path::Path, path::PathBuf, time::SystemTime};
 
 struct ProcessingState {
     downloaded_crate: PathBuf,
@@ -22,9 +22,7 @@ impl Agent {
             asset_dir,
             results,
             state: None,
-            standard_bin_path: globset::Glob::new("src/bin/*.rs")
-                .expect("valid statically known glob")
-                .compile_matcher(),
+            standard_bin_path: standard_bin_path(),
         })
     }
 }
@@ -90,7 +88,7 @@ pub struct ExtractRequest {
 
 pub fn default_persisted_extraction_task() -> model::Task {
     const TASK_NAME: &str = "extract_crate";
-    const TASK_VERSION: &str = "1.0.0";
+    const TASK_VERSION: &str = "1.3.0";
     model::Task {
         stored_at: SystemTime::now(),
         process: TASK_NAME.into(),
@@ -99,33 +97,149 @@ pub fn default_persisted_extraction_task() -> model::Task {
     }
 }
 
-fn extract_crate(
-    results: &persistence::TaskResultTable,
-    key: &str,
-    progress: &mut prodash::tree::Item,
-    downloaded_crate: PathBuf,
-    standard_bin_path: &globset::GlobMatcher,
-) -> Result<()> {
-    use persistence::TableAccess;
+/// A sink counting the bytes written to it, to learn the size of compressed data without keeping it.
+#[derive(Default)]
+struct ByteCounter(u64);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Return a matcher for the binary sources cargo discovers automatically, which are needed to compute waste reports.
+pub(crate) fn standard_bin_path() -> globset::GlobMatcher {
+    globset::Glob::new("src/bin/*.rs")
+        .expect("valid statically known glob")
+        .compile_matcher()
+}
+
+/// The meta-data of all files in a crate, along with the files needed to compute its waste report
+/// in full or in outline.
+pub(crate) struct ExplodedCrate<'a> {
+    interesting_paths: Vec<String>,
+    standard_bin_path: &'a globset::GlobMatcher,
+    buf: Vec<u8>,
+    pub entries_meta_data: Vec<model::TarHeader>,
+    pub selected_entries: Vec<(model::TarHeader, Vec<u8>)>,
+}
+
+impl<'a> ExplodedCrate<'a> {
+    /// Prepare collecting the files of a crate with the given normalized `manifest`, which is the `Cargo.toml` file.
+    pub(crate) fn new(manifest: &[u8], standard_bin_path: &'a globset::GlobMatcher) -> Self {
+        let mut interesting_paths = vec!["Cargo.toml".to_string(), "Cargo.toml.orig".into(), "Cargo.lock".into()];
+        let config = std::str::from_utf8(manifest).map(CargoConfig::from).unwrap_or_default();
+        interesting_paths.push(config.actual_or_expected_build_script_path().to_owned());
+        interesting_paths.push(config.lib_path().to_owned());
+        interesting_paths.extend(config.bin_paths());
+        ExplodedCrate {
+            interesting_paths,
+            standard_bin_path,
+            buf: vec![0; 64 * 1024],
+            entries_meta_data: Vec::new(),
+            selected_entries: Vec::new(),
+        }
+    }
+
+    /// Record the entry at `path` as seen in the crate archive, including the directory of the crate itself,
+    /// and keep its `content` if it is needed to compute the waste report.
+    /// The content is streamed, and only what's kept is held in memory.
+    pub(crate) fn add(
+        &mut self,
+        path: &[u8],
+        size: u64,
+        entry_type: u8,
+        mut content: impl Read,
+    ) -> std::io::Result<()> {
+        const MAX_STORAGE_SIZE: usize = 128 * 1024;
+        let relative_path = tar_path_to_utf8_str(path);
+        let max_kept_bytes =
+            if relative_path == "Cargo.toml" || relative_path == "Cargo.toml.orig" || relative_path == "Cargo.lock" {
+                Some(usize::MAX)
+            } else if self.interesting_paths.iter().any(|p| p == relative_path)
+                || self.standard_bin_path.is_match(relative_path)
+            {
+                Some(MAX_STORAGE_SIZE)
+            } else {
+                None
+            };
+        // Other sources are only needed to find all compiled modules and the files they include
+        let is_other_source = max_kept_bytes.is_none() && relative_path.ends_with(".rs");
+
+        let mut kept = Vec::new();
+        let mut incomplete_lines = Vec::new();
+        let mut encoder = libflate::deflate::Encoder::new(ByteCounter::default());
+        let mut num_bytes = 0;
+        loop {
+            let num_read = match content.read(&mut self.buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            let chunk = &self.buf[..num_read];
+            num_bytes += num_read;
+            encoder.write_all(chunk)?;
+            if let Some(max_kept_bytes) = max_kept_bytes {
+                kept.extend_from_slice(&chunk[..num_read.min(max_kept_bytes - kept.len())]);
+            } else if is_other_source {
+                incomplete_lines.extend_from_slice(chunk);
+                if let Some(end_of_last_line) = incomplete_lines.iter().rposition(|b| *b == b'\n') {
+                    kept.extend(rust_source_outline(&incomplete_lines[..=end_of_last_line]));
+                    incomplete_lines.drain(..=end_of_last_line);
+                }
+            }
+        }
+        if is_other_source {
+            kept.extend(rust_source_outline(&incomplete_lines));
+        }
+        // The compressed size approximates how much the file adds to the size of the compressed crate
+        let compressed_size = if num_bytes == 0 {
+            0
+        } else {
+            encoder.finish().into_result()?.0
+        };
+
+        let header = model::TarHeader {
+            path: path.to_vec(),
+            size,
+            entry_type,
+            compressed_size: Some(compressed_size),
+        };
+        if max_kept_bytes.is_some() || !kept.is_empty() {
+            self.selected_entries.push((header.clone(), kept));
+        }
+        self.entries_meta_data.push(header);
+        Ok(())
+    }
+}
+
+/// Read all entries of the gzipped tar archive at `downloaded_crate`, calling `progress` with the amount of entries
+/// seen so far, and return them as exploded crate.
+pub(crate) fn explode_crate_archive<'a>(
+    downloaded_crate: &Path,
+    standard_bin_path: &'a globset::GlobMatcher,
+    mut progress: impl FnMut(usize),
+) -> Result<ExplodedCrate<'a>> {
     let mut archive = tar::Archive::new(libflate::gzip::Decoder::new(BufReader::new(File::open(
         downloaded_crate,
     )?))?);
 
     let mut buf = Vec::new();
-    let mut interesting_paths = vec!["Cargo.toml".to_string(), "Cargo.lock".into()];
-    let mut files = Vec::new();
-    for e in archive.entries()? {
-        progress.inc();
+    for (count, e) in archive.entries()?.enumerate() {
+        progress(count + 1);
         let mut e: tar::Entry<_> = e?;
         if tar_path_to_utf8_str(e.path_bytes().as_ref()) == "Cargo.toml" {
             e.read_to_end(&mut buf)?;
-            let config = std::str::from_utf8(&buf).map(CargoConfig::from).unwrap_or_default();
-            interesting_paths.push(config.actual_or_expected_build_script_path().to_owned());
-            interesting_paths.push(config.lib_path().to_owned());
-            interesting_paths.extend(config.bin_paths().into_iter().map(|s| s.to_owned()));
             break;
         }
     }
+    let mut exploded = ExplodedCrate::new(&buf, standard_bin_path);
 
     let mut archive = tar::Archive::new(libflate::gzip::Decoder::new(BufReader::new({
         let mut file = archive.into_inner().into_inner();
@@ -133,51 +247,38 @@ fn extract_crate(
         file
     }))?);
 
-    let mut meta_data = Vec::new();
-    let mut meta_count = 0;
-    let mut file_count = 0;
-    let mut max_storage_size = [0; 128 * 1024];
-    for e in archive.entries()? {
-        meta_count += 1;
-        progress.set(meta_count);
-        let mut e: tar::Entry<_> = e?;
-        meta_data.push(model::TarHeader {
-            path: e.path_bytes().to_vec(),
-            size: e.header().size()?,
-            entry_type: e.header().entry_type().as_byte(),
-        });
-
-        if interesting_paths
-            .iter()
-            .any(|p| p == tar_path_to_utf8_str(e.path_bytes().as_ref()))
-            || standard_bin_path.is_match(tar_path_to_utf8_str(e.path_bytes().as_ref()))
-        {
-            file_count += 1;
-
-            let slice = if tar_path_to_utf8_str(e.path_bytes().as_ref()) == "Cargo.toml"
-                || tar_path_to_utf8_str(e.path_bytes().as_ref()) == "Cargo.lock"
-            {
-                buf.clear();
-                e.read_to_end(&mut buf)?;
-                &buf
-            } else {
-                let bytes_read = e.read(&mut max_storage_size[..])?;
-                &max_storage_size[..bytes_read]
-            };
-            files.push((
-                meta_data.last().expect("to have pushed one just now").to_owned(),
-                slice.to_owned(),
-            ));
-        }
+    for (count, e) in archive.entries()?.enumerate() {
+        progress(count + 1);
+        let e: tar::Entry<_> = e?;
+        let path = e.path_bytes().into_owned();
+        let (size, entry_type) = (e.header().size()?, e.header().entry_type().as_byte());
+        exploded.add(&path, size, entry_type, e)?;
     }
+    Ok(exploded)
+}
+
+fn extract_crate(
+    results: &persistence::TaskResultTable,
+    key: &str,
+    progress: &mut prodash::tree::Item,
+    downloaded_crate: PathBuf,
+    standard_bin_path: &globset::GlobMatcher,
+) -> Result<()> {
+    use persistence::TableAccess;
+    let ExplodedCrate {
+        entries_meta_data,
+        selected_entries,
+        ..
+    } = explode_crate_archive(&downloaded_crate, standard_bin_path, |count| progress.set(count))?;
     progress.info(format!(
-        "Recorded {} files and stored {} in full",
-        meta_count, file_count
+        "Recorded {} files and stored {} in full or in outline",
+        entries_meta_data.len(),
+        selected_entries.len()
     ));
 
     let task_result = model::TaskResult::ExplodedCrate {
-        entries_meta_data: meta_data,
-        selected_entries: files,
+        entries_meta_data,
+        selected_entries,
     };
     results.insert(progress, &key, &task_result)?;
 
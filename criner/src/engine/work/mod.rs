@@ -3,3 +3,6 @@ pub mod iobound;
 pub mod schedule;
 
 pub mod cpubound;
+
+#[cfg(test)]
+mod work_test;
@@ -44,7 +44,7 @@ pub async fn tasks(
     let io_task = task_or_default(tasks, &mut key_buf, krate, iobound::default_persisted_download_task)?;
 
     let kind = "crate";
-    let submit_result = submit_single(startup_time, io_task, progress, perform_io, 1, 1, || {
+    let make_download_request = || {
         let dummy_task = iobound::default_persisted_download_task();
         let mut task_key = String::new();
         dummy_task.fq_key(&krate.name, &krate.version, &mut task_key);
@@ -68,13 +68,34 @@ pub async fn tasks(
                 version = krate.version
             ),
         }
-    })
+    };
+    let submit_result = submit_single(
+        startup_time,
+        io_task,
+        progress,
+        perform_io,
+        1,
+        1,
+        &make_download_request,
+    )
     .await;
 
     Ok(match submit_result {
         PermanentFailure | Submitted => AsyncResult::Done,
         Done(download_crate_task) => {
             let cpu_task = task_or_default(tasks, &mut key_buf, krate, cpubound::default_persisted_extraction_task)?;
+            if needs_processing(&cpu_task)
+                && !archive_is_present(assets_dir, &krate.name, &krate.version, &download_crate_task)
+            {
+                // The archive was garbage-collected, but we need it for (re-)extraction. Downloading it again
+                // will schedule the extraction once done.
+                progress.init(Some(1), Some("task".into()));
+                progress.set(1);
+                progress.info("Archive is gone, downloading it again");
+                progress.blocked("wait for consumer", None);
+                perform_io.send(make_download_request()).await.unwrap();
+                return Ok(AsyncResult::Done);
+            }
             submit_single(startup_time, cpu_task, progress, perform_cpu, 2, 2, || {
                 cpubound::ExtractRequest {
                     download_task: download_crate_task,
@@ -100,6 +121,15 @@ fn task_or_default(
     Ok(tasks.get(key_buf)?.unwrap_or(task))
 }
 
+fn needs_processing(task: &model::Task) -> bool {
+    use model::TaskState::*;
+    match task.state {
+        Complete => false,
+        AttemptsWithFailure(ref v) => v.len() < MAX_ATTEMPTS_BEFORE_WE_GIVE_UP,
+        NotStarted | InProgress(_) => true,
+    }
+}
+
 enum SubmitResult {
     Submitted,
     Done(model::Task),
@@ -146,6 +176,24 @@ async fn submit_single<R>(
     }
 }
 
+/// Returns true if the archive downloaded by `download_task` for the given crate version is still on disk.
+pub fn archive_is_present(
+    assets_dir: &Path,
+    crate_name: &str,
+    crate_version: &str,
+    download_task: &model::Task,
+) -> bool {
+    download_file_path(
+        assets_dir,
+        crate_name,
+        crate_version,
+        &download_task.process,
+        &download_task.version,
+        "crate",
+    )
+    .is_file()
+}
+
 fn crate_dir(assets_dir: &Path, crate_name: &str) -> PathBuf {
     // we can safely assume ascii here - otherwise we panic
     let crate_path = match crate_name.len() {
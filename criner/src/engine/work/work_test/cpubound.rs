@@ -0,0 +1,82 @@
+use crate::engine::{
+    report::waste::rust_source_outline,
+    work::cpubound::{standard_bin_path, ExplodedCrate},
+};
+use std::io::{Read, Write};
+
+/// A reader returning at most `chunk_size` bytes at a time, to see content split at arbitrary positions.
+struct Trickle<'a> {
+    data: &'a [u8],
+    chunk_size: usize,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.data.len().min(self.chunk_size).min(buf.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+fn compressed_size(data: &[u8]) -> u64 {
+    let mut encoder = libflate::deflate::Encoder::new(Vec::new());
+    encoder.write_all(data).unwrap();
+    encoder.finish().into_result().unwrap().len() as u64
+}
+
+#[test]
+fn entries_are_streamed_and_only_what_is_needed_is_kept() -> std::io::Result<()> {
+    let source: Vec<u8> = (0..20_000)
+        .map(|n| match n % 3 {
+            0 => format!("mod m{};\n", n),
+            1 => format!("const N{}: usize = {};\n", n, n),
+            _ => format!("static D{}: &str = include_str!(\"d{}.txt\");", n, n),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .into_bytes();
+    assert!(source.len() > 128 * 1024, "large enough to be truncated if it was kept");
+    let standard_bin_path = standard_bin_path();
+    let mut exploded = ExplodedCrate::new(b"[package]\nname = \"a\"", &standard_bin_path);
+    for chunk_size in &[7, 100_000] {
+        for path in &["a/src/other.rs", "a/src/lib.rs", "a/data.bin"] {
+            exploded.add(
+                path.as_bytes(),
+                source.len() as u64,
+                b'0',
+                Trickle {
+                    data: &source,
+                    chunk_size: *chunk_size,
+                },
+            )?;
+        }
+    }
+    exploded.add(b"a/empty.rs", 0, b'0', std::io::empty())?;
+
+    // Compressing the content in one go or piece by piece yields slightly different sizes
+    let expected_compressed_size = compressed_size(&source) as f64;
+    assert!(exploded.entries_meta_data[..6].iter().all(|e| {
+        let compressed_size = e.compressed_size.expect("always estimated") as f64;
+        (compressed_size / expected_compressed_size - 1.0).abs() < 0.01
+    }));
+    assert_eq!(exploded.entries_meta_data[6].compressed_size, Some(0));
+
+    let kept: Vec<_> = exploded
+        .selected_entries
+        .iter()
+        .map(|(e, content)| (e.path.as_slice(), content.clone()))
+        .collect();
+    let outline = rust_source_outline(&source);
+    assert_eq!(
+        kept,
+        vec![
+            (b"a/src/other.rs".as_ref(), outline.clone()),
+            (b"a/src/lib.rs".as_ref(), source[..128 * 1024].to_vec()),
+            (b"a/src/other.rs".as_ref(), outline),
+            (b"a/src/lib.rs".as_ref(), source[..128 * 1024].to_vec()),
+        ],
+        "outlines are the same no matter how content is split, and other files aren't kept"
+    );
+    Ok(())
+}
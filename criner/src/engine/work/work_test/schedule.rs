@@ -0,0 +1,86 @@
+use crate::{
+    engine::work::{
+        cpubound, iobound,
+        schedule::{self, download_file_path, Scheduling},
+    },
+    model,
+    persistence::{Db, TableAccess},
+};
+use std::time::SystemTime;
+
+#[test]
+fn collected_archives_are_downloaded_again_before_extraction() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let assets_dir = dir.path().join("assets");
+    let db = Db::open(dir.path())?;
+    let tasks = db.open_tasks()?;
+    let root = prodash::tree::root::Options::default().create();
+    let mut progress = root.add_child("schedule");
+    let mut db_progress = root.add_child("db");
+    let krate = model::CrateVersion {
+        name: "abcd".into(),
+        kind: model::ChangeKind::Added,
+        version: "1.0.0".into(),
+        checksum: String::new(),
+        features: Default::default(),
+        dependencies: Vec::new(),
+    };
+    let download_task = model::Task {
+        state: model::TaskState::Complete,
+        ..iobound::default_persisted_download_task()
+    };
+    let mut key = String::new();
+    download_task.fq_key(&krate.name, &krate.version, &mut key);
+    tasks.insert(&mut db_progress, &key, &download_task)?;
+
+    let (io_tx, io_rx) = async_channel::bounded(1);
+    let (cpu_tx, cpu_rx) = async_channel::bounded(1);
+    let mut schedule = || {
+        futures_lite::future::block_on(schedule::tasks(
+            &assets_dir,
+            &tasks,
+            &krate,
+            &mut progress,
+            Scheduling::AtLeastOne,
+            &io_tx,
+            &cpu_tx,
+            SystemTime::now(),
+        ))
+        .map(|_| ())
+    };
+
+    schedule()?;
+    let archive = download_file_path(
+        &assets_dir,
+        &krate.name,
+        &krate.version,
+        &download_task.process,
+        &download_task.version,
+        "crate",
+    );
+    let request = io_rx.try_recv().expect("the archive to be downloaded again");
+    assert_eq!(request.output_file_path, archive);
+    assert!(cpu_rx.try_recv().is_err(), "there is nothing to extract yet");
+
+    std::fs::create_dir_all(archive.parent().expect("archive in crate directory"))?;
+    std::fs::write(&archive, b"")?;
+    schedule()?;
+    assert!(io_rx.try_recv().is_err());
+    let request = cpu_rx.try_recv().expect("the archive to be extracted");
+    assert_eq!(request.crate_version, krate.version);
+
+    let extraction_task = model::Task {
+        state: model::TaskState::Complete,
+        ..cpubound::default_persisted_extraction_task()
+    };
+    key.clear();
+    extraction_task.fq_key(&krate.name, &krate.version, &mut key);
+    tasks.insert(&mut db_progress, &key, &extraction_task)?;
+    std::fs::remove_file(&archive)?;
+    schedule()?;
+    assert!(
+        io_rx.try_recv().is_err() && cpu_rx.try_recv().is_err(),
+        "archives of extracted versions aren't needed anymore"
+    );
+    Ok(())
+}
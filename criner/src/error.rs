@@ -45,9 +45,9 @@ quick_error! {
             from()
             source(err)
         }
-        Git2(err: git2::Error) {
-            from()
-            source(err)
+        Gix(err: Box<dyn std::error::Error + Send + Sync>) {
+            display("{}", err)
+            source(&**err)
         }
         IndexDiffInit(err: crates_index_diff::index::init::Error) {
             from()
@@ -77,6 +77,10 @@ quick_error! {
             from()
             source(err)
         }
+        Sled(err: sled::Error) {
+            from()
+            source(err)
+        }
         GlobSet(err: globset::Error) {
             from()
             source(err)
@@ -111,6 +115,35 @@ quick_error! {
     }
 }
 
+/// gitoxide has an error type for each operation, which all share the same variant to keep the error small.
+macro_rules! from_gix_errors {
+    ($($err:ty),* $(,)?) => {
+        $(
+            impl From<$err> for Error {
+                fn from(err: $err) -> Self {
+                    Error::Gix(Box::new(err))
+                }
+            }
+        )*
+    };
+}
+
+from_gix_errors!(
+    gix::open::Error,
+    gix::worktree::open_index::Error,
+    gix::repository::index_from_tree::Error,
+    gix::index::file::write::Error,
+    gix::reference::find::existing::Error,
+    gix::reference::edit::Error,
+    gix::remote::find::existing::Error,
+    gix::object::find::existing::Error,
+    gix::objs::decode::Error,
+    gix_pack::data::output::entry::Error,
+    gix_pack::data::output::bytes::Error<std::convert::Infallible>,
+    gix_pack::bundle::write::Error,
+    gix_pack::index::init::Error,
+);
+
 impl Error {
     pub fn send_msg<T>(msg: &'static str) -> impl FnOnce(async_channel::SendError<T>) -> Error {
         move |_err| Error::ChannelSendMessage(msg)
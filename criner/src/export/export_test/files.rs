@@ -0,0 +1,156 @@
+use super::add_version;
+use crate::export::{run_blocking, Format};
+use std::path::{Path, PathBuf};
+
+fn export(format: Format) -> crate::Result<(tempfile::TempDir, PathBuf)> {
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("db");
+    add_version(&db_path, "a", "1.0.0", &[("src/lib.rs", 10)])?;
+    let source = add_version(&db_path, "b", "2.0.0", &[("src/lib.rs", 20), ("a.png", 5)])?;
+    let out = dir.path().join("export");
+    run_blocking(source, &out, format, false, &Default::default())?;
+    assert!(!out.join(".staging.sqlite").exists(), "the staging database is removed");
+    Ok((dir, out))
+}
+
+fn table_path(out: &Path, table: &str, format: Format) -> PathBuf {
+    out.join(format!("{}.{}", table, format.extension()))
+}
+
+#[test]
+fn csv_files_have_a_header_and_a_record_per_row() -> crate::Result<()> {
+    let (_dir, out) = export(Format::Csv)?;
+    let mut reader = csv::Reader::from_path(table_path(&out, "crate_version", Format::Csv))?;
+    assert_eq!(
+        reader.headers()?.iter().collect::<Vec<_>>(),
+        vec!["id", "name", "version", "kind", "checksum", "features"]
+    );
+    let records = reader.records().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(records.len(), 2);
+    assert_eq!(
+        records.iter().map(|r| (&r[1], &r[2], &r[3])).collect::<Vec<_>>(),
+        vec![("a", "1.0.0", "added"), ("b", "2.0.0", "added")]
+    );
+
+    let mut reader = csv::Reader::from_path(table_path(&out, "waste_version", Format::Csv))?;
+    let records = reader.records().collect::<Result<Vec<_>, _>>()?;
+    let wasted_bytes = reader
+        .headers()?
+        .iter()
+        .position(|h| h == "wasted_bytes")
+        .expect("column");
+    assert_eq!(
+        records.iter().map(|r| r[wasted_bytes].to_owned()).collect::<Vec<_>>(),
+        vec!["0", "5"]
+    );
+    assert!(
+        table_path(&out, "export_state", Format::Csv).metadata().is_err(),
+        "the export state isn't data"
+    );
+    Ok(())
+}
+
+#[test]
+fn views_are_written_like_tables() -> crate::Result<()> {
+    let (_dir, out) = export(Format::Csv)?;
+    let mut reader = csv::Reader::from_path(table_path(&out, "waste_crate", Format::Csv))?;
+    let headers = reader.headers()?.clone();
+    let records = reader.records().collect::<Result<Vec<_>, _>>()?;
+    let column = |name: &str| headers.iter().position(|h| h == name).expect("column");
+    assert_eq!(
+        records
+            .iter()
+            .map(|r| (
+                &r[column("crate_name")],
+                &r[column("num_versions")],
+                &r[column("latest_version")],
+                &r[column("wasted_bytes")]
+            ))
+            .collect::<Vec<_>>(),
+        vec![("a", "1", "1.0.0", "0"), ("b", "1", "2.0.0", "5")]
+    );
+
+    let (_dir, out) = export(Format::JsonLines)?;
+    let content = std::fs::read_to_string(table_path(&out, "waste_crate", Format::JsonLines))?;
+    let rows = content
+        .lines()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("valid json");
+    assert_eq!(rows.len(), 2);
+    assert_eq!(
+        rows[1]["wasted_bytes_in_all_versions"], 5,
+        "computed columns keep the type of their values"
+    );
+    Ok(())
+}
+
+#[test]
+fn json_lines_have_an_object_per_row_with_json_columns_embedded() -> crate::Result<()> {
+    let (_dir, out) = export(Format::JsonLines)?;
+    let content = std::fs::read_to_string(table_path(&out, "crate_version_dependency", Format::JsonLines))?;
+    let rows = content
+        .lines()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("valid json");
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["name"], "dep");
+    assert_eq!(rows[0]["required_version"], "1");
+    assert_eq!(rows[0]["optional"], 0);
+    assert_eq!(
+        rows[0]["features"],
+        serde_json::json!([]),
+        "JSON columns aren't strings"
+    );
+    assert_eq!(rows[0]["target"], serde_json::Value::Null);
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn parquet_files_have_a_column_per_table_column() -> crate::Result<()> {
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::Field,
+    };
+    let (_dir, out) = export(Format::Parquet)?;
+    let reader = SerializedFileReader::new(std::fs::File::open(table_path(&out, "crate_entry", Format::Parquet))?)
+        .expect("valid parquet file");
+    assert_eq!(reader.metadata().file_metadata().num_rows(), 3);
+    let rows = reader
+        .get_row_iter(None)
+        .expect("rows")
+        .map(|row| {
+            row.expect("valid row")
+                .get_column_iter()
+                .map(|(name, field)| (name.to_owned(), field.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows[2],
+        vec![
+            ("parent_id".into(), Field::Long(1)),
+            ("path".into(), Field::Str("b-2.0.0/a.png".into())),
+            ("size".into(), Field::Long(5)),
+            ("entry_type".into(), Field::Long(48)),
+            ("data".into(), Field::Null),
+        ]
+    );
+
+    let reader = SerializedFileReader::new(std::fs::File::open(table_path(&out, "waste_crate", Format::Parquet))?)
+        .expect("valid parquet file");
+    let row = reader
+        .get_row_iter(None)
+        .expect("rows")
+        .nth(1)
+        .expect("two crates")
+        .expect("valid row");
+    assert!(
+        row.get_column_iter()
+            .any(|(name, field)| name == "num_versions" && *field == Field::Long(1)),
+        "computed columns of views are typed by their values"
+    );
+    Ok(())
+}
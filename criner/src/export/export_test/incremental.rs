@@ -0,0 +1,66 @@
+use super::{add_version, count};
+use crate::export::{run_blocking, Format};
+
+const TABLES: &[&str] = &[
+    "crate",
+    "crate_version",
+    "crate_version_dependency",
+    "result_extract_crate",
+    "crate_entry",
+    "waste_version",
+];
+
+fn counts(db: &std::path::Path) -> crate::Result<Vec<i64>> {
+    TABLES.iter().map(|table| count(db, table)).collect()
+}
+
+fn id_of(db: &std::path::Path, version: &str) -> crate::Result<i64> {
+    Ok(rusqlite::Connection::open(db)?.query_row(
+        "SELECT id FROM crate_version WHERE name = 'a' AND version = ?1",
+        [version],
+        |r| r.get(0),
+    )?)
+}
+
+#[test]
+fn incremental_exports_only_add_new_rows_and_are_idempotent() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("db");
+    let source = add_version(&db_path, "a", "1.0.0", &[("src/lib.rs", 10), ("Cargo.toml", 5)])?;
+    let export = dir.path().join("export.sqlite");
+
+    run_blocking(&source, &export, Format::Sqlite, false, &Default::default())?;
+    assert_eq!(counts(&export)?, vec![1, 1, 1, 1, 2, 1]);
+    let first_id = id_of(&export, "1.0.0")?;
+    assert!(
+        run_blocking(&source, &export, Format::Sqlite, false, &Default::default()).is_err(),
+        "existing databases are only updated incrementally"
+    );
+
+    run_blocking(&source, &export, Format::Sqlite, true, &Default::default())?;
+    assert_eq!(
+        counts(&export)?,
+        vec![1, 1, 1, 1, 2, 1],
+        "nothing changed, nothing is added"
+    );
+    assert_eq!(id_of(&export, "1.0.0")?, first_id);
+
+    add_version(
+        &db_path,
+        "a",
+        "1.1.0",
+        &[("src/lib.rs", 10), ("Cargo.toml", 5), ("a.png", 1)],
+    )?;
+    run_blocking(&source, &export, Format::Sqlite, true, &Default::default())?;
+    assert_eq!(counts(&export)?, vec![2, 2, 2, 2, 5, 2]);
+    assert_eq!(
+        id_of(&export, "1.0.0")?,
+        first_id,
+        "unchanged rows are kept as they are"
+    );
+    assert_ne!(id_of(&export, "1.1.0")?, first_id);
+
+    run_blocking(&source, &export, Format::Sqlite, true, &Default::default())?;
+    assert_eq!(counts(&export)?, vec![2, 2, 2, 2, 5, 2]);
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use crate::{model, persistence::Db};
+use std::path::{Path, PathBuf};
+
+mod files;
+mod incremental;
+mod waste;
+
+/// Add `version` of `crate_name` to the database at `db_path` along with its extraction result, a crate with the
+/// given `files`, and return the path to its sqlite file for use as export source.
+fn add_version(db_path: &Path, crate_name: &str, version: &str, files: &[(&str, u64)]) -> crate::Result<PathBuf> {
+    crate::test_utils::add_version(
+        &Db::open(db_path)?,
+        crate_name,
+        version,
+        files,
+        vec![model::Dependency {
+            name: "dep".into(),
+            required_version: "1".into(),
+            features: Vec::new(),
+            optional: false,
+            default_features: true,
+            target: None,
+            kind: None,
+            package: None,
+        }],
+    )?;
+    Ok(db_path.join("db.msgpack.sqlite"))
+}
+
+fn count(db: &Path, table: &str) -> crate::Result<i64> {
+    Ok(rusqlite::Connection::open(db)?.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |r| r.get(0))?)
+}
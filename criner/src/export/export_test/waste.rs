@@ -0,0 +1,73 @@
+use super::add_version;
+use crate::{
+    engine::report::waste::RuleBook,
+    export::{run_blocking, Format},
+};
+use std::path::Path;
+
+/// Crate name, number of versions, wasted bytes in all versions, latest version and its wasted bytes
+type WasteCrate = (String, i64, i64, String, i64);
+
+/// Return all rows of the `waste_crate` view.
+fn waste_crates(db: &Path) -> crate::Result<Vec<WasteCrate>> {
+    let connection = rusqlite::Connection::open(db)?;
+    let mut statement = connection.prepare(
+        "SELECT crate_name, num_versions, wasted_bytes_in_all_versions, latest_version, wasted_bytes
+         FROM waste_crate ORDER BY crate_name",
+    )?;
+    let rows = statement
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(rows)
+}
+
+#[test]
+fn crates_are_summarized_by_the_waste_of_all_versions_and_of_their_latest_release() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("db");
+    add_version(&db_path, "a", "1.0.0", &[("src/lib.rs", 10), ("a.png", 5)])?;
+    add_version(&db_path, "a", "1.1.0", &[("src/lib.rs", 10), ("a.png", 7)])?;
+    add_version(&db_path, "a", "2.0.0-alpha.1", &[("src/lib.rs", 10), ("a.png", 1)])?;
+    add_version(&db_path, "b", "0.1.0", &[("src/lib.rs", 10), ("b.png", 3)])?;
+    add_version(&db_path, "b", "0.2.0", &[("src/lib.rs", 10)])?;
+    let source = add_version(&db_path, "c", "0.1.0-rc.1", &[("src/lib.rs", 10), ("c.png", 2)])?;
+    let export = dir.path().join("export.sqlite");
+
+    run_blocking(&source, &export, Format::Sqlite, false, &RuleBook::default())?;
+    assert_eq!(
+        waste_crates(&export)?,
+        vec![
+            ("a".into(), 3, 13, "1.1.0".into(), 7),
+            ("b".into(), 2, 3, "0.2.0".into(), 0),
+            ("c".into(), 1, 2, "0.1.0-rc.1".into(), 2)
+        ],
+        "pre-releases are only the latest version if there is no release"
+    );
+
+    add_version(&db_path, "a", "1.2.0", &[("src/lib.rs", 10)])?;
+    run_blocking(&source, &export, Format::Sqlite, true, &RuleBook::default())?;
+    assert_eq!(
+        waste_crates(&export)?[0],
+        ("a".into(), 4, 13, "1.2.0".into(), 0),
+        "incremental exports update the latest version"
+    );
+    Ok(())
+}
+
+#[test]
+fn waste_is_computed_with_the_given_rules() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let source = add_version(
+        &dir.path().join("db"),
+        "a",
+        "1.0.0",
+        &[("src/lib.rs", 10), ("a.png", 5)],
+    )?;
+    let export = dir.path().join("export.sqlite");
+    let rules = RuleBook::from_toml("default = \"everything\"\n[sets.everything]\ninclude = [\"**/*\"]\nexclude = []")
+        .expect("valid rules");
+
+    run_blocking(&source, &export, Format::Sqlite, false, &rules)?;
+    assert_eq!(waste_crates(&export)?, vec![("a".into(), 1, 0, "1.0.0".into(), 0)]);
+    Ok(())
+}
@@ -1,4 +1,10 @@
 mod run;
+mod to_files;
+#[cfg(feature = "parquet")]
+mod to_parquet;
 mod to_sql;
 
-pub use run::run_blocking;
+pub use run::{run_blocking, Format};
+
+#[cfg(test)]
+mod export_test;
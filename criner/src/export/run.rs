@@ -1,15 +1,116 @@
-use super::to_sql::SqlConvert;
-use crate::model;
-use rusqlite::Connection;
+use super::to_sql::{to_seconds_since_epoch, SqlConvert};
+use crate::{engine::report::waste::RuleBook, model};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::path::Path;
 
-pub fn run_blocking(source_db: impl AsRef<Path>, destination_db: impl AsRef<Path>) -> crate::Result<()> {
-    if destination_db.as_ref().is_file() {
+/// The format to export data into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A single sqlite database
+    Sqlite,
+    /// A directory with a CSV file per table and view
+    Csv,
+    /// A directory with a file per table and view, with one JSON object per line and row
+    JsonLines,
+    /// A directory with a parquet file per table and view
+    Parquet,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Sqlite => "sqlite",
+            Format::Csv => "csv",
+            Format::JsonLines => "jsonl",
+            Format::Parquet => "parquet",
+        }
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "sqlite" => Format::Sqlite,
+            "csv" => Format::Csv,
+            "jsonl" | "json-lines" => Format::JsonLines,
+            "parquet" => Format::Parquet,
+            _ => {
+                return Err(format!(
+                    "Unknown format '{}', expected one of 'sqlite', 'csv', 'jsonl' or 'parquet'",
+                    s
+                ))
+            }
+        })
+    }
+}
+
+/// Export the database at `source_db` into `destination` using the given `format`.
+///
+/// All formats except for `Sqlite` write one file per table and view into the `destination` directory.
+/// If `incremental` is true and the `destination` sqlite database exists, only rows which were added or changed
+/// since the last export will be transferred. Otherwise the `destination` must not exist.
+/// The `waste_rules` are used to compute the waste of each extracted crate, just like the waste report does.
+pub fn run_blocking(
+    source_db: impl AsRef<Path>,
+    destination: impl AsRef<Path>,
+    format: Format,
+    incremental: bool,
+    waste_rules: &RuleBook,
+) -> crate::Result<()> {
+    if format == Format::Sqlite {
+        return to_sqlite(source_db, destination, incremental, waste_rules);
+    }
+    if incremental {
+        return Err(crate::Error::Message(format!(
+            "Incremental exports are only supported for sqlite, not for {:?}",
+            format
+        )));
+    }
+    if destination.as_ref().exists() {
         return Err(crate::Error::Message(format!(
-            "Destination database at '{}' does already exist - this is currently unsupported",
+            "Destination directory at '{}' does already exist",
+            destination.as_ref().display()
+        )));
+    }
+    #[cfg(not(feature = "parquet"))]
+    if format == Format::Parquet {
+        return Err(crate::Error::Message(
+            "Parquet support must be enabled with the 'parquet' feature".into(),
+        ));
+    }
+    std::fs::create_dir_all(&destination)?;
+    // The sqlite export has all tables flattened already, so all other formats are produced from it.
+    let staging_db = destination.as_ref().join(".staging.sqlite");
+    to_sqlite(source_db, &staging_db, false, waste_rules)?;
+    super::to_files::write_tables(&staging_db, destination.as_ref(), format)?;
+    std::fs::remove_file(staging_db)?;
+    Ok(())
+}
+
+fn to_sqlite(
+    source_db: impl AsRef<Path>,
+    destination_db: impl AsRef<Path>,
+    incremental: bool,
+    waste_rules: &RuleBook,
+) -> crate::Result<()> {
+    if !incremental && destination_db.as_ref().is_file() {
+        return Err(crate::Error::Message(format!(
+            "Destination database at '{}' does already exist - use an incremental export to update it",
             destination_db.as_ref().display()
         )));
     }
+    if source_db
+        .as_ref()
+        .parent()
+        .is_some_and(|dir| dir.join(crate::persistence::SLED_DIR_NAME).is_dir())
+    {
+        return Err(crate::Error::Message(format!(
+            "The tasks and results of the database at '{}' are stored in sled - please convert it to sqlite first",
+            source_db.as_ref().display()
+        )));
+    }
     let mut input = Connection::open(source_db)?;
     let mut output = Connection::open(destination_db)?;
 
@@ -18,31 +119,62 @@ pub fn run_blocking(source_db: impl AsRef<Path>, destination_db: impl AsRef<Path
     output.execute_batch(
         "
     PRAGMA foreign_keys = FALSE; -- assume we don't mess up relations, save validation time
-    PRAGMA journal_mode = 'OFF' -- no journal, direct writes
+    PRAGMA journal_mode = 'OFF'; -- no journal, direct writes
+    CREATE TABLE IF NOT EXISTS export_state (
+         source_table       TEXT NOT NULL,
+         last_rowid         INTEGER NOT NULL, -- the greatest rowid of the source table at the time of the export
+         exported_at        TIMESTAMP NOT NULL,
+         PRIMARY KEY (source_table)
+    );
 ",
     )?;
 
-    transfer::<model::db_dump::Crate>(&mut input, &mut output)?;
-    transfer::<model::Crate>(&mut input, &mut output)?;
-    transfer::<model::Task>(&mut input, &mut output)?;
-    transfer::<model::Context>(&mut input, &mut output)?;
-    transfer::<model::CrateVersion>(&mut input, &mut output)?;
-    transfer::<model::TaskResult>(&mut input, &mut output)?;
+    transfer::<model::db_dump::Crate>(&mut input, &mut output, waste_rules)?;
+    transfer::<model::Crate>(&mut input, &mut output, waste_rules)?;
+    transfer::<model::Task>(&mut input, &mut output, waste_rules)?;
+    transfer::<model::Context>(&mut input, &mut output, waste_rules)?;
+    transfer::<model::CrateVersion>(&mut input, &mut output, waste_rules)?;
+    transfer::<model::TaskResult>(&mut input, &mut output, waste_rules)?;
 
     Ok(())
 }
 
-fn transfer<T>(input: &mut Connection, output: &mut Connection) -> crate::Result<()>
+/// As all tables use `REPLACE INTO`, changed rows are deleted and re-inserted, receiving a new rowid greater than all
+/// previous ones. Thus everything after the last seen rowid is either new or changed.
+fn transfer<T>(input: &mut Connection, output: &mut Connection, waste_rules: &RuleBook) -> crate::Result<()>
 where
     for<'a> T: SqlConvert + From<&'a [u8]>,
 {
     output.execute_batch(T::init_table_statement())?;
-    let mut istm = input.prepare(&format!("SELECT key, data FROM '{}'", T::source_table_name()))?;
+    let last_rowid: Option<i64> = output
+        .query_row(
+            "SELECT last_rowid FROM export_state WHERE source_table = ?1",
+            params![T::source_table_name()],
+            |r| r.get(0),
+        )
+        .optional()?;
+    let max_rowid: i64 = input.query_row(
+        &format!("SELECT COALESCE(MAX(_rowid_), 0) FROM '{}'", T::source_table_name()),
+        [],
+        |r| r.get(0),
+    )?;
+    let mut istm = input.prepare(&format!(
+        "SELECT key, data FROM '{}' WHERE _rowid_ > ?1 AND _rowid_ <= ?2 ORDER BY _rowid_",
+        T::source_table_name()
+    ))?;
+    let incremental = last_rowid.is_some();
     let transaction = output.transaction()?;
+    let first_id: i32 = match T::id_table_name() {
+        Some(table) => transaction.query_row(&format!("SELECT COALESCE(MAX(id) + 1, 0) FROM {}", table), [], |r| {
+            r.get(0)
+        })?,
+        None => 0,
+    };
     let mut count = 0;
     let start = std::time::SystemTime::now();
     {
-        if let Some(res) = T::convert_to_sql(&mut istm, &transaction) {
+        let rows = params![last_rowid.unwrap_or(0), max_rowid];
+        if let Some(res) = T::convert_to_sql(&mut istm, rows, &transaction, first_id, incremental, waste_rules) {
             count = res?;
         } else {
             let mut ostm = transaction.prepare(T::replace_statement())?;
@@ -51,7 +183,7 @@ where
                 None => None,
             };
             for (uid, res) in istm
-                .query_map([], |r| {
+                .query_map(rows, |r| {
                     let key: String = r.get(0)?;
                     let value: Vec<u8> = r.get(1)?;
                     Ok((key, value))
@@ -60,14 +192,26 @@ where
             {
                 count += 1;
                 let (key, value) = res?;
+                if incremental {
+                    T::delete_previous(&key, &transaction)?;
+                }
                 let value = T::from(value.as_slice());
-                value.insert(&key, uid as i32, &mut ostm, secondary_ostm.as_mut())?;
+                value.insert(&key, first_id + uid as i32, &mut ostm, secondary_ostm.as_mut())?;
             }
         }
     }
+    transaction.execute(
+        "REPLACE INTO export_state (source_table, last_rowid, exported_at) VALUES (?1, ?2, ?3)",
+        params![
+            T::source_table_name(),
+            max_rowid,
+            to_seconds_since_epoch(std::time::SystemTime::now())
+        ],
+    )?;
     transaction.commit()?;
     log::info!(
-        "Inserted {} {} in {:?}",
+        "{} {} {} in {:?}",
+        if incremental { "Updated" } else { "Inserted" },
         count,
         T::source_table_name(),
         std::time::SystemTime::now().duration_since(start).unwrap()
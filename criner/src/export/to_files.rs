@@ -0,0 +1,141 @@
+use super::Format;
+use rusqlite::{types::ValueRef, Connection, OptionalExtension, Rows};
+use std::{fs::File, io::BufWriter, io::Write, path::Path};
+
+#[derive(Clone, Copy)]
+pub enum ColumnKind {
+    Integer,
+    /// Seconds since UNIX epoch
+    Timestamp,
+    Text,
+    Json,
+    Blob,
+}
+
+pub struct Column {
+    pub name: String,
+    pub kind: ColumnKind,
+}
+
+fn columns(connection: &Connection, table: &str) -> crate::Result<Vec<Column>> {
+    let mut statement = connection.prepare(&format!("PRAGMA table_info('{}')", table))?;
+    let columns = statement
+        .query_map([], |r| Ok((r.get::<_, String>(1)?, r.get::<_, String>(2)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    columns
+        .into_iter()
+        .map(|(name, mut declared_type)| {
+            if declared_type.is_empty() {
+                // Columns of views computed by expressions have no declared type, so use the one of their values
+                declared_type = connection
+                    .query_row(
+                        &format!(
+                            "SELECT upper(typeof(\"{column}\")) FROM '{}' WHERE \"{column}\" IS NOT NULL LIMIT 1",
+                            table,
+                            column = name
+                        ),
+                        [],
+                        |r| r.get(0),
+                    )
+                    .optional()?
+                    .unwrap_or_default();
+            }
+            Ok(Column {
+                name,
+                kind: match declared_type.as_str() {
+                    "INTEGER" => ColumnKind::Integer,
+                    "TIMESTAMP" => ColumnKind::Timestamp,
+                    "JSON" => ColumnKind::Json,
+                    "BLOB" => ColumnKind::Blob,
+                    _ => ColumnKind::Text,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Write each table and view of the exported sqlite database at `sqlite_path` into its own file in `out_dir`.
+pub fn write_tables(sqlite_path: &Path, out_dir: &Path, format: Format) -> crate::Result<()> {
+    let connection = Connection::open(sqlite_path)?;
+    let tables = connection
+        .prepare(
+            "SELECT name FROM sqlite_master WHERE type IN ('table', 'view') AND name != 'export_state' ORDER BY name",
+        )?
+        .query_map([], |r| r.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for table in tables {
+        let start = std::time::SystemTime::now();
+        let columns = columns(&connection, &table)?;
+        let mut statement = connection.prepare(&format!("SELECT * FROM '{}'", table))?;
+        let rows = statement.query([])?;
+        let path = out_dir.join(format!("{}.{}", table, format.extension()));
+        let count = match format {
+            Format::Csv => write_csv(rows, &columns, &path)?,
+            Format::JsonLines => write_json_lines(rows, &columns, &path)?,
+            #[cfg(feature = "parquet")]
+            Format::Parquet => super::to_parquet::write(rows, &columns, &path)?,
+            #[cfg(not(feature = "parquet"))]
+            Format::Parquet => return Err(crate::Error::Bug("parquet support was not compiled in")),
+            Format::Sqlite => unreachable!("sqlite is written directly"),
+        };
+        log::info!(
+            "Wrote {} rows of '{}' to '{}' in {:?}",
+            count,
+            table,
+            path.display(),
+            std::time::SystemTime::now().duration_since(start).unwrap()
+        );
+    }
+    Ok(())
+}
+
+fn write_csv(mut rows: Rows<'_>, columns: &[Column], path: &Path) -> crate::Result<usize> {
+    let mut out = csv::Writer::from_path(path)?;
+    out.write_record(columns.iter().map(|c| &c.name))?;
+    let mut count = 0;
+    let mut record = Vec::with_capacity(columns.len());
+    while let Some(row) = rows.next()? {
+        record.clear();
+        for idx in 0..columns.len() {
+            record.push(match row.get_ref(idx)? {
+                ValueRef::Null => String::new(),
+                ValueRef::Integer(v) => v.to_string(),
+                ValueRef::Real(v) => v.to_string(),
+                ValueRef::Text(v) => String::from_utf8_lossy(v).into_owned(),
+                ValueRef::Blob(v) => hex::encode(v),
+            });
+        }
+        out.write_record(&record)?;
+        count += 1;
+    }
+    out.flush()?;
+    Ok(count)
+}
+
+fn write_json_lines(mut rows: Rows<'_>, columns: &[Column], path: &Path) -> crate::Result<usize> {
+    use serde_json::Value;
+    let mut out = BufWriter::new(File::create(path)?);
+    let mut count = 0;
+    while let Some(row) = rows.next()? {
+        let mut object = serde_json::Map::with_capacity(columns.len());
+        for (idx, column) in columns.iter().enumerate() {
+            let value = match (row.get_ref(idx)?, column.kind) {
+                (ValueRef::Null, _) => Value::Null,
+                (ValueRef::Integer(v), _) => v.into(),
+                (ValueRef::Real(v), _) => v.into(),
+                (ValueRef::Text(v), ColumnKind::Json) => {
+                    serde_json::from_slice(v).unwrap_or_else(|_| String::from_utf8_lossy(v).into_owned().into())
+                }
+                (ValueRef::Text(v), _) => String::from_utf8_lossy(v).into_owned().into(),
+                (ValueRef::Blob(v), _) => hex::encode(v).into(),
+            };
+            object.insert(column.name.clone(), value);
+        }
+        serde_json::to_writer(&mut out, &object).map_err(std::io::Error::from)?;
+        out.write_all(b"\n")?;
+        count += 1;
+    }
+    out.flush()?;
+    Ok(count)
+}
@@ -0,0 +1,142 @@
+use super::to_files::{Column, ColumnKind};
+use parquet::{
+    basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType},
+    data_type::{ByteArray, ByteArrayType, Int64Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    format::MilliSeconds,
+    schema::types::Type,
+};
+use rusqlite::{types::ValueRef, Rows};
+use std::{fs::File, path::Path, sync::Arc};
+
+const ROWS_PER_ROW_GROUP: usize = 64 * 1024;
+
+enum Values {
+    Int64(Vec<i64>),
+    Bytes(Vec<ByteArray>),
+}
+
+/// The values of a column within a row group, with a definition level of 0 marking nulls.
+struct ColumnBuffer {
+    values: Values,
+    definition_levels: Vec<i16>,
+}
+
+fn into_error(err: parquet::errors::ParquetError) -> crate::Error {
+    crate::Error::Message(format!("Failed to write parquet file: {}", err))
+}
+
+fn schema(columns: &[Column]) -> parquet::errors::Result<Type> {
+    let fields = columns
+        .iter()
+        .map(|c| {
+            let (physical_type, logical_type) = match c.kind {
+                ColumnKind::Integer => (PhysicalType::INT64, None),
+                ColumnKind::Timestamp => (
+                    PhysicalType::INT64,
+                    Some(LogicalType::Timestamp {
+                        is_adjusted_to_u_t_c: true,
+                        unit: TimeUnit::MILLIS(MilliSeconds {}),
+                    }),
+                ),
+                ColumnKind::Text => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+                ColumnKind::Json => (PhysicalType::BYTE_ARRAY, Some(LogicalType::Json)),
+                ColumnKind::Blob => (PhysicalType::BYTE_ARRAY, None),
+            };
+            Type::primitive_type_builder(&c.name, physical_type)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(logical_type)
+                .build()
+                .map(Arc::new)
+        })
+        .collect::<parquet::errors::Result<Vec<_>>>()?;
+    Type::group_type_builder("schema").with_fields(fields).build()
+}
+
+pub fn write(mut rows: Rows<'_>, columns: &[Column], path: &Path) -> crate::Result<usize> {
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = SerializedFileWriter::new(
+        File::create(path)?,
+        Arc::new(schema(columns).map_err(into_error)?),
+        Arc::new(properties),
+    )
+    .map_err(into_error)?;
+
+    let new_buffers = || {
+        columns
+            .iter()
+            .map(|c| ColumnBuffer {
+                values: match c.kind {
+                    ColumnKind::Integer | ColumnKind::Timestamp => Values::Int64(Vec::new()),
+                    ColumnKind::Text | ColumnKind::Json | ColumnKind::Blob => Values::Bytes(Vec::new()),
+                },
+                definition_levels: Vec::new(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut buffers = new_buffers();
+    let mut count = 0;
+    let mut rows_in_group = 0;
+    while let Some(row) = rows.next()? {
+        for (idx, (buffer, column)) in buffers.iter_mut().zip(columns).enumerate() {
+            let value = row.get_ref(idx)?;
+            if let ValueRef::Null = value {
+                buffer.definition_levels.push(0);
+                continue;
+            }
+            buffer.definition_levels.push(1);
+            match (&mut buffer.values, value) {
+                (Values::Int64(values), ValueRef::Integer(v)) => values.push(match column.kind {
+                    ColumnKind::Timestamp => v * 1000,
+                    _ => v,
+                }),
+                (Values::Bytes(values), ValueRef::Text(v)) | (Values::Bytes(values), ValueRef::Blob(v)) => {
+                    values.push(v.to_vec().into())
+                }
+                _ => {
+                    return Err(crate::Error::Message(format!(
+                        "Value of column '{}' doesn't match its declared type",
+                        column.name
+                    )))
+                }
+            }
+        }
+        count += 1;
+        rows_in_group += 1;
+        if rows_in_group == ROWS_PER_ROW_GROUP {
+            write_row_group(&mut writer, std::mem::replace(&mut buffers, new_buffers()))?;
+            rows_in_group = 0;
+        }
+    }
+    if rows_in_group != 0 {
+        write_row_group(&mut writer, buffers)?;
+    }
+    writer.close().map_err(into_error)?;
+    Ok(count)
+}
+
+fn write_row_group(writer: &mut SerializedFileWriter<File>, buffers: Vec<ColumnBuffer>) -> crate::Result<()> {
+    let mut row_group = writer.next_row_group().map_err(into_error)?;
+    for buffer in buffers {
+        let mut column = row_group
+            .next_column()
+            .map_err(into_error)?
+            .ok_or(crate::Error::Bug("there is a column writer for each column"))?;
+        match buffer.values {
+            Values::Int64(values) => {
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&values, Some(&buffer.definition_levels), None)
+            }
+            Values::Bytes(values) => {
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, Some(&buffer.definition_levels), None)
+            }
+        }
+        .map_err(into_error)?;
+        column.close().map_err(into_error)?;
+    }
+    row_group.close().map_err(into_error)?;
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use crate::{
-    export::to_sql::{to_seconds_since_epoch, SqlConvert},
+    export::to_sql::{to_seconds_since_epoch, RuleBook, SqlConvert},
     model,
 };
 use rusqlite::{params, Statement};
@@ -14,7 +14,7 @@ impl SqlConvert for model::db_dump::Crate {
     fn init_table_statement() -> &'static str {
         "
         BEGIN;
-        CREATE TABLE 'crates.io-crate_version' (
+        CREATE TABLE IF NOT EXISTS 'crates.io-crate_version' (
              parent_id              INTEGER NOT NULL,
              crate_name             TEXT NOT NULL,
              semver                 TEXT NOT NULL,
@@ -26,9 +26,10 @@ impl SqlConvert for model::db_dump::Crate {
              crate_size             INTEGER,
              published_by           INTEGER,  -- Github user id as index into crates.io-actor table
              is_yanked              INTEGER NOT NULL,  -- is 1 if this version is yanked
-             FOREIGN KEY (parent_id) REFERENCES 'crates.io-crate'(_row_id_)
+             FOREIGN KEY (parent_id) REFERENCES 'crates.io-crate'(_rowid_)
         );
-        CREATE TABLE 'crates.io-actor' (
+        CREATE INDEX IF NOT EXISTS 'crates.io-crate_version_crate_name' ON 'crates.io-crate_version' (crate_name);
+        CREATE TABLE IF NOT EXISTS 'crates.io-actor' (
              crates_io_id                      INTEGER NOT NULL, -- these IDs are not unique, so we can't use it as unique id
              kind                              TEXT NOT NULL,
              github_id                         INTEGER NOT NULL, -- This is a unique id across teams and users
@@ -37,7 +38,7 @@ impl SqlConvert for model::db_dump::Crate {
              name                              TEXT,
              PRIMARY KEY (github_id)
         );
-        CREATE TABLE 'crates.io-crate' (
+        CREATE TABLE IF NOT EXISTS 'crates.io-crate' (
              name                TEXT NOT NULL,
              stored_at           TIMESTAMP NOT NULL,
              created_at          TIMESTAMP NOT NULL,
@@ -61,9 +62,13 @@ impl SqlConvert for model::db_dump::Crate {
 
     fn convert_to_sql(
         input_statement: &mut rusqlite::Statement,
+        input_params: &[&dyn rusqlite::ToSql],
         transaction: &rusqlite::Transaction,
+        _first_id: i32,
+        incremental: bool,
+        _waste_rules: &RuleBook,
     ) -> Option<crate::Result<usize>> {
-        Some(do_it(input_statement, transaction))
+        Some(do_it(input_statement, input_params, transaction, incremental))
     }
 
     fn insert(
@@ -77,7 +82,12 @@ impl SqlConvert for model::db_dump::Crate {
     }
 }
 
-fn do_it(input_statement: &mut rusqlite::Statement, transaction: &rusqlite::Transaction) -> crate::Result<usize> {
+fn do_it(
+    input_statement: &mut rusqlite::Statement,
+    input_params: &[&dyn rusqlite::ToSql],
+    transaction: &rusqlite::Transaction,
+    incremental: bool,
+) -> crate::Result<usize> {
     let mut insert_crate = transaction
         .prepare("
             REPLACE INTO 'crates.io-crate'
@@ -100,13 +110,16 @@ fn do_it(input_statement: &mut rusqlite::Statement, transaction: &rusqlite::Tran
             "
             INSERT OR IGNORE INTO 'crates.io-crate_version'
                      (parent_id, crate_name, semver, created_at, updated_at, downloads, features, license, crate_size, published_by, is_yanked)
-              VALUES (?1       , ?2        , ?3        , ?4        , ?5        , ?6       , ?7      , ?8 , ?9        , ?10         , , ?11);
+              VALUES (?1       , ?2        , ?3    , ?4        , ?5        , ?6       , ?7      , ?8     , ?9        , ?10         , ?11);
         ",
         )
         .unwrap();
 
+    let mut delete_crate_versions =
+        transaction.prepare("DELETE FROM 'crates.io-crate_version' WHERE crate_name = ?1")?;
+
     let mut count = 0;
-    for res in input_statement.query_map([], |r| {
+    for res in input_statement.query_map(input_params, |r| {
         let key: String = r.get(0)?;
         let value: Vec<u8> = r.get(1)?;
         Ok((key, value))
@@ -138,6 +151,9 @@ fn do_it(input_statement: &mut rusqlite::Statement, transaction: &rusqlite::Tran
             insert_actor_to_db(&mut insert_actor, owner)?;
         }
 
+        if incremental {
+            delete_crate_versions.execute(params![name])?;
+        }
         count += insert_crate.execute(params![
             name,
             to_seconds_since_epoch(stored_at),
@@ -154,6 +170,7 @@ fn do_it(input_statement: &mut rusqlite::Statement, transaction: &rusqlite::Tran
             serde_json::to_string_pretty(&keywords).unwrap(),
             serde_json::to_string_pretty(&categories).unwrap(),
         ])?;
+        let crate_rowid = transaction.last_insert_rowid();
 
         for version in versions {
             let model::db_dump::CrateVersion {
@@ -168,7 +185,7 @@ fn do_it(input_statement: &mut rusqlite::Statement, transaction: &rusqlite::Tran
                 is_yanked,
             } = version;
             insert_crate_version.execute(params![
-                count as i32,
+                crate_rowid,
                 name,
                 semver,
                 to_seconds_since_epoch(created_at),
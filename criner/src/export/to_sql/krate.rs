@@ -11,12 +11,18 @@ impl SqlConvert for model::Crate {
         "crate"
     }
     fn init_table_statement() -> &'static str {
-        "CREATE TABLE crate (
+        "CREATE TABLE IF NOT EXISTS crate (
              name           TEXT NOT NULL,
              version        TEXT NOT NULL,
              PRIMARY KEY (name, version)
         )"
     }
+    fn delete_previous(key: &str, transaction: &rusqlite::Transaction) -> crate::Result<()> {
+        transaction
+            .prepare_cached("DELETE FROM crate WHERE name = ?1")?
+            .execute(params![key])?;
+        Ok(())
+    }
 
     fn insert(
         &self,
@@ -22,7 +22,7 @@ impl SqlConvert for model::CrateVersion {
     }
 
     fn init_table_statement() -> &'static str {
-        "CREATE TABLE crate_version (
+        "CREATE TABLE IF NOT EXISTS crate_version (
             id                  INTEGER UNIQUE NOT NULL,
             name                TEXT NOT NULL,
             version             TEXT NOT NULL,
@@ -31,7 +31,7 @@ impl SqlConvert for model::CrateVersion {
             features            JSON NOT NULL,
             PRIMARY KEY (name, version)
         );
-        CREATE TABLE crate_version_dependency (
+        CREATE TABLE IF NOT EXISTS crate_version_dependency (
              parent_id              INTEGER NOT NULL,
              name                   TEXT NOT NULL,
              required_version       TEXT NOT NULL,
@@ -43,9 +43,30 @@ impl SqlConvert for model::CrateVersion {
              package                TEXT,
              FOREIGN KEY (parent_id) REFERENCES crate_version(id)
         );
+        CREATE INDEX IF NOT EXISTS crate_version_dependency_parent_id ON crate_version_dependency (parent_id);
         "
     }
 
+    fn id_table_name() -> Option<&'static str> {
+        Some("crate_version")
+    }
+
+    fn delete_previous(key: &str, transaction: &rusqlite::Transaction) -> crate::Result<()> {
+        let mut tokens = key.split(crate::persistence::KEY_SEP_CHAR);
+        let name = tokens.next().unwrap();
+        let version = tokens.next().unwrap();
+        transaction
+            .prepare_cached(
+                "DELETE FROM crate_version_dependency WHERE parent_id IN
+                    (SELECT id FROM crate_version WHERE name = ?1 AND version = ?2)",
+            )?
+            .execute(params![name, version])?;
+        transaction
+            .prepare_cached("DELETE FROM crate_version WHERE name = ?1 AND version = ?2")?
+            .execute(params![name, version])?;
+        Ok(())
+    }
+
     fn insert(
         &self,
         _key: &str,
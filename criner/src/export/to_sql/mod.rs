@@ -4,15 +4,25 @@ mod krate_version;
 mod meta;
 mod result;
 mod task;
+mod waste;
+
+use crate::engine::report::waste::RuleBook;
 
 pub fn to_seconds_since_epoch(time: std::time::SystemTime) -> i64 {
     time.duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64
 }
 
 pub trait SqlConvert {
+    /// Convert all rows yielded by `input_statement` when bound to `input_params`, using ids starting at `first_id`.
+    /// If `incremental` is true, previously exported rows have to be deleted before inserting them again.
+    /// The `waste_rules` classify the files of extracted crates as waste.
     fn convert_to_sql(
         _input_statement: &mut rusqlite::Statement,
+        _input_params: &[&dyn rusqlite::ToSql],
         _transaction: &rusqlite::Transaction,
+        _first_id: i32,
+        _incremental: bool,
+        _waste_rules: &RuleBook,
     ) -> Option<crate::Result<usize>> {
         None
     }
@@ -21,6 +31,14 @@ pub trait SqlConvert {
         None
     }
     fn source_table_name() -> &'static str;
+    /// The name of the table with the `id` column that other tables refer to, if there is one.
+    fn id_table_name() -> Option<&'static str> {
+        None
+    }
+    /// Delete everything previously exported for `key`, including rows in other tables referring to it.
+    fn delete_previous(_key: &str, _transaction: &rusqlite::Transaction) -> crate::Result<()> {
+        Ok(())
+    }
     fn init_table_statement() -> &'static str;
     fn insert(
         &self,
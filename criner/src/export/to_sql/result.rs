@@ -1,16 +1,25 @@
-use crate::export::to_sql::SqlConvert;
-use crate::model;
+use crate::export::to_sql::{waste, SqlConvert};
+use crate::{
+    engine::report::waste::{RuleBook, TarPackage},
+    model,
+};
 use rusqlite::{params, Statement};
 
 impl SqlConvert for model::TaskResult {
     fn convert_to_sql(
         istm: &mut rusqlite::Statement,
+        input_params: &[&dyn rusqlite::ToSql],
         transaction: &rusqlite::Transaction,
+        first_id: i32,
+        incremental: bool,
+        waste_rules: &RuleBook,
     ) -> Option<crate::Result<usize>> {
         let res = (|| {
             let mut num_downloads = 0;
             let mut num_extract_crates = 0;
             let mut num_crate_entries = 0;
+            let mut num_waste_rows = 0;
+            let mut waste = waste::Inserter::new(transaction)?;
             let mut insert_download = transaction
                 .prepare(
                     "
@@ -40,7 +49,7 @@ impl SqlConvert for model::TaskResult {
                 )
                 .unwrap();
 
-            for res in istm.query_map([], |r| {
+            for res in istm.query_map(input_params, |r| {
                 let key: String = r.get(0)?;
                 let value: Vec<u8> = r.get(1)?;
                 Ok((key, value))
@@ -82,7 +91,18 @@ impl SqlConvert for model::TaskResult {
                         selected_entries,
                     } => {
                         assert_eq!(process, "extract_crate");
-                        let id = num_extract_crates as i32;
+                        // Results of previous versions of the extraction are replaced by the ones of later versions
+                        waste::Inserter::delete_previous(transaction, crate_name, crate_version)?;
+                        if incremental {
+                            transaction
+                                .prepare_cached(
+                                    "DELETE FROM crate_entry WHERE parent_id IN
+                                        (SELECT id FROM result_extract_crate
+                                         WHERE crate_name = ?1 AND crate_version = ?2 AND version = ?3)",
+                                )?
+                                .execute(params![crate_name, crate_version, process_version])?;
+                        }
+                        let id = first_id + num_extract_crates as i32;
                         insert_extract_crate.execute(params![
                             id,
                             crate_name,
@@ -91,7 +111,12 @@ impl SqlConvert for model::TaskResult {
                             entries_meta_data.len() as i64
                         ])?;
                         for entry in entries_meta_data.iter() {
-                            let model::TarHeader { path, size, entry_type } = entry;
+                            let model::TarHeader {
+                                path,
+                                size,
+                                entry_type,
+                                compressed_size: _,
+                            } = entry;
                             insert_crate_entry.execute(params![
                                 id,
                                 std::str::from_utf8(path).expect("utf8 path in crate - lets see how long this is true"),
@@ -102,7 +127,12 @@ impl SqlConvert for model::TaskResult {
                             num_crate_entries += 1;
                         }
                         for (entry, data) in selected_entries.iter() {
-                            let model::TarHeader { path, size, entry_type } = entry;
+                            let model::TarHeader {
+                                path,
+                                size,
+                                entry_type,
+                                compressed_size: _,
+                            } = entry;
                             insert_crate_entry.execute(params![
                                 id,
                                 std::str::from_utf8(path).expect("utf8 path in crate - lets see how long this is true"),
@@ -113,11 +143,21 @@ impl SqlConvert for model::TaskResult {
                             num_crate_entries += 1;
                         }
                         num_extract_crates += 1;
+                        num_waste_rows += waste.insert(
+                            id,
+                            crate_name,
+                            crate_version,
+                            TarPackage {
+                                entries_meta_data,
+                                entries: selected_entries,
+                            },
+                            waste_rules.rules_for(crate_name),
+                        )?;
                     }
                     TaskResult::None => {}
                 };
             }
-            Ok(num_downloads + num_extract_crates + num_crate_entries)
+            Ok(num_downloads + num_extract_crates + num_crate_entries + num_waste_rows)
         })();
         Some(res)
     }
@@ -130,10 +170,14 @@ impl SqlConvert for model::TaskResult {
         "result"
     }
 
+    fn id_table_name() -> Option<&'static str> {
+        Some("result_extract_crate")
+    }
+
     fn init_table_statement() -> &'static str {
         "
         BEGIN;
-        CREATE TABLE result_download (
+        CREATE TABLE IF NOT EXISTS result_download (
             crate_name                      TEXT NOT NULL,
             crate_version                   TEXT NOT NULL,
             version                         TEXT NOT NULL, -- version of the process that created the result
@@ -144,7 +188,7 @@ impl SqlConvert for model::TaskResult {
             content_type                    TEXT,
             PRIMARY KEY (crate_name, crate_version, version, kind)
         );
-        CREATE TABLE result_extract_crate (
+        CREATE TABLE IF NOT EXISTS result_extract_crate (
             id                              INTEGER UNIQUE NOT NULL,
             crate_name                      TEXT NOT NULL,
             crate_version                   TEXT NOT NULL,
@@ -153,7 +197,7 @@ impl SqlConvert for model::TaskResult {
             num_crate_entries               INTEGER NOT NULL,
             PRIMARY KEY (crate_name, crate_version, version)
         );
-        CREATE TABLE crate_entry (
+        CREATE TABLE IF NOT EXISTS crate_entry (
             parent_id                       INTEGER NOT NULL,
             path                            TEXT NOT NULL,
 
@@ -22,7 +22,7 @@ impl SqlConvert for model::Task {
     }
     fn init_table_statement() -> &'static str {
         "BEGIN;
-            CREATE TABLE task (
+            CREATE TABLE IF NOT EXISTS task (
                  id               INTEGER UNIQUE NOT NULL,
                  key              TEXT NOT NULL,
                  process          TEXT NOT NULL,
@@ -31,13 +31,26 @@ impl SqlConvert for model::Task {
                  state            TEXT NOT NULL,
                  PRIMARY KEY      (key)
             );
-            CREATE TABLE task_error (
+            CREATE TABLE IF NOT EXISTS task_error (
                  parent_id        INTEGER NOT NULL,
                  error            TEXT NOT NULL,
                  FOREIGN KEY (parent_id) REFERENCES task(id)
             );
+            CREATE INDEX IF NOT EXISTS task_error_parent_id ON task_error (parent_id);
          COMMIT;"
     }
+    fn id_table_name() -> Option<&'static str> {
+        Some("task")
+    }
+    fn delete_previous(key: &str, transaction: &rusqlite::Transaction) -> crate::Result<()> {
+        transaction
+            .prepare_cached("DELETE FROM task_error WHERE parent_id IN (SELECT id FROM task WHERE key = ?1)")?
+            .execute(params![key])?;
+        transaction
+            .prepare_cached("DELETE FROM task WHERE key = ?1")?
+            .execute(params![key])?;
+        Ok(())
+    }
 
     fn insert(
         &self,
@@ -0,0 +1,227 @@
+use crate::engine::report::waste::{is_later_version, Fix, PotentialWaste, Report, Rules, TarPackage};
+use rusqlite::{params, OptionalExtension, Statement, Transaction};
+
+/// Tables holding the waste report of each extracted crate, as computed by the waste report generator.
+pub const INIT_TABLE_STATEMENT: &str = "
+    CREATE TABLE IF NOT EXISTS waste_version (
+        id                              INTEGER UNIQUE NOT NULL, -- the id of the respective result_extract_crate row
+        crate_name                      TEXT NOT NULL,
+        crate_version                   TEXT NOT NULL,
+
+        total_bytes                     INTEGER NOT NULL,
+        total_files                     INTEGER NOT NULL,
+        wasted_bytes                    INTEGER NOT NULL,
+        wasted_files                    INTEGER NOT NULL,
+        potential_wasted_bytes          INTEGER, -- bytes that are likely to be waste, but require a human to confirm
+        potential_wasted_files          INTEGER,
+        fix_kind                        TEXT, -- the kind of fix, or NULL if there is nothing to fix
+        fix_has_build_script            INTEGER, -- BOOL
+        PRIMARY KEY (crate_name, crate_version),
+        FOREIGN KEY (id) REFERENCES result_extract_crate(id)
+    );
+    CREATE TABLE IF NOT EXISTS waste_file (
+        parent_id                       INTEGER NOT NULL,
+        path                            TEXT NOT NULL,
+        size                            INTEGER NOT NULL, -- size in bytes
+        PRIMARY KEY (parent_id, path),
+        FOREIGN KEY (parent_id) REFERENCES waste_version(id)
+    );
+    CREATE TABLE IF NOT EXISTS waste_fix_pattern (
+        parent_id                       INTEGER NOT NULL,
+        kind                            TEXT NOT NULL, -- one of include, include_added, include_removed, exclude, exclude_added or potential_include
+        pattern                         TEXT NOT NULL,
+        FOREIGN KEY (parent_id) REFERENCES waste_version(id)
+    );
+    CREATE INDEX IF NOT EXISTS waste_fix_pattern_parent_id ON waste_fix_pattern (parent_id);
+    -- The latest release of each crate, or its latest pre-release if there are no releases, like in waste reports
+    CREATE TABLE IF NOT EXISTS waste_crate_latest (
+        crate_name                      TEXT PRIMARY KEY NOT NULL,
+        crate_version                   TEXT NOT NULL
+    );
+    -- The waste of all versions and of the latest version of each crate.
+    -- Recreated to replace views of previous exports.
+    DROP VIEW IF EXISTS waste_crate;
+    CREATE VIEW waste_crate AS
+        SELECT w.crate_name,
+               a.num_versions,
+               a.wasted_bytes_in_all_versions,
+               a.wasted_files_in_all_versions,
+               w.crate_version                      AS latest_version,
+               w.total_bytes,
+               w.total_files,
+               w.wasted_bytes,
+               w.wasted_files,
+               w.potential_wasted_bytes,
+               w.potential_wasted_files
+        FROM waste_crate_latest l
+        JOIN waste_version w ON w.crate_name = l.crate_name AND w.crate_version = l.crate_version
+        JOIN (SELECT crate_name,
+                     COUNT(*)           AS num_versions,
+                     SUM(wasted_bytes)  AS wasted_bytes_in_all_versions,
+                     SUM(wasted_files)  AS wasted_files_in_all_versions
+              FROM waste_version
+              GROUP BY crate_name) a ON a.crate_name = w.crate_name;
+";
+
+pub struct Inserter<'a> {
+    version: Statement<'a>,
+    file: Statement<'a>,
+    pattern: Statement<'a>,
+    latest_version: Statement<'a>,
+    set_latest_version: Statement<'a>,
+}
+
+impl<'a> Inserter<'a> {
+    pub fn new(transaction: &'a Transaction) -> crate::Result<Self> {
+        transaction.execute_batch(INIT_TABLE_STATEMENT)?;
+        Ok(Inserter {
+            version: transaction.prepare(
+                "REPLACE INTO waste_version
+                         (id, crate_name, crate_version, total_bytes, total_files, wasted_bytes, wasted_files,
+                          potential_wasted_bytes, potential_wasted_files, fix_kind, fix_has_build_script)
+                  VALUES (?1, ?2        , ?3           , ?4         , ?5         , ?6          , ?7          ,
+                          ?8                    , ?9                    , ?10     , ?11);",
+            )?,
+            file: transaction.prepare(
+                "REPLACE INTO waste_file
+                         (parent_id, path, size)
+                  VALUES (?1       , ?2  , ?3);",
+            )?,
+            pattern: transaction.prepare(
+                "INSERT INTO waste_fix_pattern
+                         (parent_id, kind, pattern)
+                  VALUES (?1       , ?2  , ?3);",
+            )?,
+            latest_version: transaction
+                .prepare("SELECT crate_version FROM waste_crate_latest WHERE crate_name = ?1")?,
+            set_latest_version: transaction.prepare(
+                "REPLACE INTO waste_crate_latest
+                         (crate_name, crate_version)
+                  VALUES (?1        , ?2);",
+            )?,
+        })
+    }
+
+    /// Delete the waste report previously exported for the given crate version.
+    pub fn delete_previous(transaction: &Transaction, crate_name: &str, crate_version: &str) -> crate::Result<()> {
+        for table in &["waste_file", "waste_fix_pattern"] {
+            transaction
+                .prepare_cached(&format!(
+                    "DELETE FROM {} WHERE parent_id IN
+                        (SELECT id FROM waste_version WHERE crate_name = ?1 AND crate_version = ?2)",
+                    table
+                ))?
+                .execute(params![crate_name, crate_version])?;
+        }
+        transaction
+            .prepare_cached("DELETE FROM waste_version WHERE crate_name = ?1 AND crate_version = ?2")?
+            .execute(params![crate_name, crate_version])?;
+        Ok(())
+    }
+
+    /// Compute the waste report for `package` using `rules` and insert it with the given `id`, returning the amount
+    /// of inserted rows.
+    pub fn insert(
+        &mut self,
+        id: i32,
+        crate_name: &str,
+        crate_version: &str,
+        package: TarPackage,
+        rules: &Rules,
+    ) -> crate::Result<usize> {
+        let (total_size_in_bytes, total_files, wasted_files, suggested_fix) =
+            match Report::from_package_with_rules(crate_name, crate_version, package, rules) {
+                Report::Version {
+                    total_size_in_bytes,
+                    total_files,
+                    wasted_files,
+                    suggested_fix,
+                    ..
+                } => (total_size_in_bytes, total_files, wasted_files, suggested_fix),
+                _ => unreachable!("from_package always produces version reports"),
+            };
+
+        let mut patterns = Vec::new();
+        let (fix_kind, has_build_script, potential_waste) = match suggested_fix {
+            None => (None, None, None),
+            Some(Fix::ImprovedInclude {
+                include,
+                include_removed,
+                potential,
+                has_build_script,
+            }) => {
+                patterns.push(("include", include));
+                patterns.push(("include_removed", include_removed));
+                let potential_waste = potential.map(
+                    |PotentialWaste {
+                         patterns_to_fix,
+                         potential_waste,
+                     }| {
+                        patterns.push(("potential_include", patterns_to_fix));
+                        potential_waste
+                    },
+                );
+                (Some("ImprovedInclude"), Some(has_build_script), potential_waste)
+            }
+            Some(Fix::EnrichedExclude {
+                exclude,
+                exclude_added,
+                has_build_script,
+            }) => {
+                patterns.push(("exclude", exclude));
+                patterns.push(("exclude_added", exclude_added));
+                (Some("EnrichedExclude"), Some(has_build_script), None)
+            }
+            Some(Fix::NewInclude {
+                include,
+                has_build_script,
+            }) => {
+                patterns.push(("include", include));
+                (Some("NewInclude"), Some(has_build_script), None)
+            }
+            Some(Fix::RemoveExcludeAndUseInclude {
+                include_added,
+                include,
+                include_removed,
+            }) => {
+                patterns.push(("include_added", include_added));
+                patterns.push(("include", include));
+                patterns.push(("include_removed", include_removed));
+                (Some("RemoveExcludeAndUseInclude"), None, None)
+            }
+            Some(Fix::RemoveExclude) => (Some("RemoveExclude"), None, None),
+        };
+
+        let mut count = self.version.execute(params![
+            id,
+            crate_name,
+            crate_version,
+            total_size_in_bytes as i64,
+            total_files as i64,
+            wasted_files.iter().map(|(_, size)| size).sum::<u64>() as i64,
+            wasted_files.len() as i64,
+            potential_waste
+                .as_ref()
+                .map(|files| files.iter().map(|e| e.size).sum::<u64>() as i64),
+            potential_waste.as_ref().map(|files| files.len() as i64),
+            fix_kind,
+            has_build_script,
+        ])?;
+        for (path, size) in wasted_files {
+            count += self.file.execute(params![id, path, size as i64])?;
+        }
+        for (kind, patterns) in patterns {
+            for pattern in patterns {
+                count += self.pattern.execute(params![id, kind, pattern])?;
+            }
+        }
+        let latest_version: Option<String> = self
+            .latest_version
+            .query_row(params![crate_name], |r| r.get(0))
+            .optional()?;
+        if latest_version.is_none_or(|latest| is_later_version(crate_version, &latest)) {
+            count += self.set_latest_version.execute(params![crate_name, crate_version])?;
+        }
+        Ok(count)
+    }
+}
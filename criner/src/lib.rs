@@ -13,9 +13,13 @@ pub mod migration;
 pub mod error;
 pub use error::{Error, Result};
 
+pub mod assets;
+pub mod crate_report;
 pub mod export;
 pub(crate) mod model;
 pub(crate) mod persistence;
+pub mod waste_check;
+pub use persistence::{convert_storage_blocking, StorageKind};
 pub(crate) mod utils;
 
 mod spawn;
@@ -25,4 +29,7 @@ mod engine;
 
 pub use engine::run;
 
+#[cfg(test)]
+mod test_utils;
+
 pub use prodash;
@@ -282,9 +282,10 @@ pub enum TaskResult {
     ExplodedCrate {
         /// Meta data of all entries in the crate
         entries_meta_data: Vec<TarHeader>,
-        /// The actual content of selected files, Cargo.*, build.rs and lib/main
-        /// IMPORTANT: This file may be partial and limited in size unless it is Cargo.toml, which
-        /// is always complete.
+        /// The actual content of selected files, Cargo.*, build.rs and lib/main, along with an outline of all other
+        /// Rust sources with only the lines declaring modules or including files.
+        /// IMPORTANT: This file may be partial and limited in size unless it is Cargo.toml or Cargo.toml.orig, which
+        /// are always complete.
         /// Note that these are also present in entries_meta_data.
         selected_entries: Vec<(TarHeader, Vec<u8>)>,
     },
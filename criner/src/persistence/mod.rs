@@ -1,23 +1,59 @@
-use crate::Result;
-use std::path::{Path, PathBuf};
+use crate::{Error, Result};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 mod keyed;
 mod merge;
 pub use keyed::*;
 
 mod serde;
+mod storage;
 mod table;
+pub use storage::*;
 pub use table::*;
 
+pub(crate) const SLED_DIR_NAME: &str = "db.msgpack.sled";
+const SQLITE_FILE_NAME: &str = "db.msgpack.sqlite";
+/// The tables which are written most often, and which may be kept in a storage other than SQLite.
+const HOT_TABLES: &[&str] = &["task", "result"];
+
 #[derive(Clone)]
 pub struct Db {
     sqlite_path: PathBuf,
+    kv: Option<sled::Db>,
 }
 
 impl Db {
+    /// Open the database at `path` with the storage it was created with, or with SQLite if it is new.
     pub fn open(path: impl AsRef<Path>) -> Result<Db> {
+        let storage = if path.as_ref().join(SLED_DIR_NAME).is_dir() {
+            StorageKind::Sled
+        } else {
+            StorageKind::Sqlite
+        };
+        Self::open_with_storage(path, storage)
+    }
+
+    /// Open the database at `path` like [`open()`][Db::open()], but fail if there is none yet.
+    pub fn open_existing(path: impl AsRef<Path>) -> Result<Db> {
+        if !path.as_ref().join(SQLITE_FILE_NAME).is_file() {
+            return Err(Error::Message(format!(
+                "There is no database at '{}'",
+                path.as_ref().display()
+            )));
+        }
+        Self::open(path)
+    }
+
+    /// Open the database at `path`, keeping the `task` and `result` tables in the given `storage`.
+    ///
+    /// Fails if the database already contains these tables in a different storage, which requires
+    /// a conversion with [`convert_storage_blocking()`] first.
+    pub fn open_with_storage(path: impl AsRef<Path>, storage: StorageKind) -> Result<Db> {
         std::fs::create_dir_all(&path)?;
-        let sqlite_path = path.as_ref().join("db.msgpack.sqlite");
+        let sqlite_path = path.as_ref().join(SQLITE_FILE_NAME);
         {
             let mut connection = rusqlite::Connection::open(&sqlite_path)?;
             connection.execute_batch("
@@ -45,7 +81,51 @@ impl Db {
             transaction.commit()?;
         }
 
-        Ok(Db { sqlite_path })
+        let sled_path = path.as_ref().join(SLED_DIR_NAME);
+        let kv = match storage {
+            StorageKind::Sqlite => {
+                if sled_path.is_dir() {
+                    return Err(Error::Message(format!(
+                        "The database at '{}' keeps its tasks and results in sled - convert it to sqlite first",
+                        path.as_ref().display()
+                    )));
+                }
+                None
+            }
+            StorageKind::Sled => {
+                if !sled_path.is_dir() && sqlite_has_hot_table_rows(&sqlite_path)? {
+                    return Err(Error::Message(format!(
+                        "The database at '{}' keeps its tasks and results in sqlite - convert it to sled first",
+                        path.as_ref().display()
+                    )));
+                }
+                Some(sled::open(sled_path)?)
+            }
+        };
+
+        Ok(Db { sqlite_path, kv })
+    }
+
+    pub fn storage_kind(&self) -> StorageKind {
+        match self.kv {
+            Some(_) => StorageKind::Sled,
+            None => StorageKind::Sqlite,
+        }
+    }
+
+    fn open_hot_storage(&self) -> Result<Arc<dyn Storage>> {
+        Ok(match &self.kv {
+            Some(db) => Arc::new(SledStorage { db: db.clone() }),
+            None => Arc::new(SqliteStorage {
+                connection: self.open_connection()?,
+            }),
+        })
+    }
+
+    fn open_sqlite_storage(&self) -> Result<SqliteStorage> {
+        Ok(SqliteStorage {
+            connection: self.open_connection()?,
+        })
     }
 
     pub fn open_connection(&self) -> Result<ThreadSafeConnection> {
@@ -68,26 +148,31 @@ impl Db {
 
     pub fn open_crate_versions(&self) -> Result<CrateVersionTable> {
         Ok(CrateVersionTable {
-            inner: self.open_connection()?,
+            inner: self.open_sqlite_storage()?,
         })
     }
     pub fn open_crates(&self) -> Result<CrateTable> {
         Ok(CrateTable {
-            inner: self.open_connection()?,
+            inner: self.open_sqlite_storage()?,
         })
     }
     pub fn open_tasks(&self) -> Result<TaskTable> {
         Ok(TaskTable {
-            inner: self.open_connection()?,
+            inner: self.open_hot_storage()?,
         })
     }
     pub fn open_results(&self) -> Result<TaskResultTable> {
         Ok(TaskResultTable {
-            inner: self.open_connection()?,
+            inner: self.open_hot_storage()?,
         })
     }
     pub fn open_context(&self) -> Result<MetaTable> {
         Ok(MetaTable {
+            inner: self.open_sqlite_storage()?,
+        })
+    }
+    pub fn open_crates_io_crates(&self) -> Result<CratesIoCrateTable> {
+        Ok(CratesIoCrateTable {
             inner: self.open_connection()?,
         })
     }
@@ -98,8 +183,97 @@ impl Db {
     }
 }
 
+fn sqlite_has_hot_table_rows(sqlite_path: &Path) -> Result<bool> {
+    let connection = rusqlite::Connection::open(sqlite_path)?;
+    for table in HOT_TABLES {
+        if connection.query_row(&format!("SELECT EXISTS(SELECT 1 FROM {})", table), [], |r| {
+            r.get::<_, bool>(0)
+        })? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Move the `task` and `result` tables of the database at `path` into the storage of the given kind,
+/// returning the amount of moved items.
+pub fn convert_storage_blocking(path: impl AsRef<Path>, to: StorageKind) -> Result<u64> {
+    let db = Db::open(&path)?;
+    if db.storage_kind() == to {
+        return Err(Error::Message(format!(
+            "The database at '{}' already uses {:?} storage",
+            path.as_ref().display(),
+            to
+        )));
+    }
+    let source = db.open_hot_storage()?;
+    let sled_path = path.as_ref().join(SLED_DIR_NAME);
+    let mut count = 0;
+    match to {
+        StorageKind::Sled => {
+            // Only let the sled database appear once it is complete, as its existence selects the storage
+            let tmp_sled_path = sled_path.with_extension("sled.tmp");
+            if tmp_sled_path.is_dir() {
+                std::fs::remove_dir_all(&tmp_sled_path)?;
+            }
+            {
+                let kv = sled::open(&tmp_sled_path)?;
+                for table in HOT_TABLES {
+                    let tree = kv.open_tree(table)?;
+                    source.for_each(table, &mut |key, value| {
+                        tree.insert(key, value)?;
+                        count += 1;
+                        Ok(())
+                    })?;
+                }
+                kv.flush()?;
+            }
+            // The rows are only deleted if the sled database could be moved into place, and vice versa
+            let mut connection = db.open_connection_no_async_with_busy_wait()?;
+            let transaction = connection.transaction()?;
+            for table in HOT_TABLES {
+                transaction.execute_batch(&format!("DELETE FROM {}", table))?;
+            }
+            std::fs::rename(tmp_sled_path, &sled_path)?;
+            if let Err(err) = transaction.commit() {
+                std::fs::remove_dir_all(sled_path)?;
+                return Err(err.into());
+            }
+        }
+        StorageKind::Sqlite => {
+            let mut connection = db.open_connection_no_async_with_busy_wait()?;
+            let transaction = connection.transaction()?;
+            for table in HOT_TABLES {
+                let mut insert = new_key_value_insertion(table, &transaction)?;
+                source.for_each(table, &mut |key, value| {
+                    insert.execute(rusqlite::params![key, value])?;
+                    count += 1;
+                    Ok(())
+                })?;
+            }
+            drop((source, db));
+            // Move the sled database out of the way before committing, as its existence selects the storage,
+            // and put it back if the rows couldn't be committed.
+            let old_sled_path = sled_path.with_extension("sled.old");
+            if old_sled_path.is_dir() {
+                std::fs::remove_dir_all(&old_sled_path)?;
+            }
+            std::fs::rename(&sled_path, &old_sled_path)?;
+            if let Err(err) = transaction.commit() {
+                std::fs::rename(old_sled_path, sled_path)?;
+                return Err(err.into());
+            }
+            std::fs::remove_dir_all(old_sled_path)?;
+        }
+    }
+    Ok(count)
+}
+
 fn sleeper(attempts: i32) -> bool {
     log::warn!("SQLITE_BUSY, retrying after 50ms (attempt {})", attempts);
     std::thread::sleep(std::time::Duration::from_millis(50));
     true
 }
+
+#[cfg(test)]
+mod persistence_test;
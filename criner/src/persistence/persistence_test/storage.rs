@@ -0,0 +1,177 @@
+use crate::persistence::{convert_storage_blocking, glob_matches, Db, Storage, StorageKind, SLED_DIR_NAME};
+use std::sync::Arc;
+
+const KEYS: &[&str] = &["a/1.0.0", "a/1.1.0", "ab/0.1.0", "b/2.0.0", "B/2.0.0", "c:x/1.0.0"];
+const PATTERNS: &[&str] = &[
+    "a*", "a/*", "*", "?/*", "[ab]/*", "[^a]/*", "[a-b]*", "[]]*", "*/1.?.0", "b*", "B*", "c:x*", "[a",
+];
+
+fn fill(storage: &dyn Storage) -> crate::Result<()> {
+    for key in KEYS {
+        storage.insert(None, "result", key, key.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn storages(dir: &std::path::Path) -> crate::Result<(Arc<dyn Storage>, Arc<dyn Storage>)> {
+    let sqlite = Db::open_with_storage(dir.join("sqlite"), StorageKind::Sqlite)?.open_hot_storage()?;
+    let sled = Db::open_with_storage(dir.join("sled"), StorageKind::Sled)?.open_hot_storage()?;
+    Ok((sqlite, sled))
+}
+
+#[test]
+fn values_round_trip_through_all_storages() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let (sqlite, sled) = storages(dir.path())?;
+    for storage in [sqlite, sled] {
+        fill(&*storage)?;
+        assert_eq!(storage.get("result", "a/1.0.0")?, Some(b"a/1.0.0".to_vec()));
+        assert_eq!(storage.get("result", "missing")?, None);
+        assert_eq!(storage.get("task", "a/1.0.0")?, None, "tables are separate");
+
+        let updated = storage.update(None, "result", "a/1.0.0", &mut |existing| {
+            let mut value = existing.expect("existing value").to_vec();
+            value.extend_from_slice(b"+");
+            Ok(value)
+        })?;
+        assert_eq!(updated, b"a/1.0.0+");
+        assert_eq!(storage.get("result", "a/1.0.0")?, Some(updated));
+        storage.update(None, "result", "new", &mut |existing| {
+            assert!(existing.is_none());
+            Ok(b"new".to_vec())
+        })?;
+
+        let mut items = Vec::new();
+        storage.for_each("result", &mut |key, value| {
+            items.push((key.to_owned(), value.to_vec()));
+            Ok(())
+        })?;
+        items.sort();
+        assert_eq!(items.len(), KEYS.len() + 1);
+        assert_eq!(items[0], ("B/2.0.0".into(), b"B/2.0.0".to_vec()));
+    }
+    Ok(())
+}
+
+#[test]
+fn counting_with_globs_matches_sqlite_in_all_storages() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let (sqlite, sled) = storages(dir.path())?;
+    fill(&*sqlite)?;
+    fill(&*sled)?;
+
+    assert_eq!(sqlite.count("result", None), KEYS.len() as u64);
+    assert_eq!(sled.count("result", None), KEYS.len() as u64);
+    assert_eq!(sled.count("task", None), 0);
+    for pattern in PATTERNS {
+        assert_eq!(
+            sled.count("result", Some(pattern)),
+            sqlite.count("result", Some(pattern)),
+            "{}",
+            pattern
+        );
+    }
+    assert_eq!(sled.count("result", Some("a*")), 3, "'*' matches '/' as well");
+    assert_eq!(
+        sled.count("result", Some("[^a]/*")),
+        2,
+        "'^' negates sets and globs are case-sensitive"
+    );
+    assert_eq!(sled.count("result", Some("[a")), 0, "unclosed sets never match");
+    Ok(())
+}
+
+#[test]
+fn globs_have_sqlite_semantics() {
+    assert!(glob_matches("a*", "a/b/c"));
+    assert!(glob_matches("a?c", "abc"));
+    assert!(!glob_matches("a?c", "ac"));
+    assert!(glob_matches("[]a]", "]"));
+    assert!(glob_matches("[a-]", "-"));
+    assert!(glob_matches("[^a-c]x", "dx"));
+    assert!(!glob_matches("[^a-c]x", "bx"));
+    assert!(!glob_matches("[!a]", "b"), "unlike unix globs, '!' doesn't negate sets");
+    assert!(!glob_matches("A", "a"));
+    assert!(glob_matches("**", ""));
+    assert!(glob_matches("*a*b", "xaxxb"));
+    assert!(!glob_matches("*a", "ab"));
+    assert!(glob_matches("a*[0-9]", "abc1"));
+}
+
+#[test]
+fn globs_with_many_stars_match_quickly() {
+    let text = "a".repeat(10_000);
+    let pattern = format!("{}b", "a*".repeat(30));
+    let start = std::time::Instant::now();
+    assert!(!glob_matches(&pattern, &text));
+    assert!(glob_matches(&format!("{}a", "a*".repeat(30)), &text));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[test]
+fn iterating_items_can_access_the_same_storage() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let (sqlite, sled) = storages(dir.path())?;
+    for storage in [sqlite, sled] {
+        fill(&*storage)?;
+        let mut visited = 0;
+        storage.for_each("result", &mut |key, value| {
+            visited += 1;
+            assert_eq!(storage.get("result", key)?.as_deref(), Some(value));
+            storage.insert(None, "result", key, b"updated")?;
+            Ok(())
+        })?;
+        assert_eq!(visited, KEYS.len(), "updated items are not visited again");
+        assert_eq!(storage.get("result", "a/1.0.0")?, Some(b"updated".to_vec()));
+    }
+    Ok(())
+}
+
+#[test]
+fn conversion_moves_all_items_between_storages() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("db");
+    fill(&*Db::open(&db_path)?.open_hot_storage()?)?;
+
+    assert_eq!(
+        convert_storage_blocking(&db_path, StorageKind::Sled)?,
+        KEYS.len() as u64
+    );
+    let db = Db::open(&db_path)?;
+    assert_eq!(db.storage_kind(), StorageKind::Sled);
+    assert_eq!(db.open_hot_storage()?.count("result", None), KEYS.len() as u64);
+    assert_eq!(
+        db.open_sqlite_storage()?.count("result", None),
+        0,
+        "sqlite rows are removed"
+    );
+    assert!(
+        Db::open_with_storage(&db_path, StorageKind::Sqlite).is_err(),
+        "the storage can't be switched without conversion"
+    );
+    drop(db);
+
+    assert_eq!(
+        convert_storage_blocking(&db_path, StorageKind::Sqlite)?,
+        KEYS.len() as u64
+    );
+    let db = Db::open(&db_path)?;
+    assert_eq!(db.storage_kind(), StorageKind::Sqlite);
+    assert_eq!(
+        db.open_hot_storage()?.get("result", "b/2.0.0")?,
+        Some(b"b/2.0.0".to_vec())
+    );
+    assert_eq!(db.open_hot_storage()?.count("result", None), KEYS.len() as u64);
+    let mut leftovers = std::fs::read_dir(&db_path)?
+        .map(|e| e.map(|e| e.file_name().to_string_lossy().into_owned()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    leftovers.retain(|name| name.starts_with(SLED_DIR_NAME));
+    assert_eq!(
+        leftovers,
+        Vec::<String>::new(),
+        "no sled database or temporary copy remains"
+    );
+
+    assert!(convert_storage_blocking(&db_path, StorageKind::Sqlite).is_err());
+    Ok(())
+}
@@ -0,0 +1,342 @@
+use crate::{persistence::ThreadSafeConnection, Result};
+use rusqlite::{params, OptionalExtension};
+use std::time::{Duration, SystemTime};
+
+/// The kind of storage to use for the frequently written `task` and `result` tables.
+///
+/// All other tables are always kept in SQLite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    /// Keep everything in a single SQLite database, the default
+    Sqlite,
+    /// Use an embedded key-value store for the `task` and `result` tables, which scales better with many concurrent writers.
+    Sled,
+}
+
+impl std::str::FromStr for StorageKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "sqlite" => StorageKind::Sqlite,
+            "sled" => StorageKind::Sled,
+            _ => return Err(format!("Unknown storage '{}', expected 'sqlite' or 'sled'", s)),
+        })
+    }
+}
+
+/// A function to compute a new value from the possibly existing one
+pub type UpdateFn<'a> = dyn FnMut(Option<&[u8]>) -> Result<Vec<u8>> + 'a;
+
+/// Operations on tables of keys and msgpack encoded values, as used by all implementors of [`TableAccess`][super::TableAccess].
+pub trait Storage: Send + Sync {
+    fn get(&self, table: &str, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Store `value` at `key`, replacing any existing value.
+    fn insert(&self, progress: Option<&mut prodash::tree::Item>, table: &str, key: &str, value: &[u8]) -> Result<()>;
+
+    /// Atomically replace the value at `key` with the result of `f(existing_value)`, returning the new value.
+    /// `f` may be called multiple times.
+    fn update(
+        &self,
+        progress: Option<&mut prodash::tree::Item>,
+        table: &str,
+        key: &str,
+        f: &mut UpdateFn<'_>,
+    ) -> Result<Vec<u8>>;
+
+    /// Count all keys in `table`, or only the ones matching the unix-style `glob`.
+    fn count(&self, table: &str, glob: Option<&str>) -> u64;
+
+    /// Call `f` with each key and value in `table`. `f` may access this storage, but items it inserts while iterating
+    /// may or may not be visited.
+    fn for_each(&self, table: &str, f: &mut dyn FnMut(&str, &[u8]) -> Result<()>) -> Result<()>;
+}
+
+#[derive(Clone)]
+pub struct SqliteStorage {
+    pub(crate) connection: ThreadSafeConnection,
+}
+
+impl Storage for SqliteStorage {
+    fn get(&self, table: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .connection
+            .lock()
+            .query_row(&format!("SELECT data FROM {} WHERE key = '{}'", table, key), [], |r| {
+                r.get::<_, Vec<u8>>(0)
+            })
+            .optional()?)
+    }
+
+    fn insert(&self, progress: Option<&mut prodash::tree::Item>, table: &str, key: &str, value: &[u8]) -> Result<()> {
+        retry_on_db_busy(progress, || {
+            self.connection.lock().execute(
+                &format!("REPLACE INTO {} (key, data) VALUES (?1, ?2)", table),
+                params![key, value],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn update(
+        &self,
+        progress: Option<&mut prodash::tree::Item>,
+        table: &str,
+        key: &str,
+        f: &mut UpdateFn<'_>,
+    ) -> Result<Vec<u8>> {
+        retry_on_db_busy(progress, || {
+            let mut guard = self.connection.lock();
+            let transaction = guard.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+            let existing = transaction
+                .query_row(&format!("SELECT data FROM {} WHERE key = '{}'", table, key), [], |r| {
+                    r.get::<_, Vec<u8>>(0)
+                })
+                .optional()?;
+            let new_value = f(existing.as_deref())?;
+            transaction.execute(
+                &format!("REPLACE INTO {} (key, data) VALUES (?1, ?2)", table),
+                params![key, new_value],
+            )?;
+            transaction.commit()?;
+            Ok(new_value)
+        })
+    }
+
+    fn count(&self, table: &str, glob: Option<&str>) -> u64 {
+        self.connection
+            .lock()
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM {} {}",
+                    table,
+                    match glob {
+                        Some(glob) => format!("where key glob \"{}\"", glob),
+                        None => "".into(),
+                    }
+                ),
+                [],
+                |r| r.get::<_, i64>(0),
+            )
+            .unwrap_or(0) as u64
+    }
+
+    fn for_each(&self, table: &str, f: &mut dyn FnMut(&str, &[u8]) -> Result<()>) -> Result<()> {
+        // Read in batches ordered by key and release the connection before calling `f`, which may use this storage too.
+        const BATCH_SIZE: usize = 1000;
+        let mut last_key: Option<String> = None;
+        loop {
+            let batch = {
+                let guard = self.connection.lock();
+                let mut statement = guard.prepare(&format!(
+                    "SELECT key, data FROM {} {} ORDER BY key LIMIT {}",
+                    table,
+                    if last_key.is_some() { "WHERE key > ?1" } else { "" },
+                    BATCH_SIZE
+                ))?;
+                let row = |r: &rusqlite::Row<'_>| Ok((r.get::<_, String>(0)?, r.get::<_, Vec<u8>>(1)?));
+                match &last_key {
+                    Some(key) => statement
+                        .query_map(params![key], row)?
+                        .collect::<rusqlite::Result<Vec<_>>>()?,
+                    None => statement.query_map([], row)?.collect::<rusqlite::Result<Vec<_>>>()?,
+                }
+            };
+            for (key, value) in &batch {
+                f(key, value)?;
+            }
+            if batch.len() < BATCH_SIZE {
+                return Ok(());
+            }
+            last_key = batch.into_iter().next_back().map(|(key, _)| key);
+        }
+    }
+}
+
+/// Each table is a tree in the `sled` database.
+#[derive(Clone)]
+pub struct SledStorage {
+    pub(crate) db: sled::Db,
+}
+
+impl Storage for SledStorage {
+    fn get(&self, table: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.db.open_tree(table)?.get(key)?.map(|v| v.to_vec()))
+    }
+
+    fn insert(&self, _progress: Option<&mut prodash::tree::Item>, table: &str, key: &str, value: &[u8]) -> Result<()> {
+        self.db.open_tree(table)?.insert(key, value)?;
+        Ok(())
+    }
+
+    fn update(
+        &self,
+        _progress: Option<&mut prodash::tree::Item>,
+        table: &str,
+        key: &str,
+        f: &mut UpdateFn<'_>,
+    ) -> Result<Vec<u8>> {
+        let tree = self.db.open_tree(table)?;
+        loop {
+            let existing = tree.get(key)?;
+            let new_value = f(existing.as_deref())?;
+            if tree
+                .compare_and_swap(key, existing, Some(new_value.as_slice()))?
+                .is_ok()
+            {
+                return Ok(new_value);
+            }
+        }
+    }
+
+    fn count(&self, table: &str, glob: Option<&str>) -> u64 {
+        let tree = match self.db.open_tree(table) {
+            Ok(tree) => tree,
+            Err(_) => return 0,
+        };
+        match glob {
+            Some(glob) => tree
+                .iter()
+                .keys()
+                .filter_map(std::result::Result::ok)
+                .filter(|key| std::str::from_utf8(key).is_ok_and(|key| glob_matches(glob, key)))
+                .count() as u64,
+            None => tree.len() as u64,
+        }
+    }
+
+    fn for_each(&self, table: &str, f: &mut dyn FnMut(&str, &[u8]) -> Result<()>) -> Result<()> {
+        for res in self.db.open_tree(table)?.iter() {
+            let (key, value) = res?;
+            f(
+                std::str::from_utf8(&key).map_err(|_| crate::Error::Bug("keys are always utf8"))?,
+                &value,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Return true if `text` matches `pattern` with the semantics of SQLite's `GLOB` operator, which is case-sensitive
+/// and where `*` matches any sequence of characters including `/`, `?` matches a single character and `[...]` matches
+/// any character in the set, or any character not in it if it starts with `^`.
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.chars().collect::<Vec<_>>(), text.chars().collect::<Vec<_>>());
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*` if what follows it fails to match: the pattern right after the star,
+    // and the text position the star currently extends to.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, t));
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            Some('[') => match set_matches(&pattern[p + 1..], Some(text[t])) {
+                Some((true, rest)) => {
+                    p = pattern.len() - rest.len();
+                    t += 1;
+                    continue;
+                }
+                Some((false, _)) => {}
+                // Every match has to get past the unclosed set, so nothing can match
+                None => return false,
+            },
+            Some(c) if *c == text[t] => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            _ => {}
+        }
+        match backtrack {
+            Some((star_p, star_t)) => {
+                backtrack = Some((star_p, star_t + 1));
+                p = star_p;
+                t = star_t + 1;
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Match `c` against the set at the beginning of `pattern`, right after its opening `[`, and return whether it matched
+/// along with the remainder of the pattern, or `None` if the set isn't closed.
+fn set_matches(pattern: &[char], c: Option<char>) -> Option<(bool, &[char])> {
+    let (invert, mut pattern) = match pattern.split_first() {
+        Some(('^', rest)) => (true, rest),
+        _ => (false, pattern),
+    };
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        match pattern {
+            [] => return None,
+            [']', rest @ ..] if !first => return Some((matched != invert, rest)),
+            [start, '-', end, rest @ ..] if *end != ']' => {
+                matched |= c.is_some_and(|c| *start <= c && c <= *end);
+                pattern = rest;
+            }
+            [literal, rest @ ..] => {
+                matched |= c == Some(*literal);
+                pattern = rest;
+            }
+        }
+        first = false;
+    }
+}
+
+pub(crate) fn retry_on_db_busy<T>(
+    mut progress: Option<&mut prodash::tree::Item>,
+    mut f: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    use crate::Error;
+    use rusqlite::ffi::Error as SqliteFFIError;
+    use rusqlite::ffi::ErrorCode as SqliteFFIErrorCode;
+    use rusqlite::Error as SqliteError;
+    use std::ops::Add;
+
+    let max_wait_ms = Duration::from_secs(100);
+    let mut total_wait_time = Duration::default();
+    let mut wait_for = Duration::from_millis(1);
+    loop {
+        match f() {
+            Ok(v) => return Ok(v),
+            Err(
+                err @ Error::Rusqlite(SqliteError::SqliteFailure(
+                    SqliteFFIError {
+                        code: SqliteFFIErrorCode::DatabaseBusy,
+                        ..
+                    },
+                    _,
+                )),
+            ) => {
+                if total_wait_time >= max_wait_ms {
+                    log::warn!("Giving up to wait for {:?} after {:?})", err, total_wait_time);
+                    return Err(err);
+                }
+                log::warn!(
+                    "Waiting {:?} for {:?} (total wait time {:?})",
+                    wait_for,
+                    err,
+                    total_wait_time
+                );
+                if let Some(p) = progress.as_mut() {
+                    p.blocked("wait for write lock", Some(SystemTime::now().add(wait_for)));
+                };
+                std::thread::sleep(wait_for);
+                total_wait_time += wait_for;
+                wait_for *= 2;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
@@ -1,12 +1,12 @@
 use crate::persistence::KEY_SEP_CHAR;
 use crate::{
-    model::{Context, Crate, TaskResult},
+    model::{db_dump, Context, Crate, TaskResult},
     model::{CrateVersion, Task},
-    persistence::{merge::Merge, Keyed},
+    persistence::{merge::Merge, Keyed, SqliteStorage, Storage},
     Result,
 };
-use rusqlite::{params, OptionalExtension};
-use std::time::{Duration, SystemTime};
+use rusqlite::OptionalExtension;
+use std::{sync::Arc, time::SystemTime};
 
 /// Required as we send futures to threads. The type system can't statically prove that in fact
 /// these connections will only ever be created while already in the thread they should execute on.
@@ -96,47 +96,25 @@ pub trait TableAccess {
     type StorageItem: serde::Serialize + for<'a> From<&'a [u8]> + Default + From<Self::InsertItem>;
     type InsertItem: Clone;
 
-    fn connection(&self) -> &ThreadSafeConnection;
+    fn storage(&self) -> &dyn Storage;
     fn table_name() -> &'static str;
 
     fn merge(new_item: &Self::InsertItem, _existing_item: Option<Self::StorageItem>) -> Self::StorageItem {
         Self::StorageItem::from(new_item.clone())
     }
 
-    fn into_connection(self) -> ThreadSafeConnection;
-
     fn count(&self) -> u64 {
         self.count_filtered(None)
     }
 
     fn count_filtered(&self, glob: Option<&str>) -> u64 {
-        self.connection()
-            .lock()
-            .query_row(
-                &format!(
-                    "SELECT COUNT(*) FROM {} {}",
-                    Self::table_name(),
-                    match glob {
-                        Some(glob) => format!("where key glob \"{}\"", glob),
-                        None => "".into(),
-                    }
-                ),
-                [],
-                |r| r.get::<_, i64>(0),
-            )
-            .unwrap_or(0) as u64
+        self.storage().count(Self::table_name(), glob)
     }
 
     fn get(&self, key: impl AsRef<str>) -> Result<Option<Self::StorageItem>> {
         Ok(self
-            .connection()
-            .lock()
-            .query_row(
-                &format!("SELECT data FROM {} WHERE key = '{}'", Self::table_name(), key.as_ref()),
-                [],
-                |r| r.get::<_, Vec<u8>>(0),
-            )
-            .optional()?
+            .storage()
+            .get(Self::table_name(), key.as_ref())?
             .map(|d| Self::StorageItem::from(d.as_slice())))
     }
 
@@ -148,25 +126,15 @@ pub trait TableAccess {
         key: impl AsRef<str>,
         f: impl Fn(Self::StorageItem) -> Self::StorageItem,
     ) -> Result<Self::StorageItem> {
-        retry_on_db_busy(progress, || {
-            let mut guard = self.connection().lock();
-            let transaction = guard.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
-            let new_value = transaction
-                .query_row(
-                    &format!("SELECT data FROM {} WHERE key = '{}'", Self::table_name(), key.as_ref()),
-                    [],
-                    |r| r.get::<_, Vec<u8>>(0),
-                )
-                .optional()?
-                .map_or_else(|| f(Self::StorageItem::default()), |d| f(d.as_slice().into()));
-            transaction.execute(
-                &format!("REPLACE INTO {} (key, data) VALUES (?1, ?2)", Self::table_name()),
-                params![key.as_ref(), rmp_serde::to_vec(&new_value)?],
-            )?;
-            transaction.commit()?;
-
-            Ok(new_value)
-        })
+        let mut new_value = None;
+        self.storage()
+            .update(progress, Self::table_name(), key.as_ref(), &mut |existing| {
+                let value = existing.map_or_else(|| f(Self::StorageItem::default()), |d| f(d.into()));
+                let data = rmp_serde::to_vec(&value)?;
+                new_value = Some(value);
+                Ok(data)
+            })?;
+        new_value.ok_or(crate::Error::Bug("update function must have been called"))
     }
 
     /// Similar to 'update', but provides full control over the default and allows deletion
@@ -176,94 +144,37 @@ pub trait TableAccess {
         key: impl AsRef<str>,
         item: &Self::InsertItem,
     ) -> Result<Self::StorageItem> {
-        retry_on_db_busy(Some(progress), || {
-            let mut guard = self.connection().lock();
-            let transaction = guard.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
-
-            let new_value = {
-                let maybe_vec = transaction
-                    .query_row(
-                        &format!("SELECT data FROM {} WHERE key = '{}'", Self::table_name(), key.as_ref()),
-                        [],
-                        |r| r.get::<_, Vec<u8>>(0),
-                    )
-                    .optional()?;
-                Self::merge(item, maybe_vec.map(|v| v.as_slice().into()))
-            };
-            transaction.execute(
-                &format!("REPLACE INTO {} (key, data) VALUES (?1, ?2)", Self::table_name()),
-                params![key.as_ref(), rmp_serde::to_vec(&new_value)?],
-            )?;
-            transaction.commit()?;
-            Ok(new_value)
-        })
+        let mut new_value = None;
+        self.storage()
+            .update(Some(progress), Self::table_name(), key.as_ref(), &mut |existing| {
+                let value = Self::merge(item, existing.map(Into::into));
+                let data = rmp_serde::to_vec(&value)?;
+                new_value = Some(value);
+                Ok(data)
+            })?;
+        new_value.ok_or(crate::Error::Bug("upsert function must have been called"))
     }
 
     fn insert(&self, progress: &mut prodash::tree::Item, key: impl AsRef<str>, v: &Self::InsertItem) -> Result<()> {
-        retry_on_db_busy(Some(progress), || {
-            self.connection().lock().execute(
-                &format!("REPLACE INTO {} (key, data) VALUES (?1, ?2)", Self::table_name()),
-                params![key.as_ref(), rmp_serde::to_vec(&Self::merge(v, None))?],
-            )?;
-            Ok(())
-        })
-    }
-}
-
-fn retry_on_db_busy<T>(mut progress: Option<&mut prodash::tree::Item>, mut f: impl FnMut() -> Result<T>) -> Result<T> {
-    use crate::Error;
-    use rusqlite::ffi::Error as SqliteFFIError;
-    use rusqlite::ffi::ErrorCode as SqliteFFIErrorCode;
-    use rusqlite::Error as SqliteError;
-    use std::ops::Add;
-
-    let max_wait_ms = Duration::from_secs(100);
-    let mut total_wait_time = Duration::default();
-    let mut wait_for = Duration::from_millis(1);
-    loop {
-        match f() {
-            Ok(v) => return Ok(v),
-            Err(
-                err @ Error::Rusqlite(SqliteError::SqliteFailure(
-                    SqliteFFIError {
-                        code: SqliteFFIErrorCode::DatabaseBusy,
-                        ..
-                    },
-                    _,
-                )),
-            ) => {
-                if total_wait_time >= max_wait_ms {
-                    log::warn!("Giving up to wait for {:?} after {:?})", err, total_wait_time);
-                    return Err(err);
-                }
-                log::warn!(
-                    "Waiting {:?} for {:?} (total wait time {:?})",
-                    wait_for,
-                    err,
-                    total_wait_time
-                );
-                if let Some(p) = progress.as_mut() {
-                    p.blocked("wait for write lock", Some(SystemTime::now().add(wait_for)));
-                };
-                std::thread::sleep(wait_for);
-                total_wait_time += wait_for;
-                wait_for *= 2;
-            }
-            Err(err) => return Err(err),
-        }
+        self.storage().insert(
+            Some(progress),
+            Self::table_name(),
+            key.as_ref(),
+            &rmp_serde::to_vec(&Self::merge(v, None))?,
+        )
     }
 }
 
 pub struct TaskTable {
-    pub(crate) inner: ThreadSafeConnection,
+    pub(crate) inner: Arc<dyn Storage>,
 }
 
 impl TableAccess for TaskTable {
     type StorageItem = Task;
     type InsertItem = Task;
 
-    fn connection(&self) -> &ThreadSafeConnection {
-        &self.inner
+    fn storage(&self) -> &dyn Storage {
+        self.inner.as_ref()
     }
     fn table_name() -> &'static str {
         "task"
@@ -274,9 +185,29 @@ impl TableAccess for TaskTable {
             ..existing_task.map_or_else(|| new_task.clone(), |existing_task| existing_task.merge(new_task))
         }
     }
+}
 
-    fn into_connection(self) -> ThreadSafeConnection {
-        self.inner
+/// Crates as stored from the crates.io database dump, keyed by their name.
+pub struct CratesIoCrateTable {
+    pub(crate) inner: ThreadSafeConnection,
+}
+
+impl CratesIoCrateTable {
+    pub fn table_name() -> &'static str {
+        "crates.io-crate"
+    }
+
+    pub fn get(&self, crate_name: &str) -> Result<Option<db_dump::Crate>> {
+        Ok(self
+            .inner
+            .lock()
+            .query_row(
+                &format!("SELECT data FROM '{}' WHERE key = ?1", Self::table_name()),
+                [crate_name],
+                |r| r.get::<_, Vec<u8>>(0),
+            )
+            .optional()?
+            .map(|data| db_dump::Crate::from(data.as_slice())))
     }
 }
 
@@ -316,33 +247,30 @@ impl ReportsTree {
 }
 
 pub struct TaskResultTable {
-    pub(crate) inner: ThreadSafeConnection,
+    pub(crate) inner: Arc<dyn Storage>,
 }
 
 impl TableAccess for TaskResultTable {
     type StorageItem = TaskResult;
     type InsertItem = TaskResult;
 
-    fn connection(&self) -> &ThreadSafeConnection {
-        &self.inner
+    fn storage(&self) -> &dyn Storage {
+        self.inner.as_ref()
     }
     fn table_name() -> &'static str {
         "result"
     }
-    fn into_connection(self) -> ThreadSafeConnection {
-        self.inner
-    }
 }
 
 pub struct MetaTable {
-    pub(crate) inner: ThreadSafeConnection,
+    pub(crate) inner: SqliteStorage,
 }
 
 impl TableAccess for MetaTable {
     type StorageItem = Context;
     type InsertItem = Context;
 
-    fn connection(&self) -> &ThreadSafeConnection {
+    fn storage(&self) -> &dyn Storage {
         &self.inner
     }
     fn table_name() -> &'static str {
@@ -352,9 +280,6 @@ impl TableAccess for MetaTable {
     fn merge(new: &Context, existing_item: Option<Context>) -> Self::StorageItem {
         existing_item.map_or_else(|| new.to_owned(), |existing| existing.merge(new))
     }
-    fn into_connection(self) -> ThreadSafeConnection {
-        self.inner
-    }
 }
 
 impl MetaTable {
@@ -368,7 +293,8 @@ impl MetaTable {
     // NOTE: impl iterator is not allowed in traits unfortunately, but one could implement one manually
     pub fn most_recent(&self) -> Result<Option<(String, Context)>> {
         Ok(self
-            .connection()
+            .inner
+            .connection
             .lock()
             .query_row("SELECT key, data FROM meta ORDER BY key DESC limit 1", [], |r| {
                 Ok((r.get::<_, String>(0)?, r.get::<_, Vec<u8>>(1)?))
@@ -380,14 +306,14 @@ impl MetaTable {
 
 #[derive(Clone)]
 pub struct CrateTable {
-    pub(crate) inner: ThreadSafeConnection,
+    pub(crate) inner: SqliteStorage,
 }
 
 impl TableAccess for CrateTable {
     type StorageItem = Crate;
     type InsertItem = CrateVersion;
 
-    fn connection(&self) -> &ThreadSafeConnection {
+    fn storage(&self) -> &dyn Storage {
         &self.inner
     }
     fn table_name() -> &'static str {
@@ -397,27 +323,21 @@ impl TableAccess for CrateTable {
     fn merge(new_item: &CrateVersion, existing_item: Option<Crate>) -> Crate {
         existing_item.map_or_else(|| Crate::from(new_item.to_owned()), |c| c.merge(new_item))
     }
-    fn into_connection(self) -> ThreadSafeConnection {
-        self.inner
-    }
 }
 
 #[derive(Clone)]
 pub struct CrateVersionTable {
-    pub(crate) inner: ThreadSafeConnection,
+    pub(crate) inner: SqliteStorage,
 }
 
 impl TableAccess for CrateVersionTable {
     type StorageItem = CrateVersion;
     type InsertItem = CrateVersion;
 
-    fn connection(&self) -> &ThreadSafeConnection {
+    fn storage(&self) -> &dyn Storage {
         &self.inner
     }
     fn table_name() -> &'static str {
         "crate_version"
     }
-    fn into_connection(self) -> ThreadSafeConnection {
-        self.inner
-    }
 }
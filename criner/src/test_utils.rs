@@ -0,0 +1,50 @@
+//! Fixtures shared by the tests of multiple modules.
+use crate::{
+    engine::report::{generic::Generator, waste},
+    model,
+    persistence::{Db, Keyed, TableAccess},
+};
+
+/// Add `version` of `crate_name` with the given `dependencies` to `db`, along with the result of extracting a crate with
+/// the given `files` and their sizes.
+pub fn add_version(
+    db: &Db,
+    crate_name: &str,
+    version: &str,
+    files: &[(&str, u64)],
+    dependencies: Vec<model::Dependency>,
+) -> crate::Result<()> {
+    let root = prodash::tree::root::Options::default().create();
+    let mut progress = root.add_child("db");
+    let crate_version = model::CrateVersion {
+        name: crate_name.into(),
+        kind: model::ChangeKind::Added,
+        version: version.into(),
+        checksum: String::new(),
+        features: Default::default(),
+        dependencies,
+    };
+    db.open_crate_versions()?
+        .insert(&mut progress, crate_version.key(), &crate_version)?;
+    db.open_crates()?.upsert(&mut progress, crate_name, &crate_version)?;
+
+    let mut key = String::new();
+    waste::Generator::fq_result_key(crate_name, version, &mut key);
+    db.open_results()?.insert(
+        &mut progress,
+        &key,
+        &model::TaskResult::ExplodedCrate {
+            entries_meta_data: files
+                .iter()
+                .map(|(path, size)| model::TarHeader {
+                    path: format!("{}-{}/{}", crate_name, version, path).into_bytes(),
+                    size: *size,
+                    entry_type: b'0',
+                    compressed_size: None,
+                })
+                .collect(),
+            selected_entries: Vec::new(),
+        },
+    )?;
+    Ok(())
+}
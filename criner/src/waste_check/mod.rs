@@ -0,0 +1,304 @@
+//! Compute the waste report of a single crate archive or source directory, without the need for a database.
+use crate::engine::{
+    report::waste::{tar_path_to_utf8_str, validate, CargoConfig, Report, RuleBook, TarPackage},
+    work::cpubound::{explode_crate_archive, standard_bin_path, ExplodedCrate},
+};
+use crate::{Error, Result};
+use bytesize::ByteSize;
+use std::{
+    collections::BTreeSet,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// The format to print the waste report in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The report as JSON document with a versioned schema
+    Json,
+    /// A summary for use in issues and pull requests
+    Markdown,
+    /// The report as standalone HTML page
+    Html,
+    /// A human-readable summary
+    Text,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "json" => Format::Json,
+            "markdown" | "md" => Format::Markdown,
+            "html" => Format::Html,
+            "text" => Format::Text,
+            _ => {
+                return Err(format!(
+                    "Unknown format '{}', expected one of 'json', 'markdown', 'html' or 'text'",
+                    s
+                ))
+            }
+        })
+    }
+}
+
+/// The tar entry type of regular files
+const REGULAR_FILE: u8 = b'0';
+
+/// The patterns of a `.gitignore` file, which apply to the files below the `directory` containing it.
+struct IgnoreFile {
+    directory: PathBuf,
+    patterns: validate::CargoPatterns,
+}
+
+impl IgnoreFile {
+    /// Read the `.gitignore` file in `directory`, if there is one with valid patterns.
+    fn read(directory: &Path) -> Result<Option<Self>> {
+        let content = match std::fs::read_to_string(directory.join(".gitignore")) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let patterns: Vec<_> = content
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(ToOwned::to_owned)
+            .collect();
+        Ok(validate::CargoPatterns::new(&patterns).ok().map(|patterns| IgnoreFile {
+            directory: directory.to_owned(),
+            patterns,
+        }))
+    }
+
+    fn ignores(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.directory)
+            .is_ok_and(|path| self.patterns.is_match(&path.to_string_lossy().replace('\\', "/")))
+    }
+}
+
+/// Return the files cargo would put into a crate created from the sources in `directory`, sorted by path.
+///
+/// Like cargo, symbolic links are followed, and the `target` directory, `.git` directories as well as nested
+/// packages are skipped. If `directory` is part of a git repository, files ignored by `.gitignore` files are
+/// skipped, too, with the simplification that a negated pattern can't re-include files ignored by the
+/// `.gitignore` file of a parent directory.
+fn packaged_files(directory: &Path, manifest: &str) -> Result<Vec<(String, PathBuf)>> {
+    let package = CargoConfig::from(manifest).package.unwrap_or_default();
+    let compile = |patterns: Option<Vec<String>>| patterns.and_then(|p| validate::CargoPatterns::new(&p).ok());
+    let (include, exclude) = (compile(package.include), compile(package.exclude));
+
+    let root = directory.canonicalize()?;
+    let repository = root.ancestors().find(|dir| dir.join(".git").exists());
+    let mut ignore_files = Vec::new();
+    if let Some(repository) = repository {
+        for dir in root.ancestors().skip(1).take_while(|dir| dir.starts_with(repository)) {
+            ignore_files.extend(IgnoreFile::read(dir)?);
+        }
+    }
+
+    let mut files = Vec::new();
+    let mut visited_directories = BTreeSet::new();
+    visited_directories.insert(root.clone());
+    let mut directories = vec![root.clone()];
+    while let Some(current) = directories.pop() {
+        if repository.is_some() {
+            ignore_files.extend(IgnoreFile::read(&current)?);
+        }
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            let relative_path = path
+                .strip_prefix(&root)
+                .expect("entries to be in directory")
+                .to_string_lossy()
+                .replace('\\', "/");
+            let metadata = match std::fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                // a symbolic link pointing nowhere
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            if metadata.is_dir() {
+                let is_skipped = relative_path == "target"
+                    || path.file_name().is_some_and(|name| name == ".git")
+                    || path.join("Cargo.toml").is_file();
+                // Symbolic links may lead to directories we have seen already
+                if !is_skipped && visited_directories.insert(path.canonicalize()?) {
+                    directories.push(path);
+                }
+            } else if metadata.is_file()
+                && !ignore_files.iter().any(|file| file.ignores(&path))
+                && validate::is_packaged(&relative_path, include.as_ref(), exclude.as_ref())
+            {
+                files.push((relative_path, path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Read all files cargo would package from the sources in `directory`, as if they were in a crate archive.
+fn explode_directory<'a>(directory: &Path, standard_bin_path: &'a globset::GlobMatcher) -> Result<ExplodedCrate<'a>> {
+    let manifest = std::fs::read(directory.join("Cargo.toml"))?;
+    let files = packaged_files(directory, &String::from_utf8_lossy(&manifest))?;
+    // Paths in crate archives start with the directory of the crate, which is ignored when creating reports
+    let root = directory
+        .canonicalize()?
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "crate".into());
+
+    let mut exploded = ExplodedCrate::new(&manifest, standard_bin_path);
+    for (relative_path, path) in files {
+        let file = std::fs::File::open(path)?;
+        exploded.add(
+            format!("{}/{}", root, relative_path).as_bytes(),
+            file.metadata()?.len(),
+            REGULAR_FILE,
+            file,
+        )?;
+    }
+    Ok(exploded)
+}
+
+/// Return the name and version of the crate as declared in its manifest, or derived from the name of the
+/// archive or directory at `path`, like `foo-1.0.0.crate`.
+fn name_and_version(package: &TarPackage, path: &Path) -> (String, String) {
+    let manifest = package
+        .entries
+        .iter()
+        .find(|(e, _)| tar_path_to_utf8_str(&e.path) == "Cargo.toml")
+        .and_then(|(_, buf)| std::str::from_utf8(buf).ok())
+        .and_then(|manifest| manifest.parse::<toml::Value>().ok());
+    let field = |name: &str| {
+        manifest
+            .as_ref()
+            .and_then(|m| m.get("package").or_else(|| m.get("project")))
+            .and_then(|p| p.get(name))
+            .and_then(toml::Value::as_str)
+            .map(ToOwned::to_owned)
+    };
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (stem_name, stem_version) = stem
+        .match_indices('-')
+        .find(|(pos, _)| stem[pos + 1..].starts_with(|c: char| c.is_ascii_digit()))
+        .map(|(pos, _)| (stem[..pos].to_owned(), stem[pos + 1..].to_owned()))
+        .unwrap_or_else(|| (stem.clone(), "unknown".into()));
+    (
+        field("name").unwrap_or(stem_name),
+        field("version").unwrap_or(stem_version),
+    )
+}
+
+fn write_text(report: &Report, out: &mut impl Write) -> std::io::Result<()> {
+    if let Report::Version {
+        crate_name,
+        crate_version,
+        total_size_in_bytes,
+        total_files,
+        wasted_files,
+        suggested_fix,
+        files_dropped_by_fix,
+        manifest_patch,
+        compressed_waste_bytes,
+        ..
+    } = report
+    {
+        let wasted_bytes: u64 = wasted_files.iter().map(|(_, size)| size).sum();
+        writeln!(
+            out,
+            "{} {}: {} files with a total of {}, {} of which are wasted with a total of {}{}",
+            crate_name,
+            crate_version,
+            total_files,
+            ByteSize(*total_size_in_bytes),
+            wasted_files.len(),
+            ByteSize(wasted_bytes),
+            compressed_waste_bytes
+                .map(|bytes| format!(" ({} compressed)", ByteSize(bytes)))
+                .unwrap_or_default()
+        )?;
+        for file in report.explain().map(|e| e.wasted_files).unwrap_or_default() {
+            write!(out, "  {} ({})", file.path, ByteSize(file.size))?;
+            match file.reason {
+                Some(reason) => writeln!(out, ": {}", reason)?,
+                None => writeln!(out)?,
+            }
+        }
+        if !files_dropped_by_fix.is_empty() {
+            writeln!(out, "The suggested fix would drop files needed to build the crate:")?;
+            for path in files_dropped_by_fix {
+                writeln!(out, "  {}", path)?;
+            }
+        }
+        match (manifest_patch, suggested_fix) {
+            (Some(patch), _) => write!(out, "Apply the suggested fix with 'git apply':\n{}", patch)?,
+            (None, Some(fix)) => writeln!(out, "Suggested fix: {:?}", fix)?,
+            (None, None) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Compute the waste report of the crate archive or unpacked source directory at `path` using `rules`,
+/// and write it in the given `format` to `out`.
+///
+/// Directories are treated like the crate `cargo package` would create from them.
+/// If `deny_waste` is true, an error is returned after writing the report if there are wasted files,
+/// which is useful to prevent publishing crates with waste.
+pub fn run_blocking(
+    path: impl AsRef<Path>,
+    format: Format,
+    rules: &RuleBook,
+    deny_waste: bool,
+    mut out: impl Write,
+) -> Result<()> {
+    use horrorshow::Template;
+
+    let path = path.as_ref();
+    let standard_bin_path = standard_bin_path();
+    let ExplodedCrate {
+        entries_meta_data,
+        selected_entries,
+        ..
+    } = if path.is_dir() {
+        explode_directory(path, &standard_bin_path)?
+    } else {
+        explode_crate_archive(path, &standard_bin_path, |_| {})?
+    };
+    let package = TarPackage {
+        entries_meta_data,
+        entries: selected_entries,
+    };
+    let (crate_name, crate_version) = name_and_version(&package, path);
+    let report = Report::from_package_with_rules(&crate_name, &crate_version, package, rules.rules_for(&crate_name));
+
+    match format {
+        Format::Json => {
+            report.write_json(&mut out).map_err(std::io::Error::from)?;
+            writeln!(out)?;
+        }
+        Format::Markdown => out.write_all(report.to_markdown().as_bytes())?,
+        Format::Html => report.clone().write_to_io(&mut out)?,
+        Format::Text => write_text(&report, &mut out)?,
+    }
+    out.flush()?;
+
+    match report {
+        Report::Version { wasted_files, .. } if deny_waste && !wasted_files.is_empty() => Err(Error::Message(format!(
+            "{} {} has {} wasted files",
+            crate_name,
+            crate_version,
+            wasted_files.len()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod waste_check_test;
@@ -0,0 +1,61 @@
+use super::{text_report, MANIFEST};
+use crate::engine::report::waste::TarPackage;
+use crate::waste_check::name_and_version;
+use std::path::Path;
+
+/// Write a crate archive to `path` with `files` below a directory named `root`, like `cargo package` does.
+fn write_crate(path: &Path, root: &str, files: &[(&str, &str)]) -> crate::Result<()> {
+    let mut archive = tar::Builder::new(libflate::gzip::Encoder::new(std::fs::File::create(path)?)?);
+    for (file_path, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive.append_data(&mut header, format!("{}/{}", root, file_path), content.as_bytes())?;
+    }
+    archive.into_inner()?.finish().into_result()?;
+    Ok(())
+}
+
+#[test]
+fn wasted_files_of_crate_archives_are_written_and_denied() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("renamed-0.1.0.crate");
+    write_crate(
+        &path,
+        "a-1.0.0",
+        &[
+            ("Cargo.toml", MANIFEST),
+            ("src/lib.rs", ""),
+            ("tests/integration.rs", "fn main() {}"),
+        ],
+    )?;
+    let (text, result) = text_report(&path);
+    assert!(
+        text.starts_with("a 1.0.0: 3 files with a total of "),
+        "name and version are taken from the manifest: {}",
+        text
+    );
+    assert!(text.contains("\n  tests/integration.rs (12 B)"), "{}", text);
+    assert_eq!(
+        result.map_err(|err| err.to_string()),
+        Err("a 1.0.0 has 1 wasted files".to_owned())
+    );
+    Ok(())
+}
+
+#[test]
+fn name_and_version_are_derived_from_the_path_without_manifest() {
+    let package = TarPackage {
+        entries_meta_data: Vec::new(),
+        entries: Vec::new(),
+    };
+    assert_eq!(
+        name_and_version(&package, Path::new("some/foo-bar-1.0.0-alpha.1.crate")),
+        ("foo-bar".into(), "1.0.0-alpha.1".into())
+    );
+    assert_eq!(
+        name_and_version(&package, Path::new("checkout/foo-bar")),
+        ("foo-bar".into(), "unknown".into())
+    );
+}
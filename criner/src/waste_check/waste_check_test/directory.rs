@@ -0,0 +1,110 @@
+use super::{packaged_files, text_report, write_files, MANIFEST};
+
+fn packaged_paths(directory: &std::path::Path) -> crate::Result<Vec<String>> {
+    Ok(packaged_files(directory, MANIFEST)?
+        .into_iter()
+        .map(|(relative_path, _)| relative_path)
+        .collect())
+}
+
+#[test]
+fn nested_packages_build_artifacts_and_git_directories_are_skipped() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    write_files(
+        dir.path(),
+        &[
+            ("Cargo.toml", MANIFEST),
+            ("src/lib.rs", ""),
+            ("target/debug/a", ""),
+            (".git/HEAD", ""),
+            (
+                "nested/Cargo.toml",
+                "[package]\nname = \"nested\"\nversion = \"1.0.0\"\n",
+            ),
+            ("nested/src/lib.rs", ""),
+            ("examples/demo/main.rs", ""),
+        ],
+    )?;
+    assert_eq!(
+        packaged_paths(dir.path())?,
+        vec!["Cargo.toml", "examples/demo/main.rs", "src/lib.rs"]
+    );
+    Ok(())
+}
+
+#[test]
+fn gitignore_files_are_honored_only_within_git_repositories() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let crate_dir = dir.path().join("a");
+    write_files(dir.path(), &[(".gitignore", "*.log\n")])?;
+    write_files(
+        &crate_dir,
+        &[
+            ("Cargo.toml", MANIFEST),
+            ("src/lib.rs", ""),
+            ("src/.gitignore", "# generated\n/generated/\n!keep.log\n"),
+            ("src/generated/mod.rs", ""),
+            ("run.log", ""),
+        ],
+    )?;
+    assert_eq!(
+        packaged_paths(&crate_dir)?,
+        vec![
+            "Cargo.toml",
+            "run.log",
+            "src/.gitignore",
+            "src/generated/mod.rs",
+            "src/lib.rs"
+        ],
+        "outside of a repository, .gitignore files are just files"
+    );
+
+    std::fs::create_dir(dir.path().join(".git"))?;
+    assert_eq!(
+        packaged_paths(&crate_dir)?,
+        vec!["Cargo.toml", "src/.gitignore", "src/lib.rs"],
+        "the .gitignore files of the crate and the repository apply"
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn symbolic_links_are_followed_once() -> crate::Result<()> {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempfile::tempdir()?;
+    write_files(dir.path(), &[("Cargo.toml", MANIFEST), ("src/lib.rs", "")])?;
+    symlink(dir.path().join("src/lib.rs"), dir.path().join("lib.rs"))?;
+    symlink(dir.path(), dir.path().join("src/cycle"))?;
+    symlink(dir.path().join("missing"), dir.path().join("dangling"))?;
+    assert_eq!(packaged_paths(dir.path())?, vec!["Cargo.toml", "lib.rs", "src/lib.rs"]);
+    Ok(())
+}
+
+#[test]
+fn wasted_files_of_directories_are_written_and_denied() -> crate::Result<()> {
+    let dir = tempfile::tempdir()?;
+    write_files(dir.path(), &[("Cargo.toml", MANIFEST), ("src/lib.rs", "")])?;
+    let (text, result) = text_report(dir.path());
+    assert!(result.is_ok(), "crates without waste are accepted");
+    assert!(
+        text.starts_with("a 1.0.0: 2 files with a total of "),
+        "name and version are taken from the manifest: {}",
+        text
+    );
+
+    write_files(dir.path(), &[("tests/integration.rs", "fn main() {}")])?;
+    let (text, result) = text_report(dir.path());
+    assert!(
+        text.contains(", 1 of which are wasted with a total of 12 B"),
+        "the report is written even if waste is denied: {}",
+        text
+    );
+    assert!(text.contains("\n  tests/integration.rs (12 B)"), "{}", text);
+    assert_eq!(
+        result.map_err(|err| err.to_string()),
+        Err("a 1.0.0 has 1 wasted files".to_owned())
+    );
+    Ok(())
+}
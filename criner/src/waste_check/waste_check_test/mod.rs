@@ -0,0 +1,25 @@
+use super::{packaged_files, run_blocking, Format};
+use crate::engine::report::waste::RuleBook;
+use std::path::Path;
+
+mod archive;
+mod directory;
+
+const MANIFEST: &str = "[package]\nname = \"a\"\nversion = \"1.0.0\"\n";
+
+/// Write `files` with their content into `directory`, creating parent directories as needed.
+fn write_files(directory: &Path, files: &[(&str, &str)]) -> crate::Result<()> {
+    for (path, content) in files {
+        let path = directory.join(path);
+        std::fs::create_dir_all(path.parent().expect("parent directory"))?;
+        std::fs::write(path, content)?;
+    }
+    Ok(())
+}
+
+/// Return the report of the crate at `path` in text format, along with the result of denying waste.
+fn text_report(path: &Path) -> (String, crate::Result<()>) {
+    let mut out = Vec::new();
+    let result = run_blocking(path, Format::Text, &RuleBook::default(), true, &mut out);
+    (String::from_utf8(out).expect("utf8"), result)
+}
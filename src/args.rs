@@ -15,6 +15,7 @@ pub struct Args {
 }
 
 #[derive(Debug, clap::Parser)]
+#[allow(clippy::large_enum_variant)] // parsed only once
 pub enum SubCommands {
     /// Mine crates.io in an incorruptible and resumable fashion
     #[clap(display_order = 0)]
@@ -112,6 +113,90 @@ pub enum SubCommands {
         #[clap(long, short = 'g')]
         glob: Option<String>,
 
+        /// Path to a TOML file with rules to classify files in crate packages as waste.
+        ///
+        /// If unset, the builtin rules are used.
+        /// Changing the rules generates all reports anew, starting from an empty incremental cache.
+        #[clap(long, value_name = "RULES")]
+        waste_rules: Option<PathBuf>,
+
+        /// The formats to write each version report in next to its html page, any of 'json' or 'markdown'.
+        ///
+        /// Multiple formats are separated by commas, like 'json,markdown'.
+        #[clap(long, value_name = "FORMATS", value_delimiter = ',', default_value = "json")]
+        version_formats: Vec<criner::run::VersionFormat>,
+
+        /// The percentage of wasted bytes in the latest version of a crate at or below which its badge says 'lean'.
+        ///
+        /// It must not be above the percentage of wasteful crates.
+        #[clap(long, value_name = "PERCENT", default_value = "1")]
+        badge_lean_at_most: f64,
+
+        /// The percentage of wasted bytes in the latest version of a crate above which its badge is shown in red.
+        #[clap(long, value_name = "PERCENT", default_value = "25")]
+        badge_wasteful_above: f64,
+
+        /// The name of the remote to push reports to if they are written into a git repository.
+        ///
+        /// If unset, the upstream remote of the current branch is used, or 'origin'.
+        #[clap(long, value_name = "REMOTE")]
+        publish_remote: Option<String>,
+
+        /// The branch on the remote to push reports to.
+        #[clap(long, value_name = "BRANCH", default_value = "main")]
+        publish_branch: String,
+
+        /// Path to the SSH private key to authenticate with when pushing reports.
+        ///
+        /// Its passphrase is read from the CRINER_REPORT_PUSH_SSH_PASSPHRASE environment variable, if set.
+        /// If neither this nor --publish-ssh-agent are set, the username and password to use with HTTP are read
+        /// from CRINER_REPORT_PUSH_HTTP_USERNAME and CRINER_REPORT_PUSH_HTTP_PASSWORD.
+        #[clap(long, value_name = "PATH", conflicts_with = "publish_ssh_agent")]
+        publish_ssh_key: Option<PathBuf>,
+
+        /// If set, authenticate with the keys of the running SSH agent when pushing reports.
+        #[clap(long)]
+        publish_ssh_agent: bool,
+
+        /// The name of the author of commits with reports.
+        #[clap(long, value_name = "NAME", default_value = "Criner")]
+        publish_author_name: String,
+
+        /// The email of the author of commits with reports.
+        #[clap(
+            long,
+            value_name = "EMAIL",
+            default_value = "https://github.com/the-lean-crate/criner"
+        )]
+        publish_author_email: String,
+
+        /// The message of commits with reports.
+        ///
+        /// '{files}' is replaced with the amount of files changed in the reporting run, '{total_files}' with the
+        /// amount of all files in the repository and '{time}' with the time of the commit.
+        #[clap(long, value_name = "TEMPLATE", default_value = "update {files} reports")]
+        publish_message: String,
+
+        /// If set, reports are committed to their git repository but not pushed.
+        #[clap(long)]
+        no_push: bool,
+
+        /// If set, reports are published into this directory to be served as static site, instead of being committed
+        /// to git.
+        ///
+        /// Only files whose content changed are written, pages of crates which aren't reported anymore are deleted,
+        /// and all affected files are listed in '.criner-manifest.json' within the directory. A new site starts out with
+        /// all reports published before.
+        /// Ignored if --glob is set.
+        #[clap(long, value_name = "PATH")]
+        site_dir: Option<PathBuf>,
+
+        /// The storage to use for tasks and results, either 'sqlite' or 'sled'.
+        ///
+        /// If unset, existing databases will use the storage they were created with, and new ones will use sqlite.
+        #[clap(long)]
+        storage: Option<criner::StorageKind>,
+
         /// Path to the possibly existing database. It's used to persist all mining results.
         #[clap(default_value = "criner.db")]
         db_path: PathBuf,
@@ -129,15 +214,206 @@ pub enum SubCommands {
         /// The path to the source database in sqlite format
         input_db_path: PathBuf,
 
-        /// Path to which to write the exported data. If it exists the operation will fail.
+        /// The format to export into, one of 'sqlite', 'csv', 'jsonl' or 'parquet'.
+        ///
+        /// All formats but 'sqlite' write a file per table and view into the directory at the export path.
+        /// 'parquet' requires the 'parquet' feature.
+        #[clap(long, short = 'f', default_value = "sqlite")]
+        format: criner::export::Format,
+
+        /// If set, and the export database exists, only add or update what changed since the last export.
+        #[clap(long, short = 'i')]
+        incremental: bool,
+
+        /// Path to a TOML file with rules to classify files in crate packages as waste.
+        ///
+        /// If unset, the builtin rules are used.
+        /// The waste of crates exported previously isn't recomputed in incremental exports when the rules change.
+        #[clap(long, value_name = "RULES")]
+        waste_rules: Option<PathBuf>,
+
+        /// Path to which to write the exported data. If it exists the operation will fail unless --incremental is set
+        /// for sqlite exports.
         export_db_path: PathBuf,
     },
+    /// Manage the downloaded crate archives in the assets directory of the database
+    #[clap(display_order = 2)]
+    #[clap(disable_version_flag(true))]
+    Assets {
+        #[clap(subcommand)]
+        cmd: AssetsCommands,
+    },
+    /// Maintain the database used for mining
+    #[clap(display_order = 3)]
+    #[clap(disable_version_flag(true))]
+    Db {
+        #[clap(subcommand)]
+        cmd: DbCommands,
+    },
+    /// Print the waste report of a crate archive or an unpacked source directory, without the need for a database.
+    ///
+    /// Use it before publishing a crate, for example with the '.crate' file created by 'cargo package'.
+    #[clap(display_order = 4)]
+    #[clap(disable_version_flag(true))]
+    WasteCheck {
+        /// The format to print the report in, one of 'text', 'json', 'markdown' or 'html'.
+        #[clap(long, short = 'f', default_value = "text")]
+        format: criner::waste_check::Format,
+
+        /// Path to a TOML file with rules to classify files in crate packages as waste.
+        ///
+        /// If unset, the builtin rules are used.
+        #[clap(long, value_name = "RULES")]
+        waste_rules: Option<PathBuf>,
+
+        /// If set, exit with an error if there are wasted files, after printing the report.
+        #[clap(long)]
+        deny_waste: bool,
+
+        /// Path to a '.crate' file, or to a directory containing a 'Cargo.toml' file.
+        path: PathBuf,
+    },
+    /// Generate the reports of a single crate from the results in the database, without running 'mine'.
+    ///
+    /// Versions which weren't reported before are added to the crate and top-level reports, while all other
+    /// versions have their pages regenerated, for example after a change to the report templates.
+    #[clap(display_order = 5)]
+    #[clap(disable_version_flag(true))]
+    Report {
+        /// The version of the crate to report on. If unset, all versions but the recently yanked ones are reported.
+        #[clap(long, value_name = "VERSION")]
+        version: Option<String>,
+
+        /// The directory to write the reports into, leaving the reports kept in the database untouched.
+        ///
+        /// If unset, they are published from the reports directory of the database like the ones of 'mine', and
+        /// versions which weren't reported before are added to the crate and top-level reports kept there.
+        #[clap(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+
+        /// Path to a TOML file with rules to classify files in crate packages as waste.
+        ///
+        /// If unset, the builtin rules are used.
+        #[clap(long, value_name = "RULES")]
+        waste_rules: Option<PathBuf>,
+
+        /// The formats to write each version report in next to its html page, any of 'json' or 'markdown'.
+        ///
+        /// Multiple formats are separated by commas, like 'json,markdown'.
+        #[clap(long, value_name = "FORMATS", value_delimiter = ',', default_value = "json")]
+        version_formats: Vec<criner::run::VersionFormat>,
+
+        /// The percentage of wasted bytes in the latest version of a crate at or below which its badge says 'lean'.
+        ///
+        /// It must not be above the percentage of wasteful crates.
+        #[clap(long, value_name = "PERCENT", default_value = "1")]
+        badge_lean_at_most: f64,
+
+        /// The percentage of wasted bytes in the latest version of a crate above which its badge is shown in red.
+        #[clap(long, value_name = "PERCENT", default_value = "25")]
+        badge_wasteful_above: f64,
+
+        /// The name of the remote to push reports to if they are written into a git repository.
+        ///
+        /// If unset, the upstream remote of the current branch is used, or 'origin'.
+        #[clap(long, value_name = "REMOTE")]
+        publish_remote: Option<String>,
+
+        /// The branch on the remote to push reports to.
+        #[clap(long, value_name = "BRANCH", default_value = "main")]
+        publish_branch: String,
+
+        /// Path to the SSH private key to authenticate with when pushing reports.
+        ///
+        /// Its passphrase is read from the CRINER_REPORT_PUSH_SSH_PASSPHRASE environment variable, if set.
+        /// If neither this nor --publish-ssh-agent are set, the username and password to use with HTTP are read
+        /// from CRINER_REPORT_PUSH_HTTP_USERNAME and CRINER_REPORT_PUSH_HTTP_PASSWORD.
+        #[clap(long, value_name = "PATH", conflicts_with = "publish_ssh_agent")]
+        publish_ssh_key: Option<PathBuf>,
+
+        /// If set, authenticate with the keys of the running SSH agent when pushing reports.
+        #[clap(long)]
+        publish_ssh_agent: bool,
+
+        /// The name of the author of commits with reports.
+        #[clap(long, value_name = "NAME", default_value = "Criner")]
+        publish_author_name: String,
+
+        /// The email of the author of commits with reports.
+        #[clap(
+            long,
+            value_name = "EMAIL",
+            default_value = "https://github.com/the-lean-crate/criner"
+        )]
+        publish_author_email: String,
+
+        /// The message of commits with reports.
+        ///
+        /// '{files}' is replaced with the amount of files changed, '{total_files}' with the amount of all files in
+        /// the repository and '{time}' with the time of the commit.
+        #[clap(long, value_name = "TEMPLATE", default_value = "update {files} reports")]
+        publish_message: String,
+
+        /// If set, reports are committed to their git repository but not pushed.
+        #[clap(long)]
+        no_push: bool,
+
+        /// If set, reports are published into this directory to be served as static site, instead of being committed
+        /// to git.
+        ///
+        /// Only files whose content changed are written, and the pages of other crates are kept.
+        #[clap(long, value_name = "PATH", conflicts_with = "out")]
+        site_dir: Option<PathBuf>,
+
+        /// The name of the crate to report on
+        crate_name: String,
+
+        /// Path to the existing database
+        #[clap(default_value = "criner.db")]
+        db_path: PathBuf,
+    },
     #[cfg(feature = "migration")]
     /// A special purpose command only to be executed in special circumstances
     #[clap(display_order = 9)]
     Migrate,
 }
 
+#[derive(Debug, clap::Parser)]
+pub enum AssetsCommands {
+    /// Delete crate archives which were already extracted to free up disk space.
+    ///
+    /// Archives will be downloaded again on demand should they be needed for another extraction.
+    #[clap(disable_version_flag(true))]
+    Gc {
+        /// If set, only print which archives would be deleted.
+        #[clap(long, short = 'n')]
+        dry_run: bool,
+
+        /// The amount of most recent versions per crate to keep the archives of, or 'all' or 'none'.
+        #[clap(long, short = 'k', default_value = "1")]
+        keep: criner::assets::Retention,
+
+        /// Path to the existing database
+        #[clap(default_value = "criner.db")]
+        db_path: PathBuf,
+    },
+}
+
+#[derive(Debug, clap::Parser)]
+pub enum DbCommands {
+    /// Move tasks and results into the given storage, which will be used from there on.
+    #[clap(disable_version_flag(true))]
+    Convert {
+        /// The storage to convert to, either 'sqlite' or 'sled'
+        #[clap(long)]
+        to: criner::StorageKind,
+
+        /// Path to the existing database
+        #[clap(default_value = "criner.db")]
+        db_path: PathBuf,
+    },
+}
+
 impl Default for SubCommands {
     fn default() -> Self {
         SubCommands::Mine {
@@ -159,8 +435,22 @@ impl Default for SubCommands {
             ),
             report_every: std::time::Duration::from_secs(60).into(),
             report_at_most: None,
+            storage: None,
             db_path: PathBuf::from("criner.db"),
             glob: None,
+            waste_rules: None,
+            version_formats: vec![criner::run::VersionFormat::Json],
+            badge_lean_at_most: 1.0,
+            badge_wasteful_above: 25.0,
+            publish_remote: None,
+            publish_branch: "main".into(),
+            publish_ssh_key: None,
+            publish_ssh_agent: false,
+            publish_author_name: "Criner".into(),
+            publish_author_email: "https://github.com/the-lean-crate/criner".into(),
+            publish_message: "update {files} reports".into(),
+            no_push: false,
+            site_dir: None,
         }
     }
 }
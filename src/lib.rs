@@ -1,3 +1,4 @@
+use bytesize::ByteSize;
 use std::ops::Add;
 
 mod args;
@@ -13,9 +14,124 @@ pub fn run_blocking(args: Args) -> criner::error::Result<()> {
         Export {
             input_db_path,
             export_db_path,
-        } => criner::export::run_blocking(input_db_path, export_db_path),
+            format,
+            incremental,
+            waste_rules,
+        } => criner::export::run_blocking(
+            input_db_path,
+            export_db_path,
+            format,
+            incremental,
+            &match waste_rules {
+                Some(path) => criner::run::RuleBook::from_path(path)?,
+                None => Default::default(),
+            },
+        ),
+        Assets {
+            cmd: AssetsCommands::Gc { dry_run, keep, db_path },
+        } => {
+            let outcome = criner::assets::gc_blocking(db_path, keep, dry_run)?;
+            for (path, size) in &outcome.removed_archives {
+                println!(
+                    "{} {} ({})",
+                    if dry_run { "would remove" } else { "removed" },
+                    path.display(),
+                    ByteSize(*size)
+                );
+            }
+            println!(
+                "{} {} archives with a total of {}, kept {} archives which are not yet extracted",
+                if dry_run { "Would remove" } else { "Removed" },
+                outcome.removed_archives.len(),
+                ByteSize(outcome.removed_bytes()),
+                outcome.archives_pending_extraction
+            );
+            Ok(())
+        }
+        Db {
+            cmd: DbCommands::Convert { to, db_path },
+        } => {
+            let count = criner::convert_storage_blocking(db_path, to)?;
+            println!("Moved {} tasks and results into {:?} storage", count, to);
+            Ok(())
+        }
+        WasteCheck {
+            format,
+            waste_rules,
+            deny_waste,
+            path,
+        } => criner::waste_check::run_blocking(
+            path,
+            format,
+            &match waste_rules {
+                Some(path) => criner::run::RuleBook::from_path(path)?,
+                None => Default::default(),
+            },
+            deny_waste,
+            std::io::stdout().lock(),
+        ),
+        Report {
+            version,
+            out,
+            waste_rules,
+            version_formats,
+            badge_lean_at_most,
+            badge_wasteful_above,
+            publish_remote,
+            publish_branch,
+            publish_ssh_key,
+            publish_ssh_agent,
+            publish_author_name,
+            publish_author_email,
+            publish_message,
+            no_push,
+            site_dir,
+            crate_name,
+            db_path,
+        } => {
+            let outcome = criner::crate_report::run_blocking(
+                db_path,
+                &crate_name,
+                version.as_deref(),
+                out,
+                criner::run::WasteReportOptions {
+                    rules: match waste_rules {
+                        Some(path) => criner::run::RuleBook::from_path(path)?,
+                        None => Default::default(),
+                    },
+                    formats: version_formats,
+                    badge_thresholds: criner::run::BadgeThresholds::new(badge_lean_at_most, badge_wasteful_above)
+                        .map_err(criner::Error::Message)?,
+                },
+                criner::run::PublishOptions {
+                    site_dir,
+                    remote: publish_remote,
+                    branch: publish_branch,
+                    auth: match (publish_ssh_key, publish_ssh_agent) {
+                        (Some(path), _) => criner::run::PushAuth::SshKey(path),
+                        (None, true) => criner::run::PushAuth::SshAgent,
+                        (None, false) => criner::run::PushAuth::HttpUserPass,
+                    },
+                    author_name: publish_author_name,
+                    author_email: publish_author_email,
+                    message: publish_message,
+                    push: !no_push,
+                },
+            )?;
+            for (label, versions) in [
+                ("new", &outcome.new_versions),
+                ("regenerated", &outcome.regenerated_versions),
+                ("without result", &outcome.versions_without_result),
+            ] {
+                if !versions.is_empty() {
+                    println!("{} versions of {}: {}", label, crate_name, versions.join(", "));
+                }
+            }
+            Ok(())
+        }
         Mine {
             repository,
+            storage,
             db_path,
             fps,
             time_limit,
@@ -33,8 +149,22 @@ pub fn run_blocking(args: Args) -> criner::error::Result<()> {
             report_every,
             report_at_most,
             glob,
+            waste_rules,
+            version_formats,
+            badge_lean_at_most,
+            badge_wasteful_above,
+            publish_remote,
+            publish_branch,
+            publish_ssh_key,
+            publish_ssh_agent,
+            publish_author_name,
+            publish_author_email,
+            publish_message,
+            no_push,
+            site_dir,
         } => criner::run::blocking(
             db_path,
+            storage,
             repository.unwrap_or_else(|| std::env::temp_dir().join("criner-crates-io-bare-index.git")),
             time_limit.map(|d| std::time::SystemTime::now().add(*d)),
             io_bound_processors,
@@ -56,6 +186,29 @@ pub fn run_blocking(args: Args) -> criner::error::Result<()> {
                 },
                 glob,
             },
+            criner::run::WasteReportOptions {
+                rules: match waste_rules {
+                    Some(path) => criner::run::RuleBook::from_path(path)?,
+                    None => Default::default(),
+                },
+                formats: version_formats,
+                badge_thresholds: criner::run::BadgeThresholds::new(badge_lean_at_most, badge_wasteful_above)
+                    .map_err(criner::Error::Message)?,
+            },
+            criner::run::PublishOptions {
+                site_dir,
+                remote: publish_remote,
+                branch: publish_branch,
+                auth: match (publish_ssh_key, publish_ssh_agent) {
+                    (Some(path), _) => criner::run::PushAuth::SshKey(path),
+                    (None, true) => criner::run::PushAuth::SshAgent,
+                    (None, false) => criner::run::PushAuth::HttpUserPass,
+                },
+                author_name: publish_author_name,
+                author_email: publish_author_email,
+                message: publish_message,
+                push: !no_push,
+            },
             download_crates_io_database_every_24_hours_starting_at,
             criner::prodash::tree::root::Options {
                 message_buffer_capacity: progress_message_scrollback_buffer_size,